@@ -8,16 +8,20 @@ use std::fmt;
 mod export;
 mod expr;
 mod import;
+mod include;
 mod r#let;
 mod printer;
 mod r#type;
+pub mod visit;
 
 pub use export::*;
 pub use expr::*;
 pub use import::*;
+pub use include::*;
 pub use printer::*;
 pub use r#let::*;
 pub use r#type::*;
+pub use visit::{VisitMut, Visitor};
 
 struct Found(Option<Token>);
 
@@ -60,10 +64,10 @@ impl fmt::Display for Expected<'_> {
 
 /// Represents a parse error.
 #[derive(thiserror::Error, Diagnostic, Debug)]
-#[diagnostic(code("failed to parse document"))]
 pub enum Error {
     /// A lexer error occurred.
     #[error("{error}")]
+    #[diagnostic(code(wac::parse::lexer))]
     Lexer {
         /// The lexer error that occurred.
         error: crate::lexer::Error,
@@ -73,6 +77,7 @@ pub enum Error {
     },
     /// An unexpected token was encountered when a single token was expected.
     #[error("expected {expected}, found {found}", found = Found(*.found))]
+    #[diagnostic(code(wac::parse::expected))]
     Expected {
         /// The expected token.
         expected: Token,
@@ -84,6 +89,7 @@ pub enum Error {
     },
     /// An unexpected token was encountered when either one of two tokens was expected.
     #[error("expected {first} or {second}, found {found}", found = Found(*.found))]
+    #[diagnostic(code(wac::parse::expected_either))]
     ExpectedEither {
         /// The first expected token.
         first: Token,
@@ -97,6 +103,7 @@ pub enum Error {
     },
     /// An unexpected token was encountered when multiple tokens were expected.
     #[error("expected either {expected}, found {found}", expected = Expected { expected, count: *.count }, found = Found(*.found))]
+    #[diagnostic(code(wac::parse::expected_multiple))]
     ExpectedMultiple {
         /// The tokens that were expected.
         expected: [Option<Token>; 10],
@@ -110,6 +117,7 @@ pub enum Error {
     },
     /// An empty type was encountered.
     #[error("{ty} must contain at least one {kind}")]
+    #[diagnostic(code(wac::parse::empty_type))]
     EmptyType {
         /// The type that was empty (e.g. "record", "variant", etc.)
         ty: &'static str,
@@ -119,8 +127,17 @@ pub enum Error {
         #[label(primary, "empty {ty}")]
         span: SourceSpan,
     },
+    /// A destructuring let statement did not bind any names.
+    #[error("a destructuring let statement must bind at least one name")]
+    #[diagnostic(code(wac::parse::empty_destructure))]
+    EmptyDestructure {
+        /// The span of the empty braces.
+        #[label(primary, "no names are bound")]
+        span: SourceSpan,
+    },
     /// An invalid semantic version was encountered.
     #[error("`{version}` is not a valid semantic version")]
+    #[diagnostic(code(wac::parse::invalid_version))]
     InvalidVersion {
         /// The invalid version.
         version: std::string::String,
@@ -128,6 +145,41 @@ pub enum Error {
         #[label(primary, "invalid version")]
         span: SourceSpan,
     },
+    /// An invalid version requirement was encountered.
+    #[error("`{requirement}` is not a valid version requirement")]
+    #[diagnostic(code(wac::parse::invalid_version_requirement))]
+    InvalidVersionRequirement {
+        /// The invalid version requirement.
+        requirement: std::string::String,
+        /// The span of the version requirement.
+        #[label(primary, "invalid version requirement")]
+        span: SourceSpan,
+    },
+    /// A version requirement was used where an exact version is required.
+    #[error("a version requirement cannot be used for the package being defined")]
+    #[diagnostic(code(wac::parse::unexpected_version_requirement))]
+    UnexpectedVersionRequirement {
+        /// The span of the version requirement.
+        #[label(primary, "an exact version is required")]
+        span: SourceSpan,
+    },
+}
+
+impl Error {
+    /// Gets the span of the error.
+    pub fn span(&self) -> SourceSpan {
+        match self {
+            Self::Lexer { span, .. }
+            | Self::Expected { span, .. }
+            | Self::ExpectedEither { span, .. }
+            | Self::ExpectedMultiple { span, .. }
+            | Self::EmptyType { span, .. }
+            | Self::EmptyDestructure { span }
+            | Self::InvalidVersion { span, .. }
+            | Self::InvalidVersionRequirement { span, .. }
+            | Self::UnexpectedVersionRequirement { span } => *span,
+        }
+    }
 }
 
 /// Represents a parse result.
@@ -306,7 +358,27 @@ pub struct PackageDirective<'a> {
 impl<'a> Parse<'a> for PackageDirective<'a> {
     fn parse(lexer: &mut Lexer<'a>) -> ParseResult<Self> {
         parse_token(lexer, Token::PackageKeyword)?;
-        let package = Parse::parse(lexer)?;
+        let package: PackageName = Parse::parse(lexer)?;
+        if package.requirement.is_some() {
+            let start = package.span.offset() + package.name.len() + 1;
+            let span = SourceSpan::new(
+                start.into(),
+                (package.span.offset() + package.span.len() - start).into(),
+            );
+
+            // A partial version is a requirement for references, but is
+            // reported as an invalid version for the package being defined
+            let version = &package.string[package.name.len() + 1..];
+            if version.starts_with(|c: char| c.is_ascii_digit()) && !version.contains(',') {
+                return Err(Error::InvalidVersion {
+                    version: version.to_owned(),
+                    span,
+                });
+            }
+
+            return Err(Error::UnexpectedVersionRequirement { span });
+        }
+
         let targets = parse_optional(lexer, Token::TargetsKeyword, Parse::parse)?;
         parse_token(lexer, Token::Semicolon)?;
         Ok(Self { package, targets })
@@ -328,36 +400,176 @@ pub struct Document<'a> {
 impl<'a> Document<'a> {
     /// Parses the given source string as a document.
     ///
-    /// The given path is used for error reporting.
+    /// Parsing stops at the first error; see [`Document::parse_recovering`]
+    /// to report every error in the source.
     pub fn parse(source: &'a str) -> ParseResult<Self> {
-        let mut lexer = Lexer::new(source).map_err(Error::from)?;
+        let (document, errors) = Self::parse_recovering(source);
+        match errors.into_iter().next() {
+            Some(e) => Err(e),
+            None => Ok(document.expect("document should be present without errors")),
+        }
+    }
+
+    /// Parses the given source string as a document, recovering from errors.
+    ///
+    /// When a statement fails to parse, the parser skips ahead to the next
+    /// statement boundary (a `;` or closing `}`, or a keyword that starts a
+    /// statement, such as `let`, `import`, or `export`) and continues parsing.
+    ///
+    /// Returns the partial document containing the statements that parsed
+    /// successfully along with every error encountered. The document is
+    /// `None` if the package directive could not be parsed.
+    pub fn parse_recovering(source: &'a str) -> (Option<Self>, Vec<Error>) {
+        Self::parse_recovering_with_offset(source, 0)
+    }
+
+    /// Parses the given source string as a document, recovering from errors.
+    ///
+    /// The spans of the document are offset by the given amount; this allows
+    /// the spans of multiple documents to be distinguished from one another.
+    ///
+    /// See [`Document::parse_recovering`] for more information.
+    pub fn parse_recovering_with_offset(
+        source: &'a str,
+        offset: usize,
+    ) -> (Option<Self>, Vec<Error>) {
+        match Lexer::new_with_offset(source, offset) {
+            Ok(lexer) => Self::parse_lexer(lexer),
+            Err(e) => (None, vec![e.into()]),
+        }
+    }
+
+    /// Parses a document from the given lexer, recovering from errors.
+    pub(crate) fn parse_lexer(mut lexer: Lexer<'a>) -> (Option<Self>, Vec<Error>) {
+        let mut errors = Vec::new();
+        let docs = match Parse::parse(&mut lexer) {
+            Ok(docs) => docs,
+            Err(e) => return (None, vec![e]),
+        };
 
-        let docs = Parse::parse(&mut lexer)?;
-        let directive = Parse::parse(&mut lexer)?;
+        let start = lexer.clone();
+        let directive = match PackageDirective::parse(&mut lexer) {
+            Ok(directive) => Some(directive),
+            Err(e) => {
+                recover(start, &mut lexer, &e);
+                errors.push(e);
+                None
+            }
+        };
 
         let mut statements: Vec<Statement> = Default::default();
-        while lexer.peek().is_some() {
-            statements.push(Parse::parse(&mut lexer)?);
+        while let Some((_, span)) = lexer.peek() {
+            let start = lexer.clone();
+            match Parse::parse(&mut lexer) {
+                Ok(statement) => statements.push(statement),
+                Err(e) => {
+                    recover(start, &mut lexer, &e);
+                    errors.push(e);
+
+                    // Ensure progress is made if the statement could not be started
+                    if lexer.peek().map(|(_, s)| s.offset()) == Some(span.offset()) {
+                        lexer.next();
+                    }
+                }
+            }
         }
 
         assert!(lexer.next().is_none(), "expected all tokens to be consumed");
-        Ok(Self {
-            docs,
-            directive,
-            statements,
-        })
+        (
+            directive.map(|directive| Self {
+                docs,
+                directive,
+                statements,
+            }),
+            errors,
+        )
+    }
+}
+
+/// Determines if the given token starts a top-level statement.
+pub(crate) fn starts_statement(token: Token) -> bool {
+    matches!(
+        token,
+        Token::LetKeyword
+            | Token::ImportKeyword
+            | Token::ExportKeyword
+            | Token::IncludeKeyword
+            | Token::InterfaceKeyword
+            | Token::WorldKeyword
+            | Token::VariantKeyword
+            | Token::RecordKeyword
+            | Token::FlagsKeyword
+            | Token::EnumKeyword
+            | Token::TypeKeyword
+    )
+}
+
+/// Recovers from a parse error by skipping to the next statement boundary.
+///
+/// The `start` lexer is positioned at the start of the statement that failed
+/// to parse. Skipping begins at the token that caused the error, tracking how
+/// deeply nested in braces it is so that the rest of a braced statement is
+/// skipped.
+fn recover<'a>(start: Lexer<'a>, lexer: &mut Lexer<'a>, error: &Error) {
+    let offset = error.span().offset();
+
+    // Rewind to the token that caused the error as it may start the next statement
+    let mut depth = 0usize;
+    *lexer = start;
+    while let Some((_, span)) = lexer.peek() {
+        if span.offset() >= offset {
+            break;
+        }
+
+        match lexer.next() {
+            Some((Ok(Token::OpenBrace), _)) => depth += 1,
+            Some((Ok(Token::CloseBrace), _)) => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+
+    while let Some((token, _)) = lexer.peek() {
+        match token {
+            Ok(Token::Semicolon) if depth == 0 => {
+                lexer.next();
+                return;
+            }
+            Ok(token) if depth == 0 && starts_statement(token) => return,
+            Ok(Token::OpenBrace) => depth += 1,
+            Ok(Token::CloseBrace) => {
+                lexer.next();
+                depth = depth.saturating_sub(1);
+                if depth == 0 {
+                    // A closing brace may be followed by the statement's semicolon
+                    if let Some((Ok(Token::Semicolon), _)) = lexer.peek() {
+                        lexer.next();
+                    }
+
+                    return;
+                }
+
+                continue;
+            }
+            _ => {}
+        }
+
+        lexer.next();
     }
 }
 
 /// Represents a statement in the AST.
 #[derive(Debug, Clone, Serialize)]
 pub enum Statement<'a> {
+    /// An include statement.
+    Include(IncludeStatement<'a>),
     /// An import statement.
     Import(ImportStatement<'a>),
     /// A type statement.
     Type(TypeStatement<'a>),
     /// A let statement.
     Let(LetStatement<'a>),
+    /// A destructuring let statement.
+    Destructure(DestructureStatement<'a>),
     /// An export statement.
     Export(ExportStatement<'a>),
 }
@@ -365,9 +577,16 @@ pub enum Statement<'a> {
 impl<'a> Parse<'a> for Statement<'a> {
     fn parse(lexer: &mut Lexer<'a>) -> ParseResult<Self> {
         let mut lookahead = Lookahead::new(lexer);
-        if ImportStatement::peek(&mut lookahead) {
+        if IncludeStatement::peek(&mut lookahead) {
+            Ok(Self::Include(Parse::parse(lexer)?))
+        } else if ImportStatement::peek(&mut lookahead) {
             Ok(Self::Import(Parse::parse(lexer)?))
         } else if LetStatement::peek(&mut lookahead) {
+            // A brace after `let` starts a destructuring let statement
+            if let Some((Ok(Token::OpenBrace), _)) = lexer.peek2() {
+                return Ok(Self::Destructure(Parse::parse(lexer)?));
+            }
+
             Ok(Self::Let(Parse::parse(lexer)?))
         } else if ExportStatement::peek(&mut lookahead) {
             Ok(Self::Export(Parse::parse(lexer)?))
@@ -546,4 +765,110 @@ export x as "foo";
         )
         .unwrap()
     }
+
+    #[test]
+    fn document_comments_roundtrip() {
+        let source = r#"// leading comment
+package test:foo; // package comment
+
+// import comment
+import foo: foo:bar/baz; // trailing
+
+/* block */
+interface i { // interface comment
+    // item comment
+    f: func(); // func comment
+    // end of interface
+} // after interface
+
+/// doc comment
+// regular comment
+let x = new foo:bar { // args comment
+    // arg comment
+    a, // inferred comment
+    ...
+};
+
+export x as "foo"; /* export comment */
+
+// final comment
+"#;
+
+        let doc = Document::parse(source).unwrap();
+        let mut s = std::string::String::new();
+        DocumentPrinter::new(&mut s, source, None)
+            .with_comments()
+            .document(&doc)
+            .unwrap();
+        assert_eq!(s, source, "unexpected AST output");
+
+        // Comments within a line that is reformatted stay on that line
+        let source = r#"package test:foo;
+import foo: func(
+    a: u32, /* first */
+    b: u32 // second
+);
+import bar: func();
+"#;
+
+        let doc = Document::parse(source).unwrap();
+        let mut s = std::string::String::new();
+        DocumentPrinter::new(&mut s, source, None)
+            .with_comments()
+            .document(&doc)
+            .unwrap();
+        assert_eq!(
+            s,
+            r#"package test:foo;
+
+import foo: func(a: u32, b: u32); /* first */ // second
+
+import bar: func();
+"#,
+            "unexpected AST output"
+        );
+    }
+
+    #[test]
+    fn document_recovery() {
+        let source = r#"package test:foo;
+import a: foo:bar/baz
+let x = new foo:bar { };
+interface i {
+    f: func(;
+    g: func();
+}
+let y = new foo:bar { a: };
+export x as "foo" "bar";
+export y;
+"#;
+
+        let (doc, errors) = Document::parse_recovering(source);
+        let errors: Vec<_> = errors.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            errors,
+            [
+                "expected `;`, found `let` keyword",
+                "expected `)` or identifier, found `;`",
+                "expected either `new` keyword, `(`, or identifier, found `}`",
+                "expected `;`, found string literal",
+            ]
+        );
+
+        let doc = doc.expect("document should be present");
+        assert_eq!(doc.statements.len(), 2);
+        assert!(matches!(doc.statements[0], Statement::Let(_)));
+        assert!(matches!(doc.statements[1], Statement::Export(_)));
+    }
+
+    #[test]
+    fn document_recovery_missing_directive() {
+        let (doc, errors) = Document::parse_recovering(
+            "import a: foo:bar/baz;
+let x = ;
+",
+        );
+        assert!(doc.is_none());
+        assert_eq!(errors.len(), 2);
+    }
 }
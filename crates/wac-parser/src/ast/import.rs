@@ -4,7 +4,7 @@ use super::{
 };
 use crate::lexer::{Lexer, Token};
 use miette::SourceSpan;
-use semver::Version;
+use semver::{Version, VersionReq};
 use serde::Serialize;
 
 /// Represents an extern name following an `as` clause in the AST.
@@ -132,6 +132,12 @@ pub struct PackagePath<'a> {
     pub segments: &'a str,
     /// The optional version of the package.
     pub version: Option<Version>,
+    /// The optional version requirement of the package.
+    ///
+    /// This is `Some` when the package is referenced with a version
+    /// requirement (e.g. `foo:bar/baz@^1.2`) rather than an exact version.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub requirement: Option<VersionReq>,
 }
 
 impl<'a> PackagePath<'a> {
@@ -157,19 +163,7 @@ impl<'a> Parse<'a> for PackagePath<'a> {
         let at = s.find('@');
         let name = &s[..slash];
         let segments = &s[slash + 1..at.unwrap_or(slash + s.len() - name.len())];
-        let version = at
-            .map(|at| {
-                let version = &s[at + 1..];
-                let start = span.offset() + at + 1;
-                version.parse().map_err(|_| Error::InvalidVersion {
-                    version: version.to_owned(),
-                    span: SourceSpan::new(
-                        start.into(),
-                        ((span.offset() + span.len()) - start).into(),
-                    ),
-                })
-            })
-            .transpose()?;
+        let (version, requirement) = parse_version(s, span, at)?;
 
         Ok(Self {
             span,
@@ -177,6 +171,7 @@ impl<'a> Parse<'a> for PackagePath<'a> {
             name,
             segments,
             version,
+            requirement,
         })
     }
 }
@@ -197,6 +192,12 @@ pub struct PackageName<'a> {
     pub name: &'a str,
     /// The optional version of the package.
     pub version: Option<Version>,
+    /// The optional version requirement of the package.
+    ///
+    /// This is `Some` when the package is referenced with a version
+    /// requirement (e.g. `foo:bar@^1.2`) rather than an exact version.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub requirement: Option<VersionReq>,
     /// The span of the package name,
     pub span: SourceSpan,
 }
@@ -207,28 +208,63 @@ impl<'a> Parse<'a> for PackageName<'a> {
         let s = lexer.source(span);
         let at = s.find('@');
         let name = at.map(|at| &s[..at]).unwrap_or(s);
-        let version = at
-            .map(|at| {
-                let version = &s[at + 1..];
-                let start = span.offset() + at + 1;
-                version.parse().map_err(|_| Error::InvalidVersion {
-                    version: version.to_string(),
-                    span: SourceSpan::new(
-                        start.into(),
-                        ((span.offset() + span.len()) - start).into(),
-                    ),
-                })
-            })
-            .transpose()?;
+        let (version, requirement) = parse_version(s, span, at)?;
         Ok(Self {
             string: lexer.source(span),
             name,
             version,
+            requirement,
             span,
         })
     }
 }
 
+/// Parses the version following the `@` at the given index of a package
+/// name or path.
+///
+/// A version starting with a comparison operator or `*`, or containing
+/// comma-separated comparators, is parsed as a version requirement.
+///
+/// Otherwise, the version is parsed as an exact semantic version; a partial
+/// version (e.g. `1.2`) is parsed as a requirement as it is by Cargo.
+fn parse_version(
+    s: &str,
+    span: SourceSpan,
+    at: Option<usize>,
+) -> ParseResult<(Option<Version>, Option<VersionReq>)> {
+    let at = match at {
+        Some(at) => at,
+        None => return Ok((None, None)),
+    };
+
+    let version = &s[at + 1..];
+    let start = span.offset() + at + 1;
+    let span = SourceSpan::new(start.into(), ((span.offset() + span.len()) - start).into());
+
+    if version.starts_with(|c| matches!(c, '=' | '>' | '<' | '~' | '^' | '*'))
+        || version.contains(',')
+    {
+        let requirement = version
+            .parse()
+            .map_err(|_| Error::InvalidVersionRequirement {
+                requirement: version.to_owned(),
+                span,
+            })?;
+        return Ok((None, Some(requirement)));
+    }
+
+    match version.parse() {
+        Ok(v) => Ok((Some(v), None)),
+        Err(_) => match version.parse() {
+            Ok(requirement) => Ok((None, Some(requirement))),
+            Err(_) => Err(Error::InvalidVersion {
+                version: version.to_owned(),
+                span,
+            }),
+        },
+    }
+}
+
 #[cfg(test)]
 mod test {
     use crate::ast::test::roundtrip;
@@ -246,6 +282,24 @@ mod test {
             "package foo:bar;\n\nimport x as \"y\": foo:bar:baz/qux/jam@1.2.3-preview+abc;\n",
         )
         .unwrap();
+
+        roundtrip(
+            "package foo:bar; import x: wasi:http/types@~0.2;",
+            "package foo:bar;\n\nimport x: wasi:http/types@~0.2;\n",
+        )
+        .unwrap();
+
+        roundtrip(
+            "package foo:bar; import x: wasi:http/types@>=0.2, <0.3;",
+            "package foo:bar;\n\nimport x: wasi:http/types@>=0.2, <0.3;\n",
+        )
+        .unwrap();
+
+        roundtrip(
+            "package foo:bar; import x: wasi:http/types@0.2;",
+            "package foo:bar;\n\nimport x: wasi:http/types@0.2;\n",
+        )
+        .unwrap();
     }
 
     #[test]
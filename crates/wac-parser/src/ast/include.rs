@@ -0,0 +1,64 @@
+use super::{parse_token, DocComment, Document, Lookahead, Parse, ParseResult, Peek};
+use crate::lexer::{Lexer, Token};
+use serde::Serialize;
+use std::sync::Arc;
+
+/// Represents an include statement in the AST.
+///
+/// An include statement makes the type declarations and `let` bindings
+/// of another WAC document available to the including document.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IncludeStatement<'a> {
+    /// The doc comments for the statement.
+    pub docs: Vec<DocComment<'a>>,
+    /// The path of the included document.
+    ///
+    /// The path is relative to the directory of the including document.
+    pub path: super::String<'a>,
+    /// The included document.
+    ///
+    /// This is `None` until the included document has been loaded;
+    /// see [`Sources`](crate::sources::Sources).
+    #[serde(skip)]
+    pub document: Option<Arc<Document<'a>>>,
+}
+
+impl<'a> Parse<'a> for IncludeStatement<'a> {
+    fn parse(lexer: &mut Lexer<'a>) -> ParseResult<Self> {
+        let docs = Parse::parse(lexer)?;
+        parse_token(lexer, Token::IncludeKeyword)?;
+        let path = Parse::parse(lexer)?;
+        parse_token(lexer, Token::Semicolon)?;
+        Ok(Self {
+            docs,
+            path,
+            document: None,
+        })
+    }
+}
+
+impl Peek for IncludeStatement<'_> {
+    fn peek(lookahead: &mut Lookahead) -> bool {
+        lookahead.peek(Token::IncludeKeyword)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::ast::test::roundtrip;
+
+    #[test]
+    fn include_statement_roundtrip() {
+        roundtrip(
+            "package foo:bar; include    \"./common.wac\"  ;",
+            "package foo:bar;\n\ninclude \"./common.wac\";\n",
+        )
+        .unwrap();
+        roundtrip(
+            "package foo:bar; /// Shared types\ninclude \"types.wac\"; let x = new foo:bar {};",
+            "package foo:bar;\n\n/// Shared types\ninclude \"types.wac\";\n\nlet x = new foo:bar {};\n",
+        )
+        .unwrap();
+    }
+}
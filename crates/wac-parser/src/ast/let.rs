@@ -1,5 +1,9 @@
-use super::{parse_token, DocComment, Expr, Ident, Lookahead, Parse, ParseResult, Peek};
+use super::{
+    parse_delimited, parse_optional, parse_token, DocComment, Error, Expr, Ident, Lookahead, Parse,
+    ParseResult, Peek,
+};
 use crate::lexer::{Lexer, Token};
+use miette::SourceSpan;
 use serde::Serialize;
 
 /// Represents a let statement in the AST.
@@ -32,6 +36,87 @@ impl Peek for LetStatement<'_> {
     }
 }
 
+/// Represents a destructuring let statement in the AST.
+///
+/// The exports of an instance are bound to identifiers, for example
+/// `let { foo, bar as baz } = i;`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DestructureStatement<'a> {
+    /// The doc comments for the statement.
+    pub docs: Vec<DocComment<'a>>,
+    /// The items being bound.
+    ///
+    /// There is always at least one item.
+    pub items: Vec<DestructureItem<'a>>,
+    /// The expression being destructured.
+    pub expr: Expr<'a>,
+}
+
+impl<'a> DestructureStatement<'a> {
+    /// Gets the identifiers bound by the statement.
+    pub fn ids(&self) -> impl Iterator<Item = &Ident<'a>> {
+        self.items.iter().map(DestructureItem::binding)
+    }
+}
+
+impl<'a> Parse<'a> for DestructureStatement<'a> {
+    fn parse(lexer: &mut Lexer<'a>) -> ParseResult<Self> {
+        let docs = Parse::parse(lexer)?;
+        parse_token(lexer, Token::LetKeyword)?;
+        let open = parse_token(lexer, Token::OpenBrace)?;
+        let items: Vec<DestructureItem> = parse_delimited(lexer, Token::CloseBrace, true)?;
+        let close = parse_token(lexer, Token::CloseBrace)?;
+
+        if items.is_empty() {
+            return Err(Error::EmptyDestructure {
+                span: SourceSpan::new(
+                    open.offset().into(),
+                    (close.offset() + close.len() - open.offset()).into(),
+                ),
+            });
+        }
+
+        parse_token(lexer, Token::Equals)?;
+        let expr = Parse::parse(lexer)?;
+        parse_token(lexer, Token::Semicolon)?;
+        Ok(Self { docs, items, expr })
+    }
+}
+
+/// Represents an item of a destructuring let statement in the AST.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DestructureItem<'a> {
+    /// The identifier of the instance export.
+    ///
+    /// The export name is inferred as for an access expression.
+    pub id: Ident<'a>,
+    /// The optional `as` identifier of the item.
+    pub as_id: Option<Ident<'a>>,
+}
+
+impl<'a> DestructureItem<'a> {
+    /// Gets the identifier bound by the item.
+    pub fn binding(&self) -> &Ident<'a> {
+        self.as_id.as_ref().unwrap_or(&self.id)
+    }
+}
+
+impl<'a> Parse<'a> for DestructureItem<'a> {
+    fn parse(lexer: &mut Lexer<'a>) -> ParseResult<Self> {
+        let id = Ident::parse(lexer)?;
+        let as_id = parse_optional(lexer, Token::AsKeyword, Ident::parse)?;
+        Ok(Self { id, as_id })
+    }
+}
+
+impl Peek for DestructureItem<'_> {
+    fn peek(lookahead: &mut Lookahead) -> bool {
+        Ident::peek(lookahead)
+    }
+}
+
 #[cfg(test)]
 mod test {
     use crate::ast::test::roundtrip;
@@ -71,6 +156,12 @@ mod test {
         )
         .unwrap();
 
+        roundtrip(
+            "package foo:bar; let x = new foo:bar@^1.2 {};",
+            "package foo:bar;\n\nlet x = new foo:bar@^1.2 {};\n",
+        )
+        .unwrap();
+
         roundtrip(
             "package foo:bar; let x = new foo:bar { foo, \"bar\": (new baz:qux {...}), \"baz\": foo[\"baz\"].qux };",
             "package foo:bar;\n\nlet x = new foo:bar {\n    foo,\n    \"bar\": (new baz:qux { ... }),\n    \"baz\": foo[\"baz\"].qux,\n};\n",
@@ -82,5 +173,11 @@ mod test {
             "package foo:bar;\n\nlet x = new foo:bar {\n    foo,\n    ...i,\n    bar: baz,\n    ...i2,\n    ...\n};\n",
         )
         .unwrap();
+
+        roundtrip(
+            "package foo:bar; let {foo,bar as baz,} = new foo:bar { ... };",
+            "package foo:bar;\n\nlet { foo, bar as baz } = new foo:bar { ... };\n",
+        )
+        .unwrap();
     }
 }
@@ -1,7 +1,55 @@
 //! Module for printing WAC documents.
 
-use crate::ast::*;
-use std::fmt::Write;
+use crate::{
+    ast::*,
+    lexer::{self, Lexer, Token},
+};
+use std::{cell::Cell, collections::VecDeque, fmt::Write};
+
+/// Used to track the non-documentation comments of a source string while printing.
+struct Comments<'a> {
+    /// The comments that have yet to be printed.
+    pending: VecDeque<(&'a str, SourceSpan)>,
+    /// The offsets of matching open and close braces, ordered by the open brace.
+    braces: Vec<(usize, usize)>,
+    /// The offsets of the close braces of the blocks currently being printed.
+    closes: Vec<usize>,
+    /// The source offset of the last item printed.
+    offset: usize,
+    /// The source offset of the end of the last source text printed.
+    printed: Cell<usize>,
+}
+
+impl<'a> Comments<'a> {
+    fn new(source: &'a str) -> Self {
+        let mut braces = Vec::new();
+        let mut opens = Vec::new();
+        if let Ok(lexer) = Lexer::new(source) {
+            for (token, span) in lexer {
+                match token {
+                    Ok(Token::OpenBrace) => opens.push(span.offset()),
+                    Ok(Token::CloseBrace) => {
+                        if let Some(open) = opens.pop() {
+                            braces.push((open, span.offset()));
+                        }
+                    }
+                    Ok(_) => {}
+                    Err(_) => break,
+                }
+            }
+        }
+
+        braces.sort();
+
+        Self {
+            pending: lexer::regular_comments(source).into(),
+            braces,
+            closes: Vec::new(),
+            offset: 0,
+            printed: Cell::new(0),
+        }
+    }
+}
 
 /// A printer for WAC documents.
 pub struct DocumentPrinter<'a, W: Write> {
@@ -10,6 +58,7 @@ pub struct DocumentPrinter<'a, W: Write> {
     space: &'static str,
     indent: usize,
     indented: bool,
+    comments: Option<Comments<'a>>,
 }
 
 impl<'a, W: Write> DocumentPrinter<'a, W> {
@@ -24,12 +73,24 @@ impl<'a, W: Write> DocumentPrinter<'a, W> {
             space: space.unwrap_or("    "),
             indent: 0,
             indented: false,
+            comments: None,
         }
     }
 
+    /// Configures the printer to preserve non-documentation comments.
+    ///
+    /// Comments on their own lines are printed on their own lines before the
+    /// item that follows them in the source; comments following source text
+    /// on the same line are printed at the end of the line that text is
+    /// printed on.
+    pub fn with_comments(mut self) -> Self {
+        self.comments = Some(Comments::new(self.source));
+        self
+    }
+
     /// Prints the given document.
     pub fn document(&mut self, doc: &Document) -> std::fmt::Result {
-        self.docs(&doc.docs)?;
+        self.item_comments(&doc.docs, doc.directive.package.span)?;
         self.package_directive(&doc.directive)?;
 
         for (i, statement) in doc.statements.iter().enumerate() {
@@ -41,6 +102,19 @@ impl<'a, W: Write> DocumentPrinter<'a, W> {
             self.newline()?;
         }
 
+        if self
+            .comments
+            .as_ref()
+            .map(|c| !c.pending.is_empty())
+            .unwrap_or(false)
+        {
+            if !doc.statements.is_empty() {
+                self.newline()?;
+            }
+
+            self.comments(usize::MAX)?;
+        }
+
         Ok(())
     }
 
@@ -58,16 +132,19 @@ impl<'a, W: Write> DocumentPrinter<'a, W> {
             self.package_path(targets)?;
         }
 
-        writeln!(self.writer, ";")?;
+        write!(self.writer, ";")?;
+        self.newline()?;
         self.newline()
     }
 
     /// Prints the given statement.
     pub fn statement(&mut self, statement: &Statement) -> std::fmt::Result {
         match statement {
+            Statement::Include(i) => self.include_statement(i),
             Statement::Import(i) => self.import_statement(i),
             Statement::Type(t) => self.type_statement(t),
             Statement::Let(l) => self.let_statement(l),
+            Statement::Destructure(d) => self.destructure_statement(d),
             Statement::Export(e) => self.export_statement(e),
         }
     }
@@ -85,9 +162,21 @@ impl<'a, W: Write> DocumentPrinter<'a, W> {
         Ok(())
     }
 
+    /// Prints the given include statement.
+    pub fn include_statement(&mut self, statement: &IncludeStatement) -> std::fmt::Result {
+        self.item_comments(&statement.docs, statement.path.span)?;
+
+        self.indent()?;
+        write!(
+            self.writer,
+            "include {path};",
+            path = self.source(statement.path.span)
+        )
+    }
+
     /// Prints the given import statement.
     pub fn import_statement(&mut self, statement: &ImportStatement) -> std::fmt::Result {
-        self.docs(&statement.docs)?;
+        self.item_comments(&statement.docs, statement.id.span)?;
 
         self.indent()?;
         write!(
@@ -227,6 +316,7 @@ impl<'a, W: Write> DocumentPrinter<'a, W> {
         self.newline()?;
 
         self.inc();
+        self.open_block();
         for (i, item) in iface.items.iter().enumerate() {
             if i > 0 {
                 self.newline()?;
@@ -236,6 +326,7 @@ impl<'a, W: Write> DocumentPrinter<'a, W> {
             self.newline()?;
         }
 
+        self.close_block()?;
         self.dec();
         self.indent()?;
         write!(self.writer, "}}")
@@ -252,7 +343,11 @@ impl<'a, W: Write> DocumentPrinter<'a, W> {
 
     /// Prints the given use type.
     pub fn use_type(&mut self, use_ty: &Use) -> std::fmt::Result {
-        self.docs(&use_ty.docs)?;
+        let span = match &use_ty.path {
+            UsePath::Package(p) => p.span,
+            UsePath::Ident(id) => id.span,
+        };
+        self.item_comments(&use_ty.docs, span)?;
         self.indent()?;
         write!(self.writer, "use ")?;
         self.use_path(&use_ty.path)?;
@@ -295,7 +390,7 @@ impl<'a, W: Write> DocumentPrinter<'a, W> {
 
     /// Prints the given resource declaration.
     pub fn resource_decl(&mut self, decl: &ResourceDecl) -> std::fmt::Result {
-        self.docs(&decl.docs)?;
+        self.item_comments(&decl.docs, decl.id.span)?;
         self.indent()?;
         write!(
             self.writer,
@@ -305,6 +400,7 @@ impl<'a, W: Write> DocumentPrinter<'a, W> {
         self.newline()?;
 
         self.inc();
+        self.open_block();
         for (i, method) in decl.methods.iter().enumerate() {
             if i > 0 {
                 self.newline()?;
@@ -314,6 +410,7 @@ impl<'a, W: Write> DocumentPrinter<'a, W> {
             self.newline()?;
         }
 
+        self.close_block()?;
         self.dec();
         self.indent()?;
         write!(self.writer, "}}")
@@ -329,7 +426,7 @@ impl<'a, W: Write> DocumentPrinter<'a, W> {
 
     /// Prints the given constructor.
     pub fn constructor(&mut self, constructor: &Constructor) -> std::fmt::Result {
-        self.docs(&constructor.docs)?;
+        self.item_comments(&constructor.docs, constructor.span)?;
         self.indent()?;
         write!(self.writer, "constructor(")?;
         self.named_types(&constructor.params)?;
@@ -338,7 +435,7 @@ impl<'a, W: Write> DocumentPrinter<'a, W> {
 
     /// Prints the given method.
     pub fn method(&mut self, method: &Method) -> std::fmt::Result {
-        self.docs(&method.docs)?;
+        self.item_comments(&method.docs, method.id.span)?;
         self.indent()?;
         write!(self.writer, "{id}: ", id = self.source(method.id.span))?;
 
@@ -360,7 +457,7 @@ impl<'a, W: Write> DocumentPrinter<'a, W> {
 
     /// Prints the given variant declaration.
     pub fn variant_decl(&mut self, decl: &VariantDecl) -> std::fmt::Result {
-        self.docs(&decl.docs)?;
+        self.item_comments(&decl.docs, decl.id.span)?;
         self.indent()?;
         write!(
             self.writer,
@@ -370,13 +467,15 @@ impl<'a, W: Write> DocumentPrinter<'a, W> {
         self.newline()?;
 
         self.inc();
+        self.open_block();
         for case in &decl.cases {
-            self.indent()?;
+            self.item_comments(&case.docs, case.id.span)?;
             self.variant_case(case)?;
             write!(self.writer, ",")?;
             self.newline()?;
         }
 
+        self.close_block()?;
         self.dec();
         self.indent()?;
         write!(self.writer, "}}")
@@ -384,7 +483,6 @@ impl<'a, W: Write> DocumentPrinter<'a, W> {
 
     /// Prints the given variant case.
     pub fn variant_case(&mut self, case: &VariantCase) -> std::fmt::Result {
-        self.docs(&case.docs)?;
         self.indent()?;
         write!(self.writer, "{id}", id = self.source(case.id.span))?;
 
@@ -399,7 +497,7 @@ impl<'a, W: Write> DocumentPrinter<'a, W> {
 
     /// Prints the given record declaration.
     pub fn record_decl(&mut self, decl: &RecordDecl) -> std::fmt::Result {
-        self.docs(&decl.docs)?;
+        self.item_comments(&decl.docs, decl.id.span)?;
         self.indent()?;
         write!(
             self.writer,
@@ -409,8 +507,9 @@ impl<'a, W: Write> DocumentPrinter<'a, W> {
         self.newline()?;
 
         self.inc();
+        self.open_block();
         for field in &decl.fields {
-            self.docs(&field.docs)?;
+            self.item_comments(&field.docs, field.id.span)?;
             self.indent()?;
             write!(self.writer, "{id}: ", id = self.source(field.id.span))?;
             self.ty(&field.ty)?;
@@ -418,6 +517,7 @@ impl<'a, W: Write> DocumentPrinter<'a, W> {
             self.newline()?;
         }
 
+        self.close_block()?;
         self.dec();
         self.indent()?;
         write!(self.writer, "}}")
@@ -425,19 +525,21 @@ impl<'a, W: Write> DocumentPrinter<'a, W> {
 
     /// Prints the given flags declaration.
     pub fn flags_decl(&mut self, decl: &FlagsDecl) -> std::fmt::Result {
-        self.docs(&decl.docs)?;
+        self.item_comments(&decl.docs, decl.id.span)?;
         self.indent()?;
         write!(self.writer, "flags {id} {{", id = self.source(decl.id.span))?;
         self.newline()?;
 
         self.inc();
+        self.open_block();
         for flag in &decl.flags {
-            self.docs(&flag.docs)?;
+            self.item_comments(&flag.docs, flag.id.span)?;
             self.indent()?;
             write!(self.writer, "{id},", id = self.source(flag.id.span))?;
             self.newline()?;
         }
 
+        self.close_block()?;
         self.dec();
         self.indent()?;
         write!(self.writer, "}}")
@@ -445,19 +547,21 @@ impl<'a, W: Write> DocumentPrinter<'a, W> {
 
     /// Prints the given enum declaration.
     pub fn enum_decl(&mut self, decl: &EnumDecl) -> std::fmt::Result {
-        self.docs(&decl.docs)?;
+        self.item_comments(&decl.docs, decl.id.span)?;
         self.indent()?;
         write!(self.writer, "enum {id} {{", id = self.source(decl.id.span))?;
         self.newline()?;
 
         self.inc();
+        self.open_block();
         for case in &decl.cases {
-            self.docs(&case.docs)?;
+            self.item_comments(&case.docs, case.id.span)?;
             self.indent()?;
             write!(self.writer, "{id},", id = self.source(case.id.span))?;
             self.newline()?;
         }
 
+        self.close_block()?;
         self.dec();
         self.indent()?;
         write!(self.writer, "}}")
@@ -465,7 +569,7 @@ impl<'a, W: Write> DocumentPrinter<'a, W> {
 
     /// Prints the given type alias.
     pub fn type_alias(&mut self, alias: &TypeAlias) -> std::fmt::Result {
-        self.docs(&alias.docs)?;
+        self.item_comments(&alias.docs, alias.id.span)?;
         self.indent()?;
         write!(self.writer, "type {id} = ", id = self.source(alias.id.span))?;
         match &alias.kind {
@@ -478,7 +582,7 @@ impl<'a, W: Write> DocumentPrinter<'a, W> {
 
     /// Prints the given interface export.
     pub fn interface_export(&mut self, export: &InterfaceExport) -> std::fmt::Result {
-        self.docs(&export.docs)?;
+        self.item_comments(&export.docs, export.id.span)?;
         self.indent()?;
         write!(self.writer, "{id}: ", id = self.source(export.id.span))?;
         self.func_type_ref(&export.ty)?;
@@ -496,7 +600,7 @@ impl<'a, W: Write> DocumentPrinter<'a, W> {
 
     /// Prints the given interface declaration.
     pub fn interface_decl(&mut self, decl: &InterfaceDecl) -> std::fmt::Result {
-        self.docs(&decl.docs)?;
+        self.item_comments(&decl.docs, decl.id.span)?;
         self.indent()?;
         write!(
             self.writer,
@@ -506,6 +610,7 @@ impl<'a, W: Write> DocumentPrinter<'a, W> {
         self.newline()?;
 
         self.inc();
+        self.open_block();
         for (i, item) in decl.items.iter().enumerate() {
             if i > 0 {
                 self.newline()?;
@@ -515,6 +620,7 @@ impl<'a, W: Write> DocumentPrinter<'a, W> {
             self.newline()?;
         }
 
+        self.close_block()?;
         self.dec();
         self.indent()?;
         write!(self.writer, "}}")
@@ -522,12 +628,13 @@ impl<'a, W: Write> DocumentPrinter<'a, W> {
 
     /// Prints the given world declaration.
     pub fn world_decl(&mut self, decl: &WorldDecl) -> std::fmt::Result {
-        self.docs(&decl.docs)?;
+        self.item_comments(&decl.docs, decl.id.span)?;
         self.indent()?;
         write!(self.writer, "world {id} {{", id = self.source(decl.id.span))?;
         self.newline()?;
 
         self.inc();
+        self.open_block();
         for (i, item) in decl.items.iter().enumerate() {
             if i > 0 {
                 self.newline()?;
@@ -537,6 +644,7 @@ impl<'a, W: Write> DocumentPrinter<'a, W> {
             self.newline()?;
         }
 
+        self.close_block()?;
         self.dec();
         self.indent()?;
         write!(self.writer, "}}")
@@ -555,7 +663,7 @@ impl<'a, W: Write> DocumentPrinter<'a, W> {
 
     /// Prints the given world import.
     pub fn world_import(&mut self, import: &WorldImport) -> std::fmt::Result {
-        self.docs(&import.docs)?;
+        self.item_comments(&import.docs, world_item_path_span(&import.path))?;
         self.indent()?;
         write!(self.writer, "import ")?;
 
@@ -565,7 +673,7 @@ impl<'a, W: Write> DocumentPrinter<'a, W> {
 
     /// Prints the given world export.
     pub fn world_export(&mut self, export: &WorldExport) -> std::fmt::Result {
-        self.docs(&export.docs)?;
+        self.item_comments(&export.docs, world_item_path_span(&export.path))?;
         self.indent()?;
         write!(self.writer, "export ")?;
 
@@ -599,7 +707,7 @@ impl<'a, W: Write> DocumentPrinter<'a, W> {
 
     /// Prints the given world include.
     pub fn world_include(&mut self, include: &WorldInclude) -> std::fmt::Result {
-        self.docs(&include.docs)?;
+        self.item_comments(&include.docs, include.world.span())?;
         self.indent()?;
         write!(self.writer, "include ")?;
         self.world_ref(&include.world)?;
@@ -608,8 +716,10 @@ impl<'a, W: Write> DocumentPrinter<'a, W> {
             write!(self.writer, " with {{")?;
             self.newline()?;
             self.inc();
+            self.open_block();
 
             for item in &include.with {
+                self.comments(item.from.span.offset())?;
                 self.indent()?;
                 write!(
                     self.writer,
@@ -620,6 +730,7 @@ impl<'a, W: Write> DocumentPrinter<'a, W> {
                 self.newline()?;
             }
 
+            self.close_block()?;
             self.dec();
             self.indent()?;
             write!(self.writer, "}}")?;
@@ -649,13 +760,35 @@ impl<'a, W: Write> DocumentPrinter<'a, W> {
 
     /// Prints the given let statement.
     pub fn let_statement(&mut self, stmt: &LetStatement) -> std::fmt::Result {
-        self.docs(&stmt.docs)?;
+        self.item_comments(&stmt.docs, stmt.id.span)?;
         self.indent()?;
         write!(self.writer, "let {id} = ", id = self.source(stmt.id.span))?;
         self.expr(&stmt.expr)?;
         write!(self.writer, ";")
     }
 
+    /// Prints the given destructuring let statement.
+    pub fn destructure_statement(&mut self, stmt: &DestructureStatement) -> std::fmt::Result {
+        self.item_comments(&stmt.docs, stmt.items[0].id.span)?;
+        self.indent()?;
+        write!(self.writer, "let {{ ")?;
+
+        for (i, item) in stmt.items.iter().enumerate() {
+            if i > 0 {
+                write!(self.writer, ", ")?;
+            }
+
+            write!(self.writer, "{id}", id = self.source(item.id.span))?;
+            if let Some(as_id) = &item.as_id {
+                write!(self.writer, " as {as_id}", as_id = self.source(as_id.span))?;
+            }
+        }
+
+        write!(self.writer, " }} = ")?;
+        self.expr(&stmt.expr)?;
+        write!(self.writer, ";")
+    }
+
     /// Prints the given expression.
     pub fn expr(&mut self, expr: &Expr) -> std::fmt::Result {
         self.primary_expr(&expr.primary)?;
@@ -701,8 +834,15 @@ impl<'a, W: Write> DocumentPrinter<'a, W> {
 
         self.newline()?;
         self.inc();
+        self.open_block();
 
         for arg in &expr.arguments {
+            let span = match arg {
+                InstantiationArgument::Inferred(id) | InstantiationArgument::Spread(id) => id.span,
+                InstantiationArgument::Named(arg) => arg.name.span(),
+                InstantiationArgument::Fill(span) => *span,
+            };
+            self.comments(span.offset())?;
             self.indent()?;
 
             match arg {
@@ -730,6 +870,7 @@ impl<'a, W: Write> DocumentPrinter<'a, W> {
             self.newline()?;
         }
 
+        self.close_block()?;
         self.dec();
         self.indent()?;
         write!(self.writer, "}}")
@@ -759,7 +900,7 @@ impl<'a, W: Write> DocumentPrinter<'a, W> {
 
     /// Prints the given export statement.
     pub fn export_statement(&mut self, stmt: &ExportStatement) -> std::fmt::Result {
-        self.docs(&stmt.docs)?;
+        self.item_comments(&stmt.docs, stmt.expr.span)?;
         self.indent()?;
 
         write!(self.writer, "export ")?;
@@ -779,7 +920,117 @@ impl<'a, W: Write> DocumentPrinter<'a, W> {
         write!(self.writer, ";")
     }
 
+    fn comments(&mut self, offset: usize) -> std::fmt::Result {
+        let mut printed = Vec::new();
+        if let Some(comments) = &mut self.comments {
+            comments.offset = comments.offset.max(offset);
+            while let Some((comment, span)) = comments.pending.front().copied() {
+                if span.offset() >= offset {
+                    break;
+                }
+
+                printed.push(comment);
+                comments.pending.pop_front();
+            }
+        }
+
+        for comment in printed {
+            self.indent()?;
+            write!(self.writer, "{comment}")?;
+            self.newline()?;
+        }
+
+        Ok(())
+    }
+
+    /// Prints the comments and doc comments preceding an item.
+    fn item_comments(&mut self, docs: &[DocComment], span: SourceSpan) -> std::fmt::Result {
+        for doc in docs {
+            self.comments(doc.span.offset())?;
+            self.docs(std::slice::from_ref(doc))?;
+        }
+
+        self.comments(span.offset())?;
+        self.mark_printed(span.offset());
+        Ok(())
+    }
+
+    /// Prints the comments that follow the last printed source text on the same line.
+    fn trailing_comments(&mut self) -> std::fmt::Result {
+        let comments = match &mut self.comments {
+            Some(comments) => comments,
+            None => return Ok(()),
+        };
+
+        while let Some((comment, span)) = comments.pending.front().copied() {
+            // A comment before the end of the printed text is within the
+            // printed line (e.g. in a parameter list); otherwise the comment
+            // must start on the same source line
+            let printed = comments.printed.get();
+            if span.offset() > printed && self.source[printed..span.offset()].contains('\n') {
+                break;
+            }
+
+            write!(self.writer, " {comment}")?;
+            comments
+                .printed
+                .set(printed.max(span.offset() + span.len()));
+            comments.pending.pop_front();
+
+            // Nothing may follow a line comment
+            if comment.starts_with("//") {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn mark_printed(&self, end: usize) {
+        if let Some(comments) = &self.comments {
+            comments.printed.set(comments.printed.get().max(end));
+        }
+    }
+
+    fn open_block(&mut self) {
+        if let Some(comments) = &mut self.comments {
+            let index = comments
+                .braces
+                .partition_point(|(open, _)| *open < comments.offset);
+            let (open, close) = comments
+                .braces
+                .get(index)
+                .copied()
+                .unwrap_or((usize::MAX, usize::MAX));
+            comments.offset = open.saturating_add(1);
+            comments.closes.push(close);
+        }
+    }
+
+    fn close_block(&mut self) -> std::fmt::Result {
+        let close = match &mut self.comments {
+            Some(comments) => comments.closes.pop().unwrap_or(usize::MAX),
+            None => return Ok(()),
+        };
+
+        // Comments can only be printed on their own line
+        if !self.indented && close != usize::MAX {
+            self.comments(close)?;
+        }
+
+        if let Some(comments) = &mut self.comments {
+            comments.offset = comments.offset.max(close.saturating_add(1));
+        }
+
+        self.mark_printed(close.saturating_add(1));
+        Ok(())
+    }
+
     fn newline(&mut self) -> std::fmt::Result {
+        if self.indented {
+            self.trailing_comments()?;
+        }
+
         writeln!(self.writer)?;
         self.indented = false;
         Ok(())
@@ -806,6 +1057,15 @@ impl<'a, W: Write> DocumentPrinter<'a, W> {
     }
 
     fn source(&self, span: SourceSpan) -> &'a str {
+        self.mark_printed(span.offset() + span.len());
         &self.source[span.offset()..span.offset() + span.len()]
     }
 }
+
+fn world_item_path_span(path: &WorldItemPath) -> SourceSpan {
+    match path {
+        WorldItemPath::Named(n) => n.id.span,
+        WorldItemPath::Package(p) => p.span,
+        WorldItemPath::Ident(id) => id.span,
+    }
+}
@@ -0,0 +1,1972 @@
+//! Module for visiting the nodes of a WAC document.
+//!
+//! The [`Visitor`] and [`VisitMut`] traits have a method for every node type
+//! in the AST. The default implementation of each method walks the children
+//! of the node with the corresponding `walk_*` function; implementations
+//! override the methods for the nodes they are interested in and call the
+//! `walk_*` function to continue into the children of the node.
+
+use super::*;
+
+/// A visitor of the nodes of a document.
+///
+/// Included documents are visited in place of their include statements.
+pub trait Visitor<'a> {
+    /// Visits a document.
+    fn visit_document(&mut self, doc: &'a Document<'a>) {
+        walk_document(self, doc)
+    }
+
+    /// Visits a package directive.
+    fn visit_package_directive(&mut self, directive: &'a PackageDirective<'a>) {
+        walk_package_directive(self, directive)
+    }
+
+    /// Visits a statement.
+    fn visit_statement(&mut self, statement: &'a Statement<'a>) {
+        walk_statement(self, statement)
+    }
+
+    /// Visits an include statement.
+    fn visit_include_statement(&mut self, statement: &'a IncludeStatement<'a>) {
+        walk_include_statement(self, statement)
+    }
+
+    /// Visits an import statement.
+    fn visit_import_statement(&mut self, statement: &'a ImportStatement<'a>) {
+        walk_import_statement(self, statement)
+    }
+
+    /// Visits the type of an import statement.
+    fn visit_import_type(&mut self, ty: &'a ImportType<'a>) {
+        walk_import_type(self, ty)
+    }
+
+    /// Visits an extern name.
+    fn visit_extern_name(&mut self, name: &'a ExternName<'a>) {
+        walk_extern_name(self, name)
+    }
+
+    /// Visits a type statement.
+    fn visit_type_statement(&mut self, statement: &'a TypeStatement<'a>) {
+        walk_type_statement(self, statement)
+    }
+
+    /// Visits a let statement.
+    fn visit_let_statement(&mut self, statement: &'a LetStatement<'a>) {
+        walk_let_statement(self, statement)
+    }
+
+    /// Visits a destructuring let statement.
+    fn visit_destructure_statement(&mut self, statement: &'a DestructureStatement<'a>) {
+        walk_destructure_statement(self, statement)
+    }
+
+    /// Visits an item of a destructuring let statement.
+    fn visit_destructure_item(&mut self, item: &'a DestructureItem<'a>) {
+        walk_destructure_item(self, item)
+    }
+
+    /// Visits an export statement.
+    fn visit_export_statement(&mut self, statement: &'a ExportStatement<'a>) {
+        walk_export_statement(self, statement)
+    }
+
+    /// Visits the options of an export statement.
+    fn visit_export_options(&mut self, options: &'a ExportOptions<'a>) {
+        walk_export_options(self, options)
+    }
+
+    /// Visits an expression.
+    fn visit_expr(&mut self, expr: &'a Expr<'a>) {
+        walk_expr(self, expr)
+    }
+
+    /// Visits a primary expression.
+    fn visit_primary_expr(&mut self, expr: &'a PrimaryExpr<'a>) {
+        walk_primary_expr(self, expr)
+    }
+
+    /// Visits a `new` expression.
+    fn visit_new_expr(&mut self, expr: &'a NewExpr<'a>) {
+        walk_new_expr(self, expr)
+    }
+
+    /// Visits an instantiation argument.
+    fn visit_instantiation_argument(&mut self, arg: &'a InstantiationArgument<'a>) {
+        walk_instantiation_argument(self, arg)
+    }
+
+    /// Visits a named instantiation argument.
+    fn visit_named_instantiation_argument(&mut self, arg: &'a NamedInstantiationArgument<'a>) {
+        walk_named_instantiation_argument(self, arg)
+    }
+
+    /// Visits the name of an instantiation argument.
+    fn visit_instantiation_argument_name(&mut self, name: &'a InstantiationArgumentName<'a>) {
+        walk_instantiation_argument_name(self, name)
+    }
+
+    /// Visits a nested expression.
+    fn visit_nested_expr(&mut self, expr: &'a NestedExpr<'a>) {
+        walk_nested_expr(self, expr)
+    }
+
+    /// Visits a postfix expression.
+    fn visit_postfix_expr(&mut self, expr: &'a PostfixExpr<'a>) {
+        walk_postfix_expr(self, expr)
+    }
+
+    /// Visits an access expression.
+    fn visit_access_expr(&mut self, expr: &'a AccessExpr<'a>) {
+        walk_access_expr(self, expr)
+    }
+
+    /// Visits a named access expression.
+    fn visit_named_access_expr(&mut self, expr: &'a NamedAccessExpr<'a>) {
+        walk_named_access_expr(self, expr)
+    }
+
+    /// Visits a type declaration.
+    fn visit_type_decl(&mut self, decl: &'a TypeDecl<'a>) {
+        walk_type_decl(self, decl)
+    }
+
+    /// Visits a type declaration in an interface or world.
+    fn visit_item_type_decl(&mut self, decl: &'a ItemTypeDecl<'a>) {
+        walk_item_type_decl(self, decl)
+    }
+
+    /// Visits a resource declaration.
+    fn visit_resource_decl(&mut self, decl: &'a ResourceDecl<'a>) {
+        walk_resource_decl(self, decl)
+    }
+
+    /// Visits a resource method.
+    fn visit_resource_method(&mut self, method: &'a ResourceMethod<'a>) {
+        walk_resource_method(self, method)
+    }
+
+    /// Visits a variant declaration.
+    fn visit_variant_decl(&mut self, decl: &'a VariantDecl<'a>) {
+        walk_variant_decl(self, decl)
+    }
+
+    /// Visits a variant case.
+    fn visit_variant_case(&mut self, case: &'a VariantCase<'a>) {
+        walk_variant_case(self, case)
+    }
+
+    /// Visits a record declaration.
+    fn visit_record_decl(&mut self, decl: &'a RecordDecl<'a>) {
+        walk_record_decl(self, decl)
+    }
+
+    /// Visits a record field.
+    fn visit_field(&mut self, field: &'a Field<'a>) {
+        walk_field(self, field)
+    }
+
+    /// Visits a flags declaration.
+    fn visit_flags_decl(&mut self, decl: &'a FlagsDecl<'a>) {
+        walk_flags_decl(self, decl)
+    }
+
+    /// Visits a flag.
+    fn visit_flag(&mut self, flag: &'a Flag<'a>) {
+        walk_flag(self, flag)
+    }
+
+    /// Visits an enum declaration.
+    fn visit_enum_decl(&mut self, decl: &'a EnumDecl<'a>) {
+        walk_enum_decl(self, decl)
+    }
+
+    /// Visits an enum case.
+    fn visit_enum_case(&mut self, case: &'a EnumCase<'a>) {
+        walk_enum_case(self, case)
+    }
+
+    /// Visits a type alias.
+    fn visit_type_alias(&mut self, alias: &'a TypeAlias<'a>) {
+        walk_type_alias(self, alias)
+    }
+
+    /// Visits a function type reference.
+    fn visit_func_type_ref(&mut self, ty: &'a FuncTypeRef<'a>) {
+        walk_func_type_ref(self, ty)
+    }
+
+    /// Visits a function type.
+    fn visit_func_type(&mut self, ty: &'a FuncType<'a>) {
+        walk_func_type(self, ty)
+    }
+
+    /// Visits the results of a function type.
+    fn visit_result_list(&mut self, results: &'a ResultList<'a>) {
+        walk_result_list(self, results)
+    }
+
+    /// Visits a named type.
+    fn visit_named_type(&mut self, ty: &'a NamedType<'a>) {
+        walk_named_type(self, ty)
+    }
+
+    /// Visits a type.
+    fn visit_type(&mut self, ty: &'a Type<'a>) {
+        walk_type(self, ty)
+    }
+
+    /// Visits an interface declaration.
+    fn visit_interface_decl(&mut self, decl: &'a InterfaceDecl<'a>) {
+        walk_interface_decl(self, decl)
+    }
+
+    /// Visits an interface item.
+    fn visit_interface_item(&mut self, item: &'a InterfaceItem<'a>) {
+        walk_interface_item(self, item)
+    }
+
+    /// Visits a use item.
+    fn visit_use(&mut self, item: &'a Use<'a>) {
+        walk_use(self, item)
+    }
+
+    /// Visits the path of a use item.
+    fn visit_use_path(&mut self, path: &'a UsePath<'a>) {
+        walk_use_path(self, path)
+    }
+
+    /// Visits a used type.
+    fn visit_use_item(&mut self, item: &'a UseItem<'a>) {
+        walk_use_item(self, item)
+    }
+
+    /// Visits an interface export.
+    fn visit_interface_export(&mut self, export: &'a InterfaceExport<'a>) {
+        walk_interface_export(self, export)
+    }
+
+    /// Visits an inline interface.
+    fn visit_inline_interface(&mut self, interface: &'a InlineInterface<'a>) {
+        walk_inline_interface(self, interface)
+    }
+
+    /// Visits a world declaration.
+    fn visit_world_decl(&mut self, decl: &'a WorldDecl<'a>) {
+        walk_world_decl(self, decl)
+    }
+
+    /// Visits a world item.
+    fn visit_world_item(&mut self, item: &'a WorldItem<'a>) {
+        walk_world_item(self, item)
+    }
+
+    /// Visits the path of a world import or export.
+    fn visit_world_item_path(&mut self, path: &'a WorldItemPath<'a>) {
+        walk_world_item_path(self, path)
+    }
+
+    /// Visits a named world item.
+    fn visit_named_world_item(&mut self, item: &'a NamedWorldItem<'a>) {
+        walk_named_world_item(self, item)
+    }
+
+    /// Visits the type of a named world item.
+    fn visit_extern_type(&mut self, ty: &'a ExternType<'a>) {
+        walk_extern_type(self, ty)
+    }
+
+    /// Visits a world include.
+    fn visit_world_include(&mut self, include: &'a WorldInclude<'a>) {
+        walk_world_include(self, include)
+    }
+
+    /// Visits a reference to an included world.
+    fn visit_world_ref(&mut self, world: &'a WorldRef<'a>) {
+        walk_world_ref(self, world)
+    }
+
+    /// Visits a renamed item of a world include.
+    fn visit_world_include_item(&mut self, item: &'a WorldIncludeItem<'a>) {
+        walk_world_include_item(self, item)
+    }
+
+    /// Visits a package name.
+    fn visit_package_name(&mut self, _name: &'a PackageName<'a>) {}
+
+    /// Visits a package path.
+    fn visit_package_path(&mut self, _path: &'a PackagePath<'a>) {}
+
+    /// Visits an identifier.
+    fn visit_ident(&mut self, _ident: &'a Ident<'a>) {}
+
+    /// Visits a string.
+    fn visit_string(&mut self, _string: &'a String<'a>) {}
+}
+
+/// Walks the children of a document.
+pub fn walk_document<'a, V: Visitor<'a> + ?Sized>(v: &mut V, doc: &'a Document<'a>) {
+    v.visit_package_directive(&doc.directive);
+    for statement in &doc.statements {
+        v.visit_statement(statement);
+    }
+}
+
+/// Walks the children of a package directive.
+pub fn walk_package_directive<'a, V: Visitor<'a> + ?Sized>(
+    v: &mut V,
+    directive: &'a PackageDirective<'a>,
+) {
+    v.visit_package_name(&directive.package);
+    if let Some(targets) = &directive.targets {
+        v.visit_package_path(targets);
+    }
+}
+
+/// Walks the children of a statement.
+pub fn walk_statement<'a, V: Visitor<'a> + ?Sized>(v: &mut V, statement: &'a Statement<'a>) {
+    match statement {
+        Statement::Include(s) => v.visit_include_statement(s),
+        Statement::Import(s) => v.visit_import_statement(s),
+        Statement::Type(s) => v.visit_type_statement(s),
+        Statement::Let(s) => v.visit_let_statement(s),
+        Statement::Destructure(s) => v.visit_destructure_statement(s),
+        Statement::Export(s) => v.visit_export_statement(s),
+    }
+}
+
+/// Walks the children of an include statement.
+pub fn walk_include_statement<'a, V: Visitor<'a> + ?Sized>(
+    v: &mut V,
+    statement: &'a IncludeStatement<'a>,
+) {
+    v.visit_string(&statement.path);
+    if let Some(doc) = &statement.document {
+        v.visit_document(doc);
+    }
+}
+
+/// Walks the children of an import statement.
+pub fn walk_import_statement<'a, V: Visitor<'a> + ?Sized>(
+    v: &mut V,
+    statement: &'a ImportStatement<'a>,
+) {
+    v.visit_ident(&statement.id);
+    if let Some(name) = &statement.name {
+        v.visit_extern_name(name);
+    }
+    v.visit_import_type(&statement.ty);
+}
+
+/// Walks the children of the type of an import statement.
+pub fn walk_import_type<'a, V: Visitor<'a> + ?Sized>(v: &mut V, ty: &'a ImportType<'a>) {
+    match ty {
+        ImportType::Package(p) => v.visit_package_path(p),
+        ImportType::Func(f) => v.visit_func_type(f),
+        ImportType::Interface(i) => v.visit_inline_interface(i),
+        ImportType::Ident(id) => v.visit_ident(id),
+    }
+}
+
+/// Walks the children of an extern name.
+pub fn walk_extern_name<'a, V: Visitor<'a> + ?Sized>(v: &mut V, name: &'a ExternName<'a>) {
+    match name {
+        ExternName::Ident(id) => v.visit_ident(id),
+        ExternName::String(s) => v.visit_string(s),
+    }
+}
+
+/// Walks the children of a type statement.
+pub fn walk_type_statement<'a, V: Visitor<'a> + ?Sized>(
+    v: &mut V,
+    statement: &'a TypeStatement<'a>,
+) {
+    match statement {
+        TypeStatement::Interface(i) => v.visit_interface_decl(i),
+        TypeStatement::World(w) => v.visit_world_decl(w),
+        TypeStatement::Type(t) => v.visit_type_decl(t),
+    }
+}
+
+/// Walks the children of a let statement.
+pub fn walk_let_statement<'a, V: Visitor<'a> + ?Sized>(v: &mut V, statement: &'a LetStatement<'a>) {
+    v.visit_ident(&statement.id);
+    v.visit_expr(&statement.expr);
+}
+
+/// Walks the children of a destructuring let statement.
+pub fn walk_destructure_statement<'a, V: Visitor<'a> + ?Sized>(
+    v: &mut V,
+    statement: &'a DestructureStatement<'a>,
+) {
+    for item in &statement.items {
+        v.visit_destructure_item(item);
+    }
+    v.visit_expr(&statement.expr);
+}
+
+/// Walks the children of an item of a destructuring let statement.
+pub fn walk_destructure_item<'a, V: Visitor<'a> + ?Sized>(
+    v: &mut V,
+    item: &'a DestructureItem<'a>,
+) {
+    v.visit_ident(&item.id);
+    if let Some(id) = &item.as_id {
+        v.visit_ident(id);
+    }
+}
+
+/// Walks the children of an export statement.
+pub fn walk_export_statement<'a, V: Visitor<'a> + ?Sized>(
+    v: &mut V,
+    statement: &'a ExportStatement<'a>,
+) {
+    v.visit_expr(&statement.expr);
+    v.visit_export_options(&statement.options);
+}
+
+/// Walks the children of the options of an export statement.
+pub fn walk_export_options<'a, V: Visitor<'a> + ?Sized>(v: &mut V, options: &'a ExportOptions<'a>) {
+    match options {
+        ExportOptions::None | ExportOptions::Spread(_) => {}
+        ExportOptions::Rename(name) => v.visit_extern_name(name),
+    }
+}
+
+/// Walks the children of an expression.
+pub fn walk_expr<'a, V: Visitor<'a> + ?Sized>(v: &mut V, expr: &'a Expr<'a>) {
+    v.visit_primary_expr(&expr.primary);
+    for postfix in &expr.postfix {
+        v.visit_postfix_expr(postfix);
+    }
+}
+
+/// Walks the children of a primary expression.
+pub fn walk_primary_expr<'a, V: Visitor<'a> + ?Sized>(v: &mut V, expr: &'a PrimaryExpr<'a>) {
+    match expr {
+        PrimaryExpr::New(e) => v.visit_new_expr(e),
+        PrimaryExpr::Nested(e) => v.visit_nested_expr(e),
+        PrimaryExpr::Ident(id) => v.visit_ident(id),
+    }
+}
+
+/// Walks the children of a `new` expression.
+pub fn walk_new_expr<'a, V: Visitor<'a> + ?Sized>(v: &mut V, expr: &'a NewExpr<'a>) {
+    v.visit_package_name(&expr.package);
+    for arg in &expr.arguments {
+        v.visit_instantiation_argument(arg);
+    }
+}
+
+/// Walks the children of an instantiation argument.
+pub fn walk_instantiation_argument<'a, V: Visitor<'a> + ?Sized>(
+    v: &mut V,
+    arg: &'a InstantiationArgument<'a>,
+) {
+    match arg {
+        InstantiationArgument::Inferred(id) | InstantiationArgument::Spread(id) => {
+            v.visit_ident(id)
+        }
+        InstantiationArgument::Named(a) => v.visit_named_instantiation_argument(a),
+        InstantiationArgument::Fill(_) => {}
+    }
+}
+
+/// Walks the children of a named instantiation argument.
+pub fn walk_named_instantiation_argument<'a, V: Visitor<'a> + ?Sized>(
+    v: &mut V,
+    arg: &'a NamedInstantiationArgument<'a>,
+) {
+    v.visit_instantiation_argument_name(&arg.name);
+    v.visit_expr(&arg.expr);
+}
+
+/// Walks the children of the name of an instantiation argument.
+pub fn walk_instantiation_argument_name<'a, V: Visitor<'a> + ?Sized>(
+    v: &mut V,
+    name: &'a InstantiationArgumentName<'a>,
+) {
+    match name {
+        InstantiationArgumentName::Ident(id) => v.visit_ident(id),
+        InstantiationArgumentName::String(s) => v.visit_string(s),
+    }
+}
+
+/// Walks the children of a nested expression.
+pub fn walk_nested_expr<'a, V: Visitor<'a> + ?Sized>(v: &mut V, expr: &'a NestedExpr<'a>) {
+    v.visit_expr(&expr.inner);
+}
+
+/// Walks the children of a postfix expression.
+pub fn walk_postfix_expr<'a, V: Visitor<'a> + ?Sized>(v: &mut V, expr: &'a PostfixExpr<'a>) {
+    match expr {
+        PostfixExpr::Access(e) => v.visit_access_expr(e),
+        PostfixExpr::NamedAccess(e) => v.visit_named_access_expr(e),
+    }
+}
+
+/// Walks the children of an access expression.
+pub fn walk_access_expr<'a, V: Visitor<'a> + ?Sized>(v: &mut V, expr: &'a AccessExpr<'a>) {
+    v.visit_ident(&expr.id);
+}
+
+/// Walks the children of a named access expression.
+pub fn walk_named_access_expr<'a, V: Visitor<'a> + ?Sized>(
+    v: &mut V,
+    expr: &'a NamedAccessExpr<'a>,
+) {
+    v.visit_string(&expr.string);
+}
+
+/// Walks the children of a type declaration.
+pub fn walk_type_decl<'a, V: Visitor<'a> + ?Sized>(v: &mut V, decl: &'a TypeDecl<'a>) {
+    match decl {
+        TypeDecl::Variant(d) => v.visit_variant_decl(d),
+        TypeDecl::Record(d) => v.visit_record_decl(d),
+        TypeDecl::Flags(d) => v.visit_flags_decl(d),
+        TypeDecl::Enum(d) => v.visit_enum_decl(d),
+        TypeDecl::Alias(d) => v.visit_type_alias(d),
+    }
+}
+
+/// Walks the children of a type declaration in an interface or world.
+pub fn walk_item_type_decl<'a, V: Visitor<'a> + ?Sized>(v: &mut V, decl: &'a ItemTypeDecl<'a>) {
+    match decl {
+        ItemTypeDecl::Resource(d) => v.visit_resource_decl(d),
+        ItemTypeDecl::Variant(d) => v.visit_variant_decl(d),
+        ItemTypeDecl::Record(d) => v.visit_record_decl(d),
+        ItemTypeDecl::Flags(d) => v.visit_flags_decl(d),
+        ItemTypeDecl::Enum(d) => v.visit_enum_decl(d),
+        ItemTypeDecl::Alias(d) => v.visit_type_alias(d),
+    }
+}
+
+/// Walks the children of a resource declaration.
+pub fn walk_resource_decl<'a, V: Visitor<'a> + ?Sized>(v: &mut V, decl: &'a ResourceDecl<'a>) {
+    v.visit_ident(&decl.id);
+    for method in &decl.methods {
+        v.visit_resource_method(method);
+    }
+}
+
+/// Walks the children of a resource method.
+pub fn walk_resource_method<'a, V: Visitor<'a> + ?Sized>(
+    v: &mut V,
+    method: &'a ResourceMethod<'a>,
+) {
+    match method {
+        ResourceMethod::Constructor(c) => {
+            for param in &c.params {
+                v.visit_named_type(param);
+            }
+        }
+        ResourceMethod::Method(m) => {
+            v.visit_ident(&m.id);
+            v.visit_func_type(&m.ty);
+        }
+    }
+}
+
+/// Walks the children of a variant declaration.
+pub fn walk_variant_decl<'a, V: Visitor<'a> + ?Sized>(v: &mut V, decl: &'a VariantDecl<'a>) {
+    v.visit_ident(&decl.id);
+    for case in &decl.cases {
+        v.visit_variant_case(case);
+    }
+}
+
+/// Walks the children of a variant case.
+pub fn walk_variant_case<'a, V: Visitor<'a> + ?Sized>(v: &mut V, case: &'a VariantCase<'a>) {
+    v.visit_ident(&case.id);
+    if let Some(ty) = &case.ty {
+        v.visit_type(ty);
+    }
+}
+
+/// Walks the children of a record declaration.
+pub fn walk_record_decl<'a, V: Visitor<'a> + ?Sized>(v: &mut V, decl: &'a RecordDecl<'a>) {
+    v.visit_ident(&decl.id);
+    for field in &decl.fields {
+        v.visit_field(field);
+    }
+}
+
+/// Walks the children of a record field.
+pub fn walk_field<'a, V: Visitor<'a> + ?Sized>(v: &mut V, field: &'a Field<'a>) {
+    v.visit_ident(&field.id);
+    v.visit_type(&field.ty);
+}
+
+/// Walks the children of a flags declaration.
+pub fn walk_flags_decl<'a, V: Visitor<'a> + ?Sized>(v: &mut V, decl: &'a FlagsDecl<'a>) {
+    v.visit_ident(&decl.id);
+    for flag in &decl.flags {
+        v.visit_flag(flag);
+    }
+}
+
+/// Walks the children of a flag.
+pub fn walk_flag<'a, V: Visitor<'a> + ?Sized>(v: &mut V, flag: &'a Flag<'a>) {
+    v.visit_ident(&flag.id);
+}
+
+/// Walks the children of an enum declaration.
+pub fn walk_enum_decl<'a, V: Visitor<'a> + ?Sized>(v: &mut V, decl: &'a EnumDecl<'a>) {
+    v.visit_ident(&decl.id);
+    for case in &decl.cases {
+        v.visit_enum_case(case);
+    }
+}
+
+/// Walks the children of an enum case.
+pub fn walk_enum_case<'a, V: Visitor<'a> + ?Sized>(v: &mut V, case: &'a EnumCase<'a>) {
+    v.visit_ident(&case.id);
+}
+
+/// Walks the children of a type alias.
+pub fn walk_type_alias<'a, V: Visitor<'a> + ?Sized>(v: &mut V, alias: &'a TypeAlias<'a>) {
+    v.visit_ident(&alias.id);
+    match &alias.kind {
+        TypeAliasKind::Func(f) => v.visit_func_type(f),
+        TypeAliasKind::Type(t) => v.visit_type(t),
+    }
+}
+
+/// Walks the children of a function type reference.
+pub fn walk_func_type_ref<'a, V: Visitor<'a> + ?Sized>(v: &mut V, ty: &'a FuncTypeRef<'a>) {
+    match ty {
+        FuncTypeRef::Func(f) => v.visit_func_type(f),
+        FuncTypeRef::Ident(id) => v.visit_ident(id),
+    }
+}
+
+/// Walks the children of a function type.
+pub fn walk_func_type<'a, V: Visitor<'a> + ?Sized>(v: &mut V, ty: &'a FuncType<'a>) {
+    for param in &ty.params {
+        v.visit_named_type(param);
+    }
+    v.visit_result_list(&ty.results);
+}
+
+/// Walks the children of the results of a function type.
+pub fn walk_result_list<'a, V: Visitor<'a> + ?Sized>(v: &mut V, results: &'a ResultList<'a>) {
+    match results {
+        ResultList::Empty => {}
+        ResultList::Scalar(ty) => v.visit_type(ty),
+        ResultList::Named(results) => {
+            for result in results {
+                v.visit_named_type(result);
+            }
+        }
+    }
+}
+
+/// Walks the children of a named type.
+pub fn walk_named_type<'a, V: Visitor<'a> + ?Sized>(v: &mut V, ty: &'a NamedType<'a>) {
+    v.visit_ident(&ty.id);
+    v.visit_type(&ty.ty);
+}
+
+/// Walks the children of a type.
+pub fn walk_type<'a, V: Visitor<'a> + ?Sized>(v: &mut V, ty: &'a Type<'a>) {
+    match ty {
+        Type::U8(_)
+        | Type::S8(_)
+        | Type::U16(_)
+        | Type::S16(_)
+        | Type::U32(_)
+        | Type::S32(_)
+        | Type::U64(_)
+        | Type::S64(_)
+        | Type::Float32(_)
+        | Type::Float64(_)
+        | Type::Char(_)
+        | Type::Bool(_)
+        | Type::String(_) => {}
+        Type::Tuple(types, _) => {
+            for ty in types {
+                v.visit_type(ty);
+            }
+        }
+        Type::List(ty, _) | Type::Option(ty, _) => v.visit_type(ty),
+        Type::Result { ok, err, .. } => {
+            if let Some(ok) = ok {
+                v.visit_type(ok);
+            }
+            if let Some(err) = err {
+                v.visit_type(err);
+            }
+        }
+        Type::Borrow(id, _) | Type::Ident(id) => v.visit_ident(id),
+    }
+}
+
+/// Walks the children of an interface declaration.
+pub fn walk_interface_decl<'a, V: Visitor<'a> + ?Sized>(v: &mut V, decl: &'a InterfaceDecl<'a>) {
+    v.visit_ident(&decl.id);
+    for item in &decl.items {
+        v.visit_interface_item(item);
+    }
+}
+
+/// Walks the children of an interface item.
+pub fn walk_interface_item<'a, V: Visitor<'a> + ?Sized>(v: &mut V, item: &'a InterfaceItem<'a>) {
+    match item {
+        InterfaceItem::Use(u) => v.visit_use(u),
+        InterfaceItem::Type(t) => v.visit_item_type_decl(t),
+        InterfaceItem::Export(e) => v.visit_interface_export(e),
+    }
+}
+
+/// Walks the children of a use item.
+pub fn walk_use<'a, V: Visitor<'a> + ?Sized>(v: &mut V, item: &'a Use<'a>) {
+    v.visit_use_path(&item.path);
+    for item in &item.items {
+        v.visit_use_item(item);
+    }
+}
+
+/// Walks the children of the path of a use item.
+pub fn walk_use_path<'a, V: Visitor<'a> + ?Sized>(v: &mut V, path: &'a UsePath<'a>) {
+    match path {
+        UsePath::Package(p) => v.visit_package_path(p),
+        UsePath::Ident(id) => v.visit_ident(id),
+    }
+}
+
+/// Walks the children of a used type.
+pub fn walk_use_item<'a, V: Visitor<'a> + ?Sized>(v: &mut V, item: &'a UseItem<'a>) {
+    v.visit_ident(&item.id);
+    if let Some(id) = &item.as_id {
+        v.visit_ident(id);
+    }
+}
+
+/// Walks the children of an interface export.
+pub fn walk_interface_export<'a, V: Visitor<'a> + ?Sized>(
+    v: &mut V,
+    export: &'a InterfaceExport<'a>,
+) {
+    v.visit_ident(&export.id);
+    v.visit_func_type_ref(&export.ty);
+}
+
+/// Walks the children of an inline interface.
+pub fn walk_inline_interface<'a, V: Visitor<'a> + ?Sized>(
+    v: &mut V,
+    interface: &'a InlineInterface<'a>,
+) {
+    for item in &interface.items {
+        v.visit_interface_item(item);
+    }
+}
+
+/// Walks the children of a world declaration.
+pub fn walk_world_decl<'a, V: Visitor<'a> + ?Sized>(v: &mut V, decl: &'a WorldDecl<'a>) {
+    v.visit_ident(&decl.id);
+    for item in &decl.items {
+        v.visit_world_item(item);
+    }
+}
+
+/// Walks the children of a world item.
+pub fn walk_world_item<'a, V: Visitor<'a> + ?Sized>(v: &mut V, item: &'a WorldItem<'a>) {
+    match item {
+        WorldItem::Use(u) => v.visit_use(u),
+        WorldItem::Type(t) => v.visit_item_type_decl(t),
+        WorldItem::Import(i) => v.visit_world_item_path(&i.path),
+        WorldItem::Export(e) => v.visit_world_item_path(&e.path),
+        WorldItem::Include(i) => v.visit_world_include(i),
+    }
+}
+
+/// Walks the children of the path of a world import or export.
+pub fn walk_world_item_path<'a, V: Visitor<'a> + ?Sized>(v: &mut V, path: &'a WorldItemPath<'a>) {
+    match path {
+        WorldItemPath::Named(n) => v.visit_named_world_item(n),
+        WorldItemPath::Package(p) => v.visit_package_path(p),
+        WorldItemPath::Ident(id) => v.visit_ident(id),
+    }
+}
+
+/// Walks the children of a named world item.
+pub fn walk_named_world_item<'a, V: Visitor<'a> + ?Sized>(v: &mut V, item: &'a NamedWorldItem<'a>) {
+    v.visit_ident(&item.id);
+    v.visit_extern_type(&item.ty);
+}
+
+/// Walks the children of the type of a named world item.
+pub fn walk_extern_type<'a, V: Visitor<'a> + ?Sized>(v: &mut V, ty: &'a ExternType<'a>) {
+    match ty {
+        ExternType::Ident(id) => v.visit_ident(id),
+        ExternType::Func(f) => v.visit_func_type(f),
+        ExternType::Interface(i) => v.visit_inline_interface(i),
+    }
+}
+
+/// Walks the children of a world include.
+pub fn walk_world_include<'a, V: Visitor<'a> + ?Sized>(v: &mut V, include: &'a WorldInclude<'a>) {
+    v.visit_world_ref(&include.world);
+    for item in &include.with {
+        v.visit_world_include_item(item);
+    }
+}
+
+/// Walks the children of a reference to an included world.
+pub fn walk_world_ref<'a, V: Visitor<'a> + ?Sized>(v: &mut V, world: &'a WorldRef<'a>) {
+    match world {
+        WorldRef::Ident(id) => v.visit_ident(id),
+        WorldRef::Package(p) => v.visit_package_path(p),
+    }
+}
+
+/// Walks the children of a renamed item of a world include.
+pub fn walk_world_include_item<'a, V: Visitor<'a> + ?Sized>(
+    v: &mut V,
+    item: &'a WorldIncludeItem<'a>,
+) {
+    v.visit_ident(&item.from);
+    v.visit_ident(&item.to);
+}
+
+/// A visitor of the nodes of a document that may modify them.
+///
+/// Included documents are not visited.
+pub trait VisitMut<'a> {
+    /// Visits a document.
+    fn visit_document_mut(&mut self, doc: &mut Document<'a>) {
+        walk_document_mut(self, doc)
+    }
+
+    /// Visits a package directive.
+    fn visit_package_directive_mut(&mut self, directive: &mut PackageDirective<'a>) {
+        walk_package_directive_mut(self, directive)
+    }
+
+    /// Visits a statement.
+    fn visit_statement_mut(&mut self, statement: &mut Statement<'a>) {
+        walk_statement_mut(self, statement)
+    }
+
+    /// Visits an include statement.
+    fn visit_include_statement_mut(&mut self, statement: &mut IncludeStatement<'a>) {
+        walk_include_statement_mut(self, statement)
+    }
+
+    /// Visits an import statement.
+    fn visit_import_statement_mut(&mut self, statement: &mut ImportStatement<'a>) {
+        walk_import_statement_mut(self, statement)
+    }
+
+    /// Visits the type of an import statement.
+    fn visit_import_type_mut(&mut self, ty: &mut ImportType<'a>) {
+        walk_import_type_mut(self, ty)
+    }
+
+    /// Visits an extern name.
+    fn visit_extern_name_mut(&mut self, name: &mut ExternName<'a>) {
+        walk_extern_name_mut(self, name)
+    }
+
+    /// Visits a type statement.
+    fn visit_type_statement_mut(&mut self, statement: &mut TypeStatement<'a>) {
+        walk_type_statement_mut(self, statement)
+    }
+
+    /// Visits a let statement.
+    fn visit_let_statement_mut(&mut self, statement: &mut LetStatement<'a>) {
+        walk_let_statement_mut(self, statement)
+    }
+
+    /// Visits a destructuring let statement.
+    fn visit_destructure_statement_mut(&mut self, statement: &mut DestructureStatement<'a>) {
+        walk_destructure_statement_mut(self, statement)
+    }
+
+    /// Visits an item of a destructuring let statement.
+    fn visit_destructure_item_mut(&mut self, item: &mut DestructureItem<'a>) {
+        walk_destructure_item_mut(self, item)
+    }
+
+    /// Visits an export statement.
+    fn visit_export_statement_mut(&mut self, statement: &mut ExportStatement<'a>) {
+        walk_export_statement_mut(self, statement)
+    }
+
+    /// Visits the options of an export statement.
+    fn visit_export_options_mut(&mut self, options: &mut ExportOptions<'a>) {
+        walk_export_options_mut(self, options)
+    }
+
+    /// Visits an expression.
+    fn visit_expr_mut(&mut self, expr: &mut Expr<'a>) {
+        walk_expr_mut(self, expr)
+    }
+
+    /// Visits a primary expression.
+    fn visit_primary_expr_mut(&mut self, expr: &mut PrimaryExpr<'a>) {
+        walk_primary_expr_mut(self, expr)
+    }
+
+    /// Visits a `new` expression.
+    fn visit_new_expr_mut(&mut self, expr: &mut NewExpr<'a>) {
+        walk_new_expr_mut(self, expr)
+    }
+
+    /// Visits an instantiation argument.
+    fn visit_instantiation_argument_mut(&mut self, arg: &mut InstantiationArgument<'a>) {
+        walk_instantiation_argument_mut(self, arg)
+    }
+
+    /// Visits a named instantiation argument.
+    fn visit_named_instantiation_argument_mut(&mut self, arg: &mut NamedInstantiationArgument<'a>) {
+        walk_named_instantiation_argument_mut(self, arg)
+    }
+
+    /// Visits the name of an instantiation argument.
+    fn visit_instantiation_argument_name_mut(&mut self, name: &mut InstantiationArgumentName<'a>) {
+        walk_instantiation_argument_name_mut(self, name)
+    }
+
+    /// Visits a nested expression.
+    fn visit_nested_expr_mut(&mut self, expr: &mut NestedExpr<'a>) {
+        walk_nested_expr_mut(self, expr)
+    }
+
+    /// Visits a postfix expression.
+    fn visit_postfix_expr_mut(&mut self, expr: &mut PostfixExpr<'a>) {
+        walk_postfix_expr_mut(self, expr)
+    }
+
+    /// Visits an access expression.
+    fn visit_access_expr_mut(&mut self, expr: &mut AccessExpr<'a>) {
+        walk_access_expr_mut(self, expr)
+    }
+
+    /// Visits a named access expression.
+    fn visit_named_access_expr_mut(&mut self, expr: &mut NamedAccessExpr<'a>) {
+        walk_named_access_expr_mut(self, expr)
+    }
+
+    /// Visits a type declaration.
+    fn visit_type_decl_mut(&mut self, decl: &mut TypeDecl<'a>) {
+        walk_type_decl_mut(self, decl)
+    }
+
+    /// Visits a type declaration in an interface or world.
+    fn visit_item_type_decl_mut(&mut self, decl: &mut ItemTypeDecl<'a>) {
+        walk_item_type_decl_mut(self, decl)
+    }
+
+    /// Visits a resource declaration.
+    fn visit_resource_decl_mut(&mut self, decl: &mut ResourceDecl<'a>) {
+        walk_resource_decl_mut(self, decl)
+    }
+
+    /// Visits a resource method.
+    fn visit_resource_method_mut(&mut self, method: &mut ResourceMethod<'a>) {
+        walk_resource_method_mut(self, method)
+    }
+
+    /// Visits a variant declaration.
+    fn visit_variant_decl_mut(&mut self, decl: &mut VariantDecl<'a>) {
+        walk_variant_decl_mut(self, decl)
+    }
+
+    /// Visits a variant case.
+    fn visit_variant_case_mut(&mut self, case: &mut VariantCase<'a>) {
+        walk_variant_case_mut(self, case)
+    }
+
+    /// Visits a record declaration.
+    fn visit_record_decl_mut(&mut self, decl: &mut RecordDecl<'a>) {
+        walk_record_decl_mut(self, decl)
+    }
+
+    /// Visits a record field.
+    fn visit_field_mut(&mut self, field: &mut Field<'a>) {
+        walk_field_mut(self, field)
+    }
+
+    /// Visits a flags declaration.
+    fn visit_flags_decl_mut(&mut self, decl: &mut FlagsDecl<'a>) {
+        walk_flags_decl_mut(self, decl)
+    }
+
+    /// Visits a flag.
+    fn visit_flag_mut(&mut self, flag: &mut Flag<'a>) {
+        walk_flag_mut(self, flag)
+    }
+
+    /// Visits an enum declaration.
+    fn visit_enum_decl_mut(&mut self, decl: &mut EnumDecl<'a>) {
+        walk_enum_decl_mut(self, decl)
+    }
+
+    /// Visits an enum case.
+    fn visit_enum_case_mut(&mut self, case: &mut EnumCase<'a>) {
+        walk_enum_case_mut(self, case)
+    }
+
+    /// Visits a type alias.
+    fn visit_type_alias_mut(&mut self, alias: &mut TypeAlias<'a>) {
+        walk_type_alias_mut(self, alias)
+    }
+
+    /// Visits a function type reference.
+    fn visit_func_type_ref_mut(&mut self, ty: &mut FuncTypeRef<'a>) {
+        walk_func_type_ref_mut(self, ty)
+    }
+
+    /// Visits a function type.
+    fn visit_func_type_mut(&mut self, ty: &mut FuncType<'a>) {
+        walk_func_type_mut(self, ty)
+    }
+
+    /// Visits the results of a function type.
+    fn visit_result_list_mut(&mut self, results: &mut ResultList<'a>) {
+        walk_result_list_mut(self, results)
+    }
+
+    /// Visits a named type.
+    fn visit_named_type_mut(&mut self, ty: &mut NamedType<'a>) {
+        walk_named_type_mut(self, ty)
+    }
+
+    /// Visits a type.
+    fn visit_type_mut(&mut self, ty: &mut Type<'a>) {
+        walk_type_mut(self, ty)
+    }
+
+    /// Visits an interface declaration.
+    fn visit_interface_decl_mut(&mut self, decl: &mut InterfaceDecl<'a>) {
+        walk_interface_decl_mut(self, decl)
+    }
+
+    /// Visits an interface item.
+    fn visit_interface_item_mut(&mut self, item: &mut InterfaceItem<'a>) {
+        walk_interface_item_mut(self, item)
+    }
+
+    /// Visits a use item.
+    fn visit_use_mut(&mut self, item: &mut Use<'a>) {
+        walk_use_mut(self, item)
+    }
+
+    /// Visits the path of a use item.
+    fn visit_use_path_mut(&mut self, path: &mut UsePath<'a>) {
+        walk_use_path_mut(self, path)
+    }
+
+    /// Visits a used type.
+    fn visit_use_item_mut(&mut self, item: &mut UseItem<'a>) {
+        walk_use_item_mut(self, item)
+    }
+
+    /// Visits an interface export.
+    fn visit_interface_export_mut(&mut self, export: &mut InterfaceExport<'a>) {
+        walk_interface_export_mut(self, export)
+    }
+
+    /// Visits an inline interface.
+    fn visit_inline_interface_mut(&mut self, interface: &mut InlineInterface<'a>) {
+        walk_inline_interface_mut(self, interface)
+    }
+
+    /// Visits a world declaration.
+    fn visit_world_decl_mut(&mut self, decl: &mut WorldDecl<'a>) {
+        walk_world_decl_mut(self, decl)
+    }
+
+    /// Visits a world item.
+    fn visit_world_item_mut(&mut self, item: &mut WorldItem<'a>) {
+        walk_world_item_mut(self, item)
+    }
+
+    /// Visits the path of a world import or export.
+    fn visit_world_item_path_mut(&mut self, path: &mut WorldItemPath<'a>) {
+        walk_world_item_path_mut(self, path)
+    }
+
+    /// Visits a named world item.
+    fn visit_named_world_item_mut(&mut self, item: &mut NamedWorldItem<'a>) {
+        walk_named_world_item_mut(self, item)
+    }
+
+    /// Visits the type of a named world item.
+    fn visit_extern_type_mut(&mut self, ty: &mut ExternType<'a>) {
+        walk_extern_type_mut(self, ty)
+    }
+
+    /// Visits a world include.
+    fn visit_world_include_mut(&mut self, include: &mut WorldInclude<'a>) {
+        walk_world_include_mut(self, include)
+    }
+
+    /// Visits a reference to an included world.
+    fn visit_world_ref_mut(&mut self, world: &mut WorldRef<'a>) {
+        walk_world_ref_mut(self, world)
+    }
+
+    /// Visits a renamed item of a world include.
+    fn visit_world_include_item_mut(&mut self, item: &mut WorldIncludeItem<'a>) {
+        walk_world_include_item_mut(self, item)
+    }
+
+    /// Visits a package name.
+    fn visit_package_name_mut(&mut self, _name: &mut PackageName<'a>) {}
+
+    /// Visits a package path.
+    fn visit_package_path_mut(&mut self, _path: &mut PackagePath<'a>) {}
+
+    /// Visits an identifier.
+    fn visit_ident_mut(&mut self, _ident: &mut Ident<'a>) {}
+
+    /// Visits a string.
+    fn visit_string_mut(&mut self, _string: &mut String<'a>) {}
+}
+
+/// Walks the children of a document.
+pub fn walk_document_mut<'a, V: VisitMut<'a> + ?Sized>(v: &mut V, doc: &mut Document<'a>) {
+    v.visit_package_directive_mut(&mut doc.directive);
+    for statement in &mut doc.statements {
+        v.visit_statement_mut(statement);
+    }
+}
+
+/// Walks the children of a package directive.
+pub fn walk_package_directive_mut<'a, V: VisitMut<'a> + ?Sized>(
+    v: &mut V,
+    directive: &mut PackageDirective<'a>,
+) {
+    v.visit_package_name_mut(&mut directive.package);
+    if let Some(targets) = &mut directive.targets {
+        v.visit_package_path_mut(targets);
+    }
+}
+
+/// Walks the children of a statement.
+pub fn walk_statement_mut<'a, V: VisitMut<'a> + ?Sized>(v: &mut V, statement: &mut Statement<'a>) {
+    match statement {
+        Statement::Include(s) => v.visit_include_statement_mut(s),
+        Statement::Import(s) => v.visit_import_statement_mut(s),
+        Statement::Type(s) => v.visit_type_statement_mut(s),
+        Statement::Let(s) => v.visit_let_statement_mut(s),
+        Statement::Destructure(s) => v.visit_destructure_statement_mut(s),
+        Statement::Export(s) => v.visit_export_statement_mut(s),
+    }
+}
+
+/// Walks the children of an include statement.
+///
+/// The included document is shared and is therefore not visited.
+pub fn walk_include_statement_mut<'a, V: VisitMut<'a> + ?Sized>(
+    v: &mut V,
+    statement: &mut IncludeStatement<'a>,
+) {
+    v.visit_string_mut(&mut statement.path);
+}
+
+/// Walks the children of an import statement.
+pub fn walk_import_statement_mut<'a, V: VisitMut<'a> + ?Sized>(
+    v: &mut V,
+    statement: &mut ImportStatement<'a>,
+) {
+    v.visit_ident_mut(&mut statement.id);
+    if let Some(name) = &mut statement.name {
+        v.visit_extern_name_mut(name);
+    }
+    v.visit_import_type_mut(&mut statement.ty);
+}
+
+/// Walks the children of the type of an import statement.
+pub fn walk_import_type_mut<'a, V: VisitMut<'a> + ?Sized>(v: &mut V, ty: &mut ImportType<'a>) {
+    match ty {
+        ImportType::Package(p) => v.visit_package_path_mut(p),
+        ImportType::Func(f) => v.visit_func_type_mut(f),
+        ImportType::Interface(i) => v.visit_inline_interface_mut(i),
+        ImportType::Ident(id) => v.visit_ident_mut(id),
+    }
+}
+
+/// Walks the children of an extern name.
+pub fn walk_extern_name_mut<'a, V: VisitMut<'a> + ?Sized>(v: &mut V, name: &mut ExternName<'a>) {
+    match name {
+        ExternName::Ident(id) => v.visit_ident_mut(id),
+        ExternName::String(s) => v.visit_string_mut(s),
+    }
+}
+
+/// Walks the children of a type statement.
+pub fn walk_type_statement_mut<'a, V: VisitMut<'a> + ?Sized>(
+    v: &mut V,
+    statement: &mut TypeStatement<'a>,
+) {
+    match statement {
+        TypeStatement::Interface(i) => v.visit_interface_decl_mut(i),
+        TypeStatement::World(w) => v.visit_world_decl_mut(w),
+        TypeStatement::Type(t) => v.visit_type_decl_mut(t),
+    }
+}
+
+/// Walks the children of a let statement.
+pub fn walk_let_statement_mut<'a, V: VisitMut<'a> + ?Sized>(
+    v: &mut V,
+    statement: &mut LetStatement<'a>,
+) {
+    v.visit_ident_mut(&mut statement.id);
+    v.visit_expr_mut(&mut statement.expr);
+}
+
+/// Walks the children of a destructuring let statement.
+pub fn walk_destructure_statement_mut<'a, V: VisitMut<'a> + ?Sized>(
+    v: &mut V,
+    statement: &mut DestructureStatement<'a>,
+) {
+    for item in &mut statement.items {
+        v.visit_destructure_item_mut(item);
+    }
+    v.visit_expr_mut(&mut statement.expr);
+}
+
+/// Walks the children of an item of a destructuring let statement.
+pub fn walk_destructure_item_mut<'a, V: VisitMut<'a> + ?Sized>(
+    v: &mut V,
+    item: &mut DestructureItem<'a>,
+) {
+    v.visit_ident_mut(&mut item.id);
+    if let Some(id) = &mut item.as_id {
+        v.visit_ident_mut(id);
+    }
+}
+
+/// Walks the children of an export statement.
+pub fn walk_export_statement_mut<'a, V: VisitMut<'a> + ?Sized>(
+    v: &mut V,
+    statement: &mut ExportStatement<'a>,
+) {
+    v.visit_expr_mut(&mut statement.expr);
+    v.visit_export_options_mut(&mut statement.options);
+}
+
+/// Walks the children of the options of an export statement.
+pub fn walk_export_options_mut<'a, V: VisitMut<'a> + ?Sized>(
+    v: &mut V,
+    options: &mut ExportOptions<'a>,
+) {
+    match options {
+        ExportOptions::None | ExportOptions::Spread(_) => {}
+        ExportOptions::Rename(name) => v.visit_extern_name_mut(name),
+    }
+}
+
+/// Walks the children of an expression.
+pub fn walk_expr_mut<'a, V: VisitMut<'a> + ?Sized>(v: &mut V, expr: &mut Expr<'a>) {
+    v.visit_primary_expr_mut(&mut expr.primary);
+    for postfix in &mut expr.postfix {
+        v.visit_postfix_expr_mut(postfix);
+    }
+}
+
+/// Walks the children of a primary expression.
+pub fn walk_primary_expr_mut<'a, V: VisitMut<'a> + ?Sized>(v: &mut V, expr: &mut PrimaryExpr<'a>) {
+    match expr {
+        PrimaryExpr::New(e) => v.visit_new_expr_mut(e),
+        PrimaryExpr::Nested(e) => v.visit_nested_expr_mut(e),
+        PrimaryExpr::Ident(id) => v.visit_ident_mut(id),
+    }
+}
+
+/// Walks the children of a `new` expression.
+pub fn walk_new_expr_mut<'a, V: VisitMut<'a> + ?Sized>(v: &mut V, expr: &mut NewExpr<'a>) {
+    v.visit_package_name_mut(&mut expr.package);
+    for arg in &mut expr.arguments {
+        v.visit_instantiation_argument_mut(arg);
+    }
+}
+
+/// Walks the children of an instantiation argument.
+pub fn walk_instantiation_argument_mut<'a, V: VisitMut<'a> + ?Sized>(
+    v: &mut V,
+    arg: &mut InstantiationArgument<'a>,
+) {
+    match arg {
+        InstantiationArgument::Inferred(id) | InstantiationArgument::Spread(id) => {
+            v.visit_ident_mut(id)
+        }
+        InstantiationArgument::Named(a) => v.visit_named_instantiation_argument_mut(a),
+        InstantiationArgument::Fill(_) => {}
+    }
+}
+
+/// Walks the children of a named instantiation argument.
+pub fn walk_named_instantiation_argument_mut<'a, V: VisitMut<'a> + ?Sized>(
+    v: &mut V,
+    arg: &mut NamedInstantiationArgument<'a>,
+) {
+    v.visit_instantiation_argument_name_mut(&mut arg.name);
+    v.visit_expr_mut(&mut arg.expr);
+}
+
+/// Walks the children of the name of an instantiation argument.
+pub fn walk_instantiation_argument_name_mut<'a, V: VisitMut<'a> + ?Sized>(
+    v: &mut V,
+    name: &mut InstantiationArgumentName<'a>,
+) {
+    match name {
+        InstantiationArgumentName::Ident(id) => v.visit_ident_mut(id),
+        InstantiationArgumentName::String(s) => v.visit_string_mut(s),
+    }
+}
+
+/// Walks the children of a nested expression.
+pub fn walk_nested_expr_mut<'a, V: VisitMut<'a> + ?Sized>(v: &mut V, expr: &mut NestedExpr<'a>) {
+    v.visit_expr_mut(&mut expr.inner);
+}
+
+/// Walks the children of a postfix expression.
+pub fn walk_postfix_expr_mut<'a, V: VisitMut<'a> + ?Sized>(v: &mut V, expr: &mut PostfixExpr<'a>) {
+    match expr {
+        PostfixExpr::Access(e) => v.visit_access_expr_mut(e),
+        PostfixExpr::NamedAccess(e) => v.visit_named_access_expr_mut(e),
+    }
+}
+
+/// Walks the children of an access expression.
+pub fn walk_access_expr_mut<'a, V: VisitMut<'a> + ?Sized>(v: &mut V, expr: &mut AccessExpr<'a>) {
+    v.visit_ident_mut(&mut expr.id);
+}
+
+/// Walks the children of a named access expression.
+pub fn walk_named_access_expr_mut<'a, V: VisitMut<'a> + ?Sized>(
+    v: &mut V,
+    expr: &mut NamedAccessExpr<'a>,
+) {
+    v.visit_string_mut(&mut expr.string);
+}
+
+/// Walks the children of a type declaration.
+pub fn walk_type_decl_mut<'a, V: VisitMut<'a> + ?Sized>(v: &mut V, decl: &mut TypeDecl<'a>) {
+    match decl {
+        TypeDecl::Variant(d) => v.visit_variant_decl_mut(d),
+        TypeDecl::Record(d) => v.visit_record_decl_mut(d),
+        TypeDecl::Flags(d) => v.visit_flags_decl_mut(d),
+        TypeDecl::Enum(d) => v.visit_enum_decl_mut(d),
+        TypeDecl::Alias(d) => v.visit_type_alias_mut(d),
+    }
+}
+
+/// Walks the children of a type declaration in an interface or world.
+pub fn walk_item_type_decl_mut<'a, V: VisitMut<'a> + ?Sized>(
+    v: &mut V,
+    decl: &mut ItemTypeDecl<'a>,
+) {
+    match decl {
+        ItemTypeDecl::Resource(d) => v.visit_resource_decl_mut(d),
+        ItemTypeDecl::Variant(d) => v.visit_variant_decl_mut(d),
+        ItemTypeDecl::Record(d) => v.visit_record_decl_mut(d),
+        ItemTypeDecl::Flags(d) => v.visit_flags_decl_mut(d),
+        ItemTypeDecl::Enum(d) => v.visit_enum_decl_mut(d),
+        ItemTypeDecl::Alias(d) => v.visit_type_alias_mut(d),
+    }
+}
+
+/// Walks the children of a resource declaration.
+pub fn walk_resource_decl_mut<'a, V: VisitMut<'a> + ?Sized>(
+    v: &mut V,
+    decl: &mut ResourceDecl<'a>,
+) {
+    v.visit_ident_mut(&mut decl.id);
+    for method in &mut decl.methods {
+        v.visit_resource_method_mut(method);
+    }
+}
+
+/// Walks the children of a resource method.
+pub fn walk_resource_method_mut<'a, V: VisitMut<'a> + ?Sized>(
+    v: &mut V,
+    method: &mut ResourceMethod<'a>,
+) {
+    match method {
+        ResourceMethod::Constructor(c) => {
+            for param in &mut c.params {
+                v.visit_named_type_mut(param);
+            }
+        }
+        ResourceMethod::Method(m) => {
+            v.visit_ident_mut(&mut m.id);
+            v.visit_func_type_mut(&mut m.ty);
+        }
+    }
+}
+
+/// Walks the children of a variant declaration.
+pub fn walk_variant_decl_mut<'a, V: VisitMut<'a> + ?Sized>(v: &mut V, decl: &mut VariantDecl<'a>) {
+    v.visit_ident_mut(&mut decl.id);
+    for case in &mut decl.cases {
+        v.visit_variant_case_mut(case);
+    }
+}
+
+/// Walks the children of a variant case.
+pub fn walk_variant_case_mut<'a, V: VisitMut<'a> + ?Sized>(v: &mut V, case: &mut VariantCase<'a>) {
+    v.visit_ident_mut(&mut case.id);
+    if let Some(ty) = &mut case.ty {
+        v.visit_type_mut(ty);
+    }
+}
+
+/// Walks the children of a record declaration.
+pub fn walk_record_decl_mut<'a, V: VisitMut<'a> + ?Sized>(v: &mut V, decl: &mut RecordDecl<'a>) {
+    v.visit_ident_mut(&mut decl.id);
+    for field in &mut decl.fields {
+        v.visit_field_mut(field);
+    }
+}
+
+/// Walks the children of a record field.
+pub fn walk_field_mut<'a, V: VisitMut<'a> + ?Sized>(v: &mut V, field: &mut Field<'a>) {
+    v.visit_ident_mut(&mut field.id);
+    v.visit_type_mut(&mut field.ty);
+}
+
+/// Walks the children of a flags declaration.
+pub fn walk_flags_decl_mut<'a, V: VisitMut<'a> + ?Sized>(v: &mut V, decl: &mut FlagsDecl<'a>) {
+    v.visit_ident_mut(&mut decl.id);
+    for flag in &mut decl.flags {
+        v.visit_flag_mut(flag);
+    }
+}
+
+/// Walks the children of a flag.
+pub fn walk_flag_mut<'a, V: VisitMut<'a> + ?Sized>(v: &mut V, flag: &mut Flag<'a>) {
+    v.visit_ident_mut(&mut flag.id);
+}
+
+/// Walks the children of an enum declaration.
+pub fn walk_enum_decl_mut<'a, V: VisitMut<'a> + ?Sized>(v: &mut V, decl: &mut EnumDecl<'a>) {
+    v.visit_ident_mut(&mut decl.id);
+    for case in &mut decl.cases {
+        v.visit_enum_case_mut(case);
+    }
+}
+
+/// Walks the children of an enum case.
+pub fn walk_enum_case_mut<'a, V: VisitMut<'a> + ?Sized>(v: &mut V, case: &mut EnumCase<'a>) {
+    v.visit_ident_mut(&mut case.id);
+}
+
+/// Walks the children of a type alias.
+pub fn walk_type_alias_mut<'a, V: VisitMut<'a> + ?Sized>(v: &mut V, alias: &mut TypeAlias<'a>) {
+    v.visit_ident_mut(&mut alias.id);
+    match &mut alias.kind {
+        TypeAliasKind::Func(f) => v.visit_func_type_mut(f),
+        TypeAliasKind::Type(t) => v.visit_type_mut(t),
+    }
+}
+
+/// Walks the children of a function type reference.
+pub fn walk_func_type_ref_mut<'a, V: VisitMut<'a> + ?Sized>(v: &mut V, ty: &mut FuncTypeRef<'a>) {
+    match ty {
+        FuncTypeRef::Func(f) => v.visit_func_type_mut(f),
+        FuncTypeRef::Ident(id) => v.visit_ident_mut(id),
+    }
+}
+
+/// Walks the children of a function type.
+pub fn walk_func_type_mut<'a, V: VisitMut<'a> + ?Sized>(v: &mut V, ty: &mut FuncType<'a>) {
+    for param in &mut ty.params {
+        v.visit_named_type_mut(param);
+    }
+    v.visit_result_list_mut(&mut ty.results);
+}
+
+/// Walks the children of the results of a function type.
+pub fn walk_result_list_mut<'a, V: VisitMut<'a> + ?Sized>(v: &mut V, results: &mut ResultList<'a>) {
+    match results {
+        ResultList::Empty => {}
+        ResultList::Scalar(ty) => v.visit_type_mut(ty),
+        ResultList::Named(results) => {
+            for result in results {
+                v.visit_named_type_mut(result);
+            }
+        }
+    }
+}
+
+/// Walks the children of a named type.
+pub fn walk_named_type_mut<'a, V: VisitMut<'a> + ?Sized>(v: &mut V, ty: &mut NamedType<'a>) {
+    v.visit_ident_mut(&mut ty.id);
+    v.visit_type_mut(&mut ty.ty);
+}
+
+/// Walks the children of a type.
+pub fn walk_type_mut<'a, V: VisitMut<'a> + ?Sized>(v: &mut V, ty: &mut Type<'a>) {
+    match ty {
+        Type::U8(_)
+        | Type::S8(_)
+        | Type::U16(_)
+        | Type::S16(_)
+        | Type::U32(_)
+        | Type::S32(_)
+        | Type::U64(_)
+        | Type::S64(_)
+        | Type::Float32(_)
+        | Type::Float64(_)
+        | Type::Char(_)
+        | Type::Bool(_)
+        | Type::String(_) => {}
+        Type::Tuple(types, _) => {
+            for ty in types {
+                v.visit_type_mut(ty);
+            }
+        }
+        Type::List(ty, _) | Type::Option(ty, _) => v.visit_type_mut(ty),
+        Type::Result { ok, err, .. } => {
+            if let Some(ok) = ok {
+                v.visit_type_mut(ok);
+            }
+            if let Some(err) = err {
+                v.visit_type_mut(err);
+            }
+        }
+        Type::Borrow(id, _) | Type::Ident(id) => v.visit_ident_mut(id),
+    }
+}
+
+/// Walks the children of an interface declaration.
+pub fn walk_interface_decl_mut<'a, V: VisitMut<'a> + ?Sized>(
+    v: &mut V,
+    decl: &mut InterfaceDecl<'a>,
+) {
+    v.visit_ident_mut(&mut decl.id);
+    for item in &mut decl.items {
+        v.visit_interface_item_mut(item);
+    }
+}
+
+/// Walks the children of an interface item.
+pub fn walk_interface_item_mut<'a, V: VisitMut<'a> + ?Sized>(
+    v: &mut V,
+    item: &mut InterfaceItem<'a>,
+) {
+    match item {
+        InterfaceItem::Use(u) => v.visit_use_mut(u),
+        InterfaceItem::Type(t) => v.visit_item_type_decl_mut(t),
+        InterfaceItem::Export(e) => v.visit_interface_export_mut(e),
+    }
+}
+
+/// Walks the children of a use item.
+pub fn walk_use_mut<'a, V: VisitMut<'a> + ?Sized>(v: &mut V, item: &mut Use<'a>) {
+    v.visit_use_path_mut(&mut item.path);
+    for item in &mut item.items {
+        v.visit_use_item_mut(item);
+    }
+}
+
+/// Walks the children of the path of a use item.
+pub fn walk_use_path_mut<'a, V: VisitMut<'a> + ?Sized>(v: &mut V, path: &mut UsePath<'a>) {
+    match path {
+        UsePath::Package(p) => v.visit_package_path_mut(p),
+        UsePath::Ident(id) => v.visit_ident_mut(id),
+    }
+}
+
+/// Walks the children of a used type.
+pub fn walk_use_item_mut<'a, V: VisitMut<'a> + ?Sized>(v: &mut V, item: &mut UseItem<'a>) {
+    v.visit_ident_mut(&mut item.id);
+    if let Some(id) = &mut item.as_id {
+        v.visit_ident_mut(id);
+    }
+}
+
+/// Walks the children of an interface export.
+pub fn walk_interface_export_mut<'a, V: VisitMut<'a> + ?Sized>(
+    v: &mut V,
+    export: &mut InterfaceExport<'a>,
+) {
+    v.visit_ident_mut(&mut export.id);
+    v.visit_func_type_ref_mut(&mut export.ty);
+}
+
+/// Walks the children of an inline interface.
+pub fn walk_inline_interface_mut<'a, V: VisitMut<'a> + ?Sized>(
+    v: &mut V,
+    interface: &mut InlineInterface<'a>,
+) {
+    for item in &mut interface.items {
+        v.visit_interface_item_mut(item);
+    }
+}
+
+/// Walks the children of a world declaration.
+pub fn walk_world_decl_mut<'a, V: VisitMut<'a> + ?Sized>(v: &mut V, decl: &mut WorldDecl<'a>) {
+    v.visit_ident_mut(&mut decl.id);
+    for item in &mut decl.items {
+        v.visit_world_item_mut(item);
+    }
+}
+
+/// Walks the children of a world item.
+pub fn walk_world_item_mut<'a, V: VisitMut<'a> + ?Sized>(v: &mut V, item: &mut WorldItem<'a>) {
+    match item {
+        WorldItem::Use(u) => v.visit_use_mut(u),
+        WorldItem::Type(t) => v.visit_item_type_decl_mut(t),
+        WorldItem::Import(i) => v.visit_world_item_path_mut(&mut i.path),
+        WorldItem::Export(e) => v.visit_world_item_path_mut(&mut e.path),
+        WorldItem::Include(i) => v.visit_world_include_mut(i),
+    }
+}
+
+/// Walks the children of the path of a world import or export.
+pub fn walk_world_item_path_mut<'a, V: VisitMut<'a> + ?Sized>(
+    v: &mut V,
+    path: &mut WorldItemPath<'a>,
+) {
+    match path {
+        WorldItemPath::Named(n) => v.visit_named_world_item_mut(n),
+        WorldItemPath::Package(p) => v.visit_package_path_mut(p),
+        WorldItemPath::Ident(id) => v.visit_ident_mut(id),
+    }
+}
+
+/// Walks the children of a named world item.
+pub fn walk_named_world_item_mut<'a, V: VisitMut<'a> + ?Sized>(
+    v: &mut V,
+    item: &mut NamedWorldItem<'a>,
+) {
+    v.visit_ident_mut(&mut item.id);
+    v.visit_extern_type_mut(&mut item.ty);
+}
+
+/// Walks the children of the type of a named world item.
+pub fn walk_extern_type_mut<'a, V: VisitMut<'a> + ?Sized>(v: &mut V, ty: &mut ExternType<'a>) {
+    match ty {
+        ExternType::Ident(id) => v.visit_ident_mut(id),
+        ExternType::Func(f) => v.visit_func_type_mut(f),
+        ExternType::Interface(i) => v.visit_inline_interface_mut(i),
+    }
+}
+
+/// Walks the children of a world include.
+pub fn walk_world_include_mut<'a, V: VisitMut<'a> + ?Sized>(
+    v: &mut V,
+    include: &mut WorldInclude<'a>,
+) {
+    v.visit_world_ref_mut(&mut include.world);
+    for item in &mut include.with {
+        v.visit_world_include_item_mut(item);
+    }
+}
+
+/// Walks the children of a reference to an included world.
+pub fn walk_world_ref_mut<'a, V: VisitMut<'a> + ?Sized>(v: &mut V, world: &mut WorldRef<'a>) {
+    match world {
+        WorldRef::Ident(id) => v.visit_ident_mut(id),
+        WorldRef::Package(p) => v.visit_package_path_mut(p),
+    }
+}
+
+/// Walks the children of a renamed item of a world include.
+pub fn walk_world_include_item_mut<'a, V: VisitMut<'a> + ?Sized>(
+    v: &mut V,
+    item: &mut WorldIncludeItem<'a>,
+) {
+    v.visit_ident_mut(&mut item.from);
+    v.visit_ident_mut(&mut item.to);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::{collections::BTreeMap, sync::Arc};
+
+    #[test]
+    fn it_visits_nodes() {
+        #[derive(Default)]
+        struct Collector<'a> {
+            packages: Vec<&'a str>,
+            idents: Vec<&'a str>,
+        }
+
+        impl<'a> Visitor<'a> for Collector<'a> {
+            fn visit_package_name(&mut self, name: &'a PackageName<'a>) {
+                self.packages.push(name.string);
+            }
+
+            fn visit_package_path(&mut self, path: &'a PackagePath<'a>) {
+                self.packages.push(path.string);
+            }
+
+            fn visit_ident(&mut self, ident: &'a Ident<'a>) {
+                self.idents.push(ident.string);
+            }
+        }
+
+        let doc = Document::parse(
+            r#"package test:comp targets test:comp/w;
+interface i {
+    use foo:bar/baz.{t as u};
+    f: func(x: u) -> list<borrow<r>>;
+}
+import a: foo:bar/a;
+let x = new foo:bar { a, b: (new foo:baz {}).c, ... };
+export x["d"] as e;
+"#,
+        )
+        .unwrap();
+
+        let mut collector = Collector::default();
+        collector.visit_document(&doc);
+        assert_eq!(
+            collector.packages,
+            [
+                "test:comp",
+                "test:comp/w",
+                "foo:bar/baz",
+                "foo:bar/a",
+                "foo:bar",
+                "foo:baz"
+            ]
+        );
+        assert_eq!(
+            collector.idents,
+            ["i", "t", "u", "f", "x", "u", "r", "a", "x", "a", "b", "c", "x", "e"]
+        );
+    }
+
+    #[test]
+    fn it_visits_every_node_kind() {
+        #[derive(Default)]
+        struct Counter(BTreeMap<&'static str, usize>);
+
+        macro_rules! count {
+            (nodes { $($visit:ident => $walk:ident($ty:ident),)* } leaves { $($leaf:ident($leaf_ty:ident),)* }) => {
+                impl<'a> Visitor<'a> for Counter {
+                    $(
+                        fn $visit(&mut self, node: &'a $ty<'a>) {
+                            *self.0.entry(stringify!($ty)).or_default() += 1;
+                            $walk(self, node);
+                        }
+                    )*
+
+                    $(
+                        fn $leaf(&mut self, _: &'a $leaf_ty<'a>) {
+                            *self.0.entry(stringify!($leaf_ty)).or_default() += 1;
+                        }
+                    )*
+                }
+            };
+        }
+
+        count! {
+            nodes {
+                visit_document => walk_document(Document),
+                visit_package_directive => walk_package_directive(PackageDirective),
+                visit_statement => walk_statement(Statement),
+                visit_include_statement => walk_include_statement(IncludeStatement),
+                visit_import_statement => walk_import_statement(ImportStatement),
+                visit_import_type => walk_import_type(ImportType),
+                visit_extern_name => walk_extern_name(ExternName),
+                visit_type_statement => walk_type_statement(TypeStatement),
+                visit_let_statement => walk_let_statement(LetStatement),
+                visit_destructure_statement => walk_destructure_statement(DestructureStatement),
+                visit_destructure_item => walk_destructure_item(DestructureItem),
+                visit_export_statement => walk_export_statement(ExportStatement),
+                visit_export_options => walk_export_options(ExportOptions),
+                visit_expr => walk_expr(Expr),
+                visit_primary_expr => walk_primary_expr(PrimaryExpr),
+                visit_new_expr => walk_new_expr(NewExpr),
+                visit_instantiation_argument => walk_instantiation_argument(InstantiationArgument),
+                visit_named_instantiation_argument => walk_named_instantiation_argument(NamedInstantiationArgument),
+                visit_instantiation_argument_name => walk_instantiation_argument_name(InstantiationArgumentName),
+                visit_nested_expr => walk_nested_expr(NestedExpr),
+                visit_postfix_expr => walk_postfix_expr(PostfixExpr),
+                visit_access_expr => walk_access_expr(AccessExpr),
+                visit_named_access_expr => walk_named_access_expr(NamedAccessExpr),
+                visit_type_decl => walk_type_decl(TypeDecl),
+                visit_item_type_decl => walk_item_type_decl(ItemTypeDecl),
+                visit_resource_decl => walk_resource_decl(ResourceDecl),
+                visit_resource_method => walk_resource_method(ResourceMethod),
+                visit_variant_decl => walk_variant_decl(VariantDecl),
+                visit_variant_case => walk_variant_case(VariantCase),
+                visit_record_decl => walk_record_decl(RecordDecl),
+                visit_field => walk_field(Field),
+                visit_flags_decl => walk_flags_decl(FlagsDecl),
+                visit_flag => walk_flag(Flag),
+                visit_enum_decl => walk_enum_decl(EnumDecl),
+                visit_enum_case => walk_enum_case(EnumCase),
+                visit_type_alias => walk_type_alias(TypeAlias),
+                visit_func_type_ref => walk_func_type_ref(FuncTypeRef),
+                visit_func_type => walk_func_type(FuncType),
+                visit_result_list => walk_result_list(ResultList),
+                visit_named_type => walk_named_type(NamedType),
+                visit_type => walk_type(Type),
+                visit_interface_decl => walk_interface_decl(InterfaceDecl),
+                visit_interface_item => walk_interface_item(InterfaceItem),
+                visit_use => walk_use(Use),
+                visit_use_path => walk_use_path(UsePath),
+                visit_use_item => walk_use_item(UseItem),
+                visit_interface_export => walk_interface_export(InterfaceExport),
+                visit_inline_interface => walk_inline_interface(InlineInterface),
+                visit_world_decl => walk_world_decl(WorldDecl),
+                visit_world_item => walk_world_item(WorldItem),
+                visit_world_item_path => walk_world_item_path(WorldItemPath),
+                visit_named_world_item => walk_named_world_item(NamedWorldItem),
+                visit_extern_type => walk_extern_type(ExternType),
+                visit_world_include => walk_world_include(WorldInclude),
+                visit_world_ref => walk_world_ref(WorldRef),
+                visit_world_include_item => walk_world_include_item(WorldIncludeItem),
+            }
+            leaves {
+                visit_package_name(PackageName),
+                visit_package_path(PackagePath),
+                visit_ident(Ident),
+                visit_string(String),
+            }
+        }
+
+        let mut doc = Document::parse(
+            r#"package test:comp targets test:comp/w;
+include "types.wac";
+variant v { a(u8), b }
+record r { f: list<t> }
+flags fl { x }
+enum e { y }
+interface i {
+    use foo:bar/baz.{u as w};
+    resource res {
+        constructor(n: u8);
+        m: func(b: borrow<res>) -> result<u8, string>;
+    }
+    type fa = func() -> (o: u8);
+    g: fa;
+}
+world w {
+    use i.{res};
+    type wt = tuple<u8, t>;
+    import foo:bar/a;
+    import h: func();
+    export j: interface { k: func(); };
+    export i;
+    include foo:bar/w2 with { l as m };
+}
+import a: foo:bar/a;
+import c as "c": func();
+let { d, e as f } = new foo:bar { a, g: (new foo:baz { ... }).h, "i": c, ... };
+export d["j"] as "k";
+"#,
+        )
+        .unwrap();
+
+        match &mut doc.statements[0] {
+            Statement::Include(s) => {
+                s.document = Some(Arc::new(
+                    Document::parse("package test:types;\ntype t = u32;\n").unwrap(),
+                ))
+            }
+            _ => panic!("expected an include statement"),
+        }
+
+        let mut counter = Counter::default();
+        counter.visit_document(&doc);
+        assert_eq!(
+            counter.0,
+            BTreeMap::from([
+                ("AccessExpr", 1),
+                ("DestructureItem", 2),
+                ("DestructureStatement", 1),
+                ("Document", 2),
+                ("EnumCase", 1),
+                ("EnumDecl", 1),
+                ("ExportOptions", 1),
+                ("ExportStatement", 1),
+                ("Expr", 5),
+                ("ExternName", 2),
+                ("ExternType", 2),
+                ("Field", 1),
+                ("Flag", 1),
+                ("FlagsDecl", 1),
+                ("FuncType", 5),
+                ("FuncTypeRef", 2),
+                ("Ident", 44),
+                ("ImportStatement", 2),
+                ("ImportType", 2),
+                ("IncludeStatement", 1),
+                ("InlineInterface", 1),
+                ("InstantiationArgument", 5),
+                ("InstantiationArgumentName", 2),
+                ("InterfaceDecl", 1),
+                ("InterfaceExport", 2),
+                ("InterfaceItem", 5),
+                ("ItemTypeDecl", 3),
+                ("NamedAccessExpr", 1),
+                ("NamedInstantiationArgument", 2),
+                ("NamedType", 3),
+                ("NamedWorldItem", 2),
+                ("NestedExpr", 1),
+                ("NewExpr", 2),
+                ("PackageDirective", 2),
+                ("PackageName", 4),
+                ("PackagePath", 5),
+                ("PostfixExpr", 2),
+                ("PrimaryExpr", 5),
+                ("RecordDecl", 1),
+                ("ResourceDecl", 1),
+                ("ResourceMethod", 2),
+                ("ResultList", 5),
+                ("Statement", 12),
+                ("String", 5),
+                ("Type", 13),
+                ("TypeAlias", 3),
+                ("TypeDecl", 5),
+                ("TypeStatement", 7),
+                ("Use", 2),
+                ("UseItem", 2),
+                ("UsePath", 2),
+                ("VariantCase", 2),
+                ("VariantDecl", 1),
+                ("WorldDecl", 1),
+                ("WorldInclude", 1),
+                ("WorldIncludeItem", 1),
+                ("WorldItem", 7),
+                ("WorldItemPath", 4),
+                ("WorldRef", 1),
+            ])
+        );
+    }
+
+    #[test]
+    fn it_modifies_nodes() {
+        struct Renamer;
+
+        impl<'a> VisitMut<'a> for Renamer {
+            fn visit_ident_mut(&mut self, ident: &mut Ident<'a>) {
+                if ident.string == "a" {
+                    ident.string = "b";
+                }
+            }
+        }
+
+        struct Collector<'a>(Vec<&'a str>);
+
+        impl<'a> Visitor<'a> for Collector<'a> {
+            fn visit_ident(&mut self, ident: &'a Ident<'a>) {
+                self.0.push(ident.string);
+            }
+        }
+
+        let mut doc = Document::parse(
+            "package test:comp;\nimport a: func();\nlet c = new foo:bar { a };\nexport c.a;\n",
+        )
+        .unwrap();
+        Renamer.visit_document_mut(&mut doc);
+
+        let mut collector = Collector(Vec::new());
+        collector.visit_document(&doc);
+        assert_eq!(collector.0, ["b", "c", "b", "c", "b"]);
+    }
+}
@@ -0,0 +1,580 @@
+//! Module for the lossless concrete syntax tree (CST) implementation.
+//!
+//! Unlike the AST, the CST keeps every token of the source, including
+//! whitespace and comments, so that printing a tree reproduces its source
+//! exactly.
+//!
+//! Tools that edit documents can rearrange the nodes of a tree and print it
+//! without losing comments; use [`SyntaxTree::document`] to get the AST of
+//! a tree.
+
+use crate::{
+    ast::{self, starts_statement},
+    lexer::{helpers::block_comment_length, Error, Lexer, Token},
+};
+use logos::Logos;
+use miette::SourceSpan;
+use std::fmt;
+
+fn to_source_span(start: usize, end: usize) -> SourceSpan {
+    SourceSpan::new(start.into(), (end - start).into())
+}
+
+/// Represents the kind of a token in the CST.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyntaxKind {
+    /// The token is a lexer token.
+    ///
+    /// Comments are represented by [`Token::Comment`] and [`Token::BlockComment`].
+    Token(Token),
+    /// The token is a run of whitespace.
+    ///
+    /// A whitespace token contains at most one newline, at its end.
+    Whitespace,
+    /// The token is invalid.
+    Error(Error),
+}
+
+impl SyntaxKind {
+    /// Determines if the kind is trivia (i.e. whitespace or a comment).
+    pub fn is_trivia(&self) -> bool {
+        matches!(
+            self,
+            Self::Whitespace | Self::Token(Token::Comment) | Self::Token(Token::BlockComment)
+        )
+    }
+}
+
+/// Represents a token in the CST.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SyntaxToken<'a> {
+    /// The kind of the token.
+    pub kind: SyntaxKind,
+    /// The source text of the token.
+    pub text: &'a str,
+    /// The span of the token in the source the tree was created from.
+    pub span: SourceSpan,
+}
+
+impl fmt::Display for SyntaxToken<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{text}", text = self.text)
+    }
+}
+
+/// Represents the kind of a node in the CST.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeKind {
+    /// The node is the root of the tree.
+    Document,
+    /// The node is the package directive.
+    Directive,
+    /// The node is a top-level statement.
+    Statement,
+    /// The node is delimited by braces.
+    Braces,
+    /// The node is delimited by parentheses.
+    Parens,
+}
+
+/// Represents an element of a node in the CST.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SyntaxElement<'a> {
+    /// The element is a node.
+    Node(SyntaxNode<'a>),
+    /// The element is a token.
+    Token(SyntaxToken<'a>),
+}
+
+impl fmt::Display for SyntaxElement<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Node(n) => n.fmt(f),
+            Self::Token(t) => t.fmt(f),
+        }
+    }
+}
+
+/// Represents a node in the CST.
+///
+/// A directive or statement node includes the comments preceding it and any
+/// comment trailing it on the same line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyntaxNode<'a> {
+    /// The kind of the node.
+    pub kind: NodeKind,
+    /// The child elements of the node.
+    pub children: Vec<SyntaxElement<'a>>,
+}
+
+impl<'a> SyntaxNode<'a> {
+    /// Gets the tokens of the node in source order, including the tokens of
+    /// descendant nodes.
+    pub fn tokens(&self) -> impl Iterator<Item = &SyntaxToken<'a>> {
+        let mut stack = vec![self.children.iter()];
+        std::iter::from_fn(move || loop {
+            match stack.last_mut()?.next() {
+                Some(SyntaxElement::Token(t)) => return Some(t),
+                Some(SyntaxElement::Node(n)) => stack.push(n.children.iter()),
+                None => {
+                    stack.pop();
+                }
+            }
+        })
+    }
+
+    /// Gets the comment tokens of the node, including the comments of
+    /// descendant nodes.
+    pub fn comments(&self) -> impl Iterator<Item = &SyntaxToken<'a>> {
+        self.tokens().filter(|t| {
+            matches!(
+                t.kind,
+                SyntaxKind::Token(Token::Comment) | SyntaxKind::Token(Token::BlockComment)
+            )
+        })
+    }
+
+    /// Gets the child nodes of the node.
+    pub fn nodes(&self) -> impl Iterator<Item = &SyntaxNode<'a>> {
+        self.children.iter().filter_map(|c| match c {
+            SyntaxElement::Node(n) => Some(n),
+            SyntaxElement::Token(_) => None,
+        })
+    }
+
+    /// Gets the span of the node's non-trivia tokens.
+    ///
+    /// Returns `None` if the node has only trivia.
+    pub fn span(&self) -> Option<SourceSpan> {
+        let mut tokens = self.tokens().filter(|t| !t.kind.is_trivia());
+        let first = tokens.next()?.span;
+        let last = tokens.last().map(|t| t.span).unwrap_or(first);
+        Some(to_source_span(first.offset(), last.offset() + last.len()))
+    }
+}
+
+impl fmt::Display for SyntaxNode<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for child in &self.children {
+            child.fmt(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Represents a lossless concrete syntax tree of a WAC document.
+///
+/// A tree can be created for any source; invalid tokens are kept as
+/// [`SyntaxKind::Error`] tokens.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyntaxTree<'a> {
+    source: &'a str,
+    /// The root node of the tree.
+    pub root: SyntaxNode<'a>,
+}
+
+impl<'a> SyntaxTree<'a> {
+    /// Creates a tree for the given source.
+    pub fn parse(source: &'a str) -> Self {
+        let mut tokens = tokens(source);
+
+        // Find the first token of each top-level directive or statement
+        let mut starts = Vec::new();
+        let mut depth = 0usize;
+        for (i, token) in tokens.iter().enumerate() {
+            match token.kind {
+                SyntaxKind::Token(Token::OpenBrace | Token::OpenParen) => depth += 1,
+                SyntaxKind::Token(Token::CloseBrace | Token::CloseParen) => {
+                    depth = depth.saturating_sub(1)
+                }
+                SyntaxKind::Token(t)
+                    if depth == 0 && (t == Token::PackageKeyword || starts_statement(t)) =>
+                {
+                    starts.push(i)
+                }
+                _ => {}
+            }
+        }
+
+        // Split the tokens so that each node includes its leading comments and
+        // the trivia trailing it on the same line; any trivia preceding the
+        // first node belongs to it and any remaining trivia belongs to the root
+        let mut bounds: Vec<_> = starts
+            .iter()
+            .enumerate()
+            .map(|(i, start)| match i {
+                0 => 0,
+                _ => leading_trivia_start(&tokens, *start),
+            })
+            .collect();
+
+        if !bounds.is_empty() {
+            bounds.push(trailing_trivia_end(&tokens));
+        }
+
+        let kinds: Vec<_> = starts
+            .iter()
+            .map(|start| match tokens[*start].kind {
+                SyntaxKind::Token(Token::PackageKeyword) => NodeKind::Directive,
+                _ => NodeKind::Statement,
+            })
+            .collect();
+
+        let rest = tokens.split_off(bounds.last().copied().unwrap_or(0));
+        let mut children = Vec::new();
+        let mut tokens = tokens.into_iter();
+        for (i, kind) in kinds.into_iter().enumerate() {
+            children.push(SyntaxElement::Node(group(
+                kind,
+                tokens.by_ref().take(bounds[i + 1] - bounds[i]),
+            )));
+        }
+
+        children.extend(rest.into_iter().map(SyntaxElement::Token));
+
+        Self {
+            source,
+            root: SyntaxNode {
+                kind: NodeKind::Document,
+                children,
+            },
+        }
+    }
+
+    /// Gets the source the tree was created from.
+    pub fn source(&self) -> &'a str {
+        self.source
+    }
+
+    /// Gets the statement nodes of the tree.
+    ///
+    /// If the source parses, the statement nodes correspond to the
+    /// statements of the AST in the same order.
+    pub fn statements(&self) -> impl Iterator<Item = &SyntaxNode<'a>> {
+        self.root.nodes().filter(|n| n.kind == NodeKind::Statement)
+    }
+
+    /// Parses the AST of the tree.
+    ///
+    /// The AST is built from the tokens of the tree in tree order, so changes
+    /// to the tree (e.g. reordering or removing statements) are reflected in
+    /// the AST. The tokens of the tree must be from the source the tree was
+    /// created from; the spans of the AST are in that source.
+    pub fn document(&self) -> Result<ast::Document<'a>, ast::Error> {
+        let lexer = Lexer::from_syntax_tokens(self.source, 0, self.root.tokens())?;
+        let (document, errors) = ast::Document::parse_lexer(lexer);
+        match errors.into_iter().next() {
+            Some(e) => Err(e),
+            None => Ok(document.expect("document should be present without errors")),
+        }
+    }
+}
+
+impl fmt::Display for SyntaxTree<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.root.fmt(f)
+    }
+}
+
+/// Lexes the given source into tokens that cover the entire source.
+pub(crate) fn tokens(source: &str) -> Vec<SyntaxToken> {
+    let mut tokens = Vec::new();
+    let mut start = 0;
+    for (result, span) in Token::lexer(source).spanned() {
+        trivia(source, start, span.start, &mut tokens);
+        tokens.push(SyntaxToken {
+            kind: match result {
+                Ok(token) => SyntaxKind::Token(token),
+                Err(e) => SyntaxKind::Error(e),
+            },
+            text: &source[span.clone()],
+            span: to_source_span(span.start, span.end),
+        });
+        start = span.end;
+    }
+
+    trivia(source, start, source.len(), &mut tokens);
+    tokens
+}
+
+/// Lexes the trivia skipped by the lexer between the given offsets.
+fn trivia<'a>(source: &'a str, mut offset: usize, end: usize, tokens: &mut Vec<SyntaxToken<'a>>) {
+    while offset < end {
+        let rest = &source[offset..end];
+        let (kind, len) = if rest.starts_with("//") {
+            (
+                SyntaxKind::Token(Token::Comment),
+                rest.find('\n').unwrap_or(rest.len()),
+            )
+        } else if rest.starts_with("/*") {
+            match block_comment_length(&rest.as_bytes()[2..]) {
+                Some(len) => (SyntaxKind::Token(Token::BlockComment), len),
+                None => (SyntaxKind::Error(Error::UnterminatedComment), rest.len()),
+            }
+        } else {
+            // Whitespace is split after each newline so that lines can be
+            // attached to different nodes
+            let len = rest
+                .find(|c: char| !c.is_ascii_whitespace())
+                .unwrap_or(rest.len());
+            match rest[..len].find('\n') {
+                _ if len == 0 => (
+                    SyntaxKind::Error(Error::UnexpectedToken),
+                    rest.chars().next().unwrap().len_utf8(),
+                ),
+                Some(newline) => (SyntaxKind::Whitespace, newline + 1),
+                None => (SyntaxKind::Whitespace, len),
+            }
+        };
+
+        tokens.push(SyntaxToken {
+            kind,
+            text: &rest[..len],
+            span: to_source_span(offset, offset + len),
+        });
+        offset += len;
+    }
+}
+
+/// Gets the index of the first trivia token that belongs to the node starting
+/// with the given token.
+///
+/// Trivia following the previous non-trivia token up to and including the
+/// first newline belongs to the previous node.
+fn leading_trivia_start(tokens: &[SyntaxToken], start: usize) -> usize {
+    let first = tokens[..start]
+        .iter()
+        .rposition(|t| !t.kind.is_trivia())
+        .map(|i| i + 1)
+        .unwrap_or(0);
+
+    tokens[first..start]
+        .iter()
+        .position(|t| t.kind == SyntaxKind::Whitespace && t.text.contains('\n'))
+        .map(|i| first + i + 1)
+        .unwrap_or(first)
+}
+
+/// Gets the index following the trivia that trails the last non-trivia token
+/// on the same line.
+fn trailing_trivia_end(tokens: &[SyntaxToken]) -> usize {
+    let first = tokens
+        .iter()
+        .rposition(|t| !t.kind.is_trivia())
+        .map(|i| i + 1)
+        .unwrap_or(0);
+
+    tokens[first..]
+        .iter()
+        .position(|t| t.kind == SyntaxKind::Whitespace && t.text.contains('\n'))
+        .map(|i| first + i + 1)
+        .unwrap_or(tokens.len())
+}
+
+/// Groups the given tokens into a node, nesting delimited tokens in child nodes.
+fn group<'a>(kind: NodeKind, tokens: impl Iterator<Item = SyntaxToken<'a>>) -> SyntaxNode<'a> {
+    let mut stack = vec![SyntaxNode {
+        kind,
+        children: Vec::new(),
+    }];
+
+    for token in tokens {
+        let kind = match token.kind {
+            SyntaxKind::Token(Token::OpenBrace) => Some(NodeKind::Braces),
+            SyntaxKind::Token(Token::OpenParen) => Some(NodeKind::Parens),
+            _ => None,
+        };
+
+        if let Some(kind) = kind {
+            stack.push(SyntaxNode {
+                kind,
+                children: vec![SyntaxElement::Token(token)],
+            });
+            continue;
+        }
+
+        let top = stack.last_mut().unwrap();
+        top.children.push(SyntaxElement::Token(token));
+
+        let closes = match (top.kind, token.kind) {
+            (NodeKind::Braces, SyntaxKind::Token(Token::CloseBrace))
+            | (NodeKind::Parens, SyntaxKind::Token(Token::CloseParen)) => true,
+            _ => false,
+        };
+
+        if closes {
+            let node = stack.pop().unwrap();
+            stack
+                .last_mut()
+                .unwrap()
+                .children
+                .push(SyntaxElement::Node(node));
+        }
+    }
+
+    // Close any unterminated delimited nodes
+    while stack.len() > 1 {
+        let node = stack.pop().unwrap();
+        stack
+            .last_mut()
+            .unwrap()
+            .children
+            .push(SyntaxElement::Node(node));
+    }
+
+    stack.pop().unwrap()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    const SOURCE: &str = r#"// The composition.
+package test:comp;
+
+/// Imports `f`.
+import f: func(); // A trailing comment.
+
+/* Instantiates `foo:bar`. */
+let i = new foo:bar {
+    // An argument.
+    f, /* Another argument. */ ...
+};
+
+export i.g; // Exports `g`.
+// A final comment.
+"#;
+
+    #[test]
+    fn it_is_lossless() {
+        let tree = SyntaxTree::parse(SOURCE);
+        assert_eq!(tree.to_string(), SOURCE);
+
+        // Invalid sources are also kept
+        for source in ["package /* unterminated", "package \"foo;", "let x = ~;"] {
+            assert_eq!(SyntaxTree::parse(source).to_string(), source);
+        }
+    }
+
+    #[test]
+    fn it_attaches_comments_to_statements() {
+        let tree = SyntaxTree::parse(SOURCE);
+        let nodes: Vec<_> = tree.root.nodes().map(ToString::to_string).collect();
+        assert_eq!(
+            nodes,
+            [
+                "// The composition.\npackage test:comp;\n",
+                "\n/// Imports `f`.\nimport f: func(); // A trailing comment.\n",
+                "\n/* Instantiates `foo:bar`. */\nlet i = new foo:bar {\n    // An argument.\n    f, /* Another argument. */ ...\n};\n",
+                "\nexport i.g; // Exports `g`.\n",
+            ]
+        );
+
+        let comments: Vec<_> = tree
+            .statements()
+            .nth(1)
+            .unwrap()
+            .comments()
+            .map(|t| t.text)
+            .collect();
+        assert_eq!(
+            comments,
+            [
+                "/* Instantiates `foo:bar`. */",
+                "// An argument.",
+                "/* Another argument. */"
+            ]
+        );
+
+        let document = tree.document().unwrap();
+        assert_eq!(document.statements.len(), tree.statements().count());
+    }
+
+    #[test]
+    fn it_preserves_comments_when_editing() {
+        let mut tree = SyntaxTree::parse(SOURCE);
+
+        // Move the export statement before the import statement
+        let export = tree.root.children.remove(3);
+        tree.root.children.insert(1, export);
+
+        let source = tree.to_string();
+        assert_eq!(
+            source,
+            r#"// The composition.
+package test:comp;
+
+export i.g; // Exports `g`.
+
+/// Imports `f`.
+import f: func(); // A trailing comment.
+
+/* Instantiates `foo:bar`. */
+let i = new foo:bar {
+    // An argument.
+    f, /* Another argument. */ ...
+};
+// A final comment.
+"#
+        );
+
+        assert_eq!(SyntaxTree::parse(&source).to_string(), source);
+    }
+
+    #[test]
+    fn it_builds_the_ast_from_the_tree() {
+        let mut tree = SyntaxTree::parse(SOURCE);
+
+        // Move the export statement before the import statement and remove
+        // the let statement
+        let export = tree.root.children.remove(3);
+        tree.root.children.insert(1, export);
+        tree.root.children.remove(3);
+
+        let document = tree.document().unwrap();
+        assert_eq!(document.directive.package.name, "test:comp");
+        assert_eq!(document.statements.len(), 2);
+
+        match &document.statements[0] {
+            ast::Statement::Export(e) => {
+                assert!(e.docs.is_empty());
+                match &e.expr.primary {
+                    ast::PrimaryExpr::Ident(id) => {
+                        assert_eq!(id.string, "i");
+                        assert_eq!(&SOURCE[id.span.offset()..][..id.span.len()], "i");
+                    }
+                    _ => panic!("expected an identifier"),
+                }
+            }
+            _ => panic!("expected an export statement"),
+        }
+
+        match &document.statements[1] {
+            ast::Statement::Import(i) => {
+                assert_eq!(i.docs.len(), 1);
+                assert_eq!(i.docs[0].comment, "Imports `f`.");
+                assert_eq!(i.id.string, "f");
+            }
+            _ => panic!("expected an import statement"),
+        }
+
+        // The AST of the printed tree has the same statements
+        let source = tree.to_string();
+        let printed = ast::Document::parse(&source).unwrap();
+        assert!(matches!(
+            printed.statements.as_slice(),
+            [ast::Statement::Export(_), ast::Statement::Import(_)]
+        ));
+
+        // Errors are reported for the tree rather than the source
+        let mut tree = SyntaxTree::parse(SOURCE);
+        tree.root.children.remove(0);
+        let e = tree.document().unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "expected `package` keyword, found `import` keyword"
+        );
+    }
+}
@@ -1,8 +1,9 @@
 //! Module for the lexer implementation.
 
-use logos::{Logos, SpannedIter};
+use crate::cst::{SyntaxKind, SyntaxToken};
+use logos::Logos;
 use miette::SourceSpan;
-use std::fmt;
+use std::{fmt, rc::Rc};
 
 fn to_source_span(span: logos::Span) -> SourceSpan {
     SourceSpan::new(span.start.into(), (span.end - span.start).into())
@@ -96,6 +97,8 @@ fn detect_invalid_input(source: &str) -> Result<(), (Error, SourceSpan)> {
 #[logos(subpattern id = r"%?(?&word)(-(?&word))*")]
 #[logos(subpattern package_name = r"(?&id)(:(?&id))+")]
 #[logos(subpattern semver = r"[0-9a-zA-Z-\.\+]+")]
+#[logos(subpattern version = r"((=|>=|>|<=|<|~|\^)?(?&semver)|\*)")]
+#[logos(subpattern versions = r"(?&version)(,[ \t]*((=|>=|>|<=|<|~|\^)(?&semver)|[0-9](?&semver)?|\*))*")]
 pub enum Token {
     /// A comment.
     #[regex(r"//[^\n]*", logos::skip)]
@@ -113,12 +116,12 @@ pub enum Token {
     #[token("\"", helpers::string)]
     String,
 
-    /// A package name.
-    #[regex(r"(?&package_name)(@(?&semver))?")]
+    /// A package name with optional semantic version or version requirement.
+    #[regex(r"(?&package_name)(@(?&versions))?")]
     PackageName,
 
-    /// A package path with optional semantic version.
-    #[regex(r"(?&package_name)(/(?&id))+(@(?&semver))?")]
+    /// A package path with optional semantic version or version requirement.
+    #[regex(r"(?&package_name)(/(?&id))+(@(?&versions))?")]
     PackagePath,
 
     /// The `import` keyword.
@@ -295,6 +298,62 @@ pub enum Token {
     At,
 }
 
+impl Token {
+    /// Determines if the token is a keyword.
+    pub fn is_keyword(&self) -> bool {
+        matches!(
+            self,
+            Token::ImportKeyword
+                | Token::WithKeyword
+                | Token::TypeKeyword
+                | Token::TupleKeyword
+                | Token::ListKeyword
+                | Token::OptionKeyword
+                | Token::ResultKeyword
+                | Token::BorrowKeyword
+                | Token::ResourceKeyword
+                | Token::VariantKeyword
+                | Token::RecordKeyword
+                | Token::FlagsKeyword
+                | Token::EnumKeyword
+                | Token::FuncKeyword
+                | Token::StaticKeyword
+                | Token::ConstructorKeyword
+                | Token::U8Keyword
+                | Token::S8Keyword
+                | Token::U16Keyword
+                | Token::S16Keyword
+                | Token::U32Keyword
+                | Token::S32Keyword
+                | Token::U64Keyword
+                | Token::S64Keyword
+                | Token::Float32Keyword
+                | Token::Float64Keyword
+                | Token::CharKeyword
+                | Token::BoolKeyword
+                | Token::StringKeyword
+                | Token::InterfaceKeyword
+                | Token::WorldKeyword
+                | Token::ExportKeyword
+                | Token::NewKeyword
+                | Token::LetKeyword
+                | Token::UseKeyword
+                | Token::IncludeKeyword
+                | Token::AsKeyword
+                | Token::PackageKeyword
+                | Token::TargetsKeyword
+        )
+    }
+}
+
+/// Determines if the given identifier is a keyword.
+///
+/// Identifiers matching a keyword must be escaped with `%`.
+pub fn is_keyword(ident: &str) -> bool {
+    let mut lexer = Token::lexer(ident);
+    matches!(lexer.next(), Some(Ok(token)) if token.is_keyword()) && lexer.next().is_none()
+}
+
 impl fmt::Display for Token {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -364,23 +423,9 @@ impl fmt::Display for Token {
     }
 }
 
-mod helpers {
+pub(crate) mod helpers {
     use super::{Error, Token};
-    use logos::{FilterResult, Logos};
-
-    /// Represents a WAC comment token.
-    #[derive(Logos, Debug, Clone, Copy, PartialEq, Eq)]
-    #[logos(error = Error)]
-    #[logos(skip r"[ \t\n\f]+")]
-    pub enum CommentToken<'a> {
-        /// A comment.
-        #[regex(r"//[^\n]*")]
-        Comment(&'a str),
-
-        /// A block comment.
-        #[token(r"/*", block_comment)]
-        BlockComment(&'a str),
-    }
+    use logos::FilterResult;
 
     pub fn string(lex: &mut logos::Lexer<Token>) -> Result<(), Error> {
         let remainder = lex.remainder();
@@ -413,23 +458,6 @@ mod helpers {
         Some(len + 2 /* opening tokens */)
     }
 
-    pub fn block_comment<'a>(
-        lex: &mut logos::Lexer<'a, CommentToken<'a>>,
-    ) -> Result<&'a str, Error> {
-        let span = lex.span();
-        match block_comment_length(lex.remainder().as_bytes()) {
-            Some(len) => {
-                let s = &lex.source()[span.start..span.start + len];
-                lex.bump(len - 2 /* opening tokens */);
-                Ok(s)
-            }
-            None => {
-                lex.bump(lex.remainder().len());
-                Err(Error::UnterminatedComment)
-            }
-        }
-    }
-
     pub fn skip_block_comment(lex: &mut logos::Lexer<Token>) -> FilterResult<(), Error> {
         match block_comment_length(lex.remainder().as_bytes()) {
             Some(len) => {
@@ -448,56 +476,119 @@ mod helpers {
 pub type LexerResult<T> = Result<T, Error>;
 
 /// Implements a WAC lexer.
-pub struct Lexer<'a>(SpannedIter<'a, Token>);
+///
+/// The spans of tokens are offset by the lexer's base offset.
+#[derive(Clone)]
+pub struct Lexer<'a> {
+    source: &'a str,
+    offset: usize,
+    /// The tokens of the source, including comments.
+    tokens: Rc<[(LexerResult<Token>, SourceSpan)]>,
+    /// The index of the next token.
+    index: usize,
+    /// The span of the last token returned, relative to the source.
+    span: logos::Span,
+}
 
 impl<'a> Lexer<'a> {
     /// Creates a new lexer for the given source string.
     pub fn new(source: &'a str) -> Result<Self, (Error, SourceSpan)> {
-        detect_invalid_input(source)?;
-        Ok(Self(Token::lexer(source).spanned()))
+        Self::new_with_offset(source, 0)
+    }
+
+    /// Creates a new lexer for the given source string with the given base offset.
+    ///
+    /// The base offset is added to the spans of tokens; this is used to keep
+    /// spans distinct when multiple source strings are parsed.
+    pub fn new_with_offset(source: &'a str, offset: usize) -> Result<Self, (Error, SourceSpan)> {
+        Self::from_syntax_tokens(source, offset, &crate::cst::tokens(source))
+    }
+
+    /// Creates a new lexer for the given CST tokens of a source string.
+    ///
+    /// The tokens are lexed in the given order, which may differ from the
+    /// order of the source; the spans of the tokens must be in the source.
+    pub(crate) fn from_syntax_tokens<'b>(
+        source: &'a str,
+        offset: usize,
+        tokens: impl IntoIterator<Item = &'b SyntaxToken<'a>>,
+    ) -> Result<Self, (Error, SourceSpan)>
+    where
+        'a: 'b,
+    {
+        detect_invalid_input(source).map_err(|(e, span)| {
+            (
+                e,
+                SourceSpan::new((span.offset() + offset).into(), span.len().into()),
+            )
+        })?;
+
+        let tokens = tokens
+            .into_iter()
+            .filter_map(|t| {
+                let result = match t.kind {
+                    SyntaxKind::Token(token) => Ok(token),
+                    SyntaxKind::Error(e) => Err(e),
+                    SyntaxKind::Whitespace => return None,
+                };
+
+                Some((
+                    result,
+                    to_source_span(
+                        t.span.offset() + offset..t.span.offset() + t.span.len() + offset,
+                    ),
+                ))
+            })
+            .collect();
+
+        Ok(Self {
+            source,
+            offset,
+            tokens,
+            index: 0,
+            span: 0..0,
+        })
     }
 
     /// Gets the source string of the given span.
     pub fn source(&self, span: SourceSpan) -> &'a str {
-        &self.0.source()[span.offset()..span.offset() + span.len()]
+        let start = span.offset() - self.offset;
+        &self.source[start..start + span.len()]
     }
 
     /// Gets the current span of the lexer.
     pub fn span(&self) -> SourceSpan {
-        let mut span = self.0.span();
-        if span.end == self.0.source().len() {
+        let mut span = self.span.clone();
+        if span.end == self.source.len() {
             // Currently miette silently fails to display a label
             // if the span is at the end of the source; this means
             // we can't properly show the "end of input" span.
             // For now, have the span point at the last byte in the source.
             // See: https://github.com/zkat/miette/issues/219
-            span.start -= 1;
+            span.start = span.start.saturating_sub(1);
             span.end = span.start + 1;
         }
 
-        to_source_span(span)
+        to_source_span(span.start + self.offset..span.end + self.offset)
     }
 
     /// Peeks at the next token.
     pub fn peek(&self) -> Option<(LexerResult<Token>, SourceSpan)> {
-        let mut lexer = self.0.clone().spanned();
-        lexer.next().map(|(r, s)| (r, to_source_span(s)))
+        self.tokens_from(self.index).next().map(|(_, t)| t)
     }
 
     /// Peeks at the token after the next token.
     pub fn peek2(&self) -> Option<(LexerResult<Token>, SourceSpan)> {
-        let mut lexer = self.0.clone().spanned();
-        lexer.next();
-        lexer.next().map(|(r, s)| (r, to_source_span(s)))
+        self.tokens_from(self.index).nth(1).map(|(_, t)| t)
     }
 
     /// Consumes available documentation comment tokens.
     pub fn comments<'b>(&'b self) -> Result<Vec<(&'a str, SourceSpan)>, (Error, SourceSpan)> {
         let mut comments = Vec::new();
-        let mut lexer = self.0.clone().morph::<helpers::CommentToken>().spanned();
-        while let Some((Ok(token), span)) = lexer.next() {
-            match token {
-                helpers::CommentToken::Comment(c) | helpers::CommentToken::BlockComment(c) => {
+        for (result, span) in self.tokens[self.index..].iter().cloned() {
+            match result {
+                Ok(Token::Comment) | Ok(Token::BlockComment) => {
+                    let c = self.source(span);
                     let c = if let Some(c) = c.strip_prefix("///") {
                         c.trim()
                     } else if let Some(c) = c.strip_prefix("/**") {
@@ -508,20 +599,106 @@ impl<'a> Lexer<'a> {
                     } else {
                         continue;
                     };
-                    comments.push((c, to_source_span(span)));
+                    comments.push((c, span));
                 }
+                _ => break,
             }
         }
         Ok(comments)
     }
+
+    /// Gets the non-comment tokens starting at the given index, along with their indexes.
+    fn tokens_from(
+        &self,
+        index: usize,
+    ) -> impl Iterator<Item = (usize, (LexerResult<Token>, SourceSpan))> + '_ {
+        self.tokens[index..]
+            .iter()
+            .cloned()
+            .enumerate()
+            .map(move |(i, t)| (index + i, t))
+            .filter(|(_, (r, _))| !matches!(r, Ok(Token::Comment) | Ok(Token::BlockComment)))
+    }
 }
 
 impl<'a> Iterator for Lexer<'a> {
     type Item = (LexerResult<Token>, SourceSpan);
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.0.next().map(|(r, s)| (r, to_source_span(s)))
+        match self.tokens_from(self.index).next() {
+            Some((index, (result, span))) => {
+                self.index = index + 1;
+                let start = span.offset() - self.offset;
+                self.span = start..start + span.len();
+                Some((result, span))
+            }
+            None => {
+                self.index = self.tokens.len();
+                self.span = self.source.len()..self.source.len();
+                None
+            }
+        }
+    }
+}
+
+/// Gets the non-documentation comments of the given source string.
+///
+/// Comments are returned in source order; unterminated comments
+/// and comments following a lexer error are not returned.
+pub(crate) fn regular_comments(source: &str) -> Vec<(&str, SourceSpan)> {
+    fn gap<'a>(
+        source: &'a str,
+        start: usize,
+        end: usize,
+        comments: &mut Vec<(&'a str, SourceSpan)>,
+    ) {
+        let bytes = source.as_bytes();
+        let mut offset = start;
+        while offset + 1 < end {
+            if bytes[offset] != b'/' {
+                offset += 1;
+                continue;
+            }
+
+            let len = match bytes[offset + 1] {
+                b'/' => source[offset..end].find('\n').unwrap_or(end - offset),
+                b'*' => match helpers::block_comment_length(&bytes[offset + 2..end]) {
+                    Some(len) => len,
+                    None => return,
+                },
+                _ => {
+                    offset += 1;
+                    continue;
+                }
+            };
+
+            let comment = source[offset..offset + len].trim_end();
+            let doc =
+                comment.starts_with("///") || (comment.starts_with("/**") && comment != "/**/");
+            if !doc {
+                comments.push((
+                    comment,
+                    SourceSpan::new(offset.into(), comment.len().into()),
+                ));
+            }
+
+            offset += len;
+        }
+    }
+
+    let mut comments = Vec::new();
+    let mut start = 0;
+    for (result, span) in Token::lexer(source).spanned() {
+        if result.is_err() {
+            return comments;
+        }
+
+        gap(source, start, span.start, &mut comments);
+        start = span.end;
     }
+
+    gap(source, start, source.len(), &mut comments);
+    comments
 }
 
 #[cfg(test)]
@@ -790,6 +967,46 @@ foo:bar/baz@1.0.0-0A.is.legal
         );
     }
 
+    #[test]
+    fn version_requirements() {
+        assert_lex(
+            r#"
+foo:bar@^1.2
+foo:bar@~0.2
+foo:bar/baz@>=1.0.0
+foo:bar/baz@<2
+foo:bar/baz@=1.2.3
+foo:bar@*
+foo:bar@1.2
+foo:bar/baz@>=1.0, <2.0
+foo:bar@>=1.0,<1.5
+"#,
+            &[
+                (Ok(Token::PackageName), "foo:bar@^1.2", 1..13),
+                (Ok(Token::PackageName), "foo:bar@~0.2", 14..26),
+                (Ok(Token::PackagePath), "foo:bar/baz@>=1.0.0", 27..46),
+                (Ok(Token::PackagePath), "foo:bar/baz@<2", 47..61),
+                (Ok(Token::PackagePath), "foo:bar/baz@=1.2.3", 62..80),
+                (Ok(Token::PackageName), "foo:bar@*", 81..90),
+                (Ok(Token::PackageName), "foo:bar@1.2", 91..102),
+                (Ok(Token::PackagePath), "foo:bar/baz@>=1.0, <2.0", 103..126),
+                (Ok(Token::PackageName), "foo:bar@>=1.0,<1.5", 127..145),
+            ],
+        );
+    }
+
+    #[test]
+    fn keyword_identifiers() {
+        assert!(super::is_keyword("import"));
+        assert!(super::is_keyword("float32"));
+        assert!(super::is_keyword("targets"));
+        assert!(!super::is_keyword("imports"));
+        assert!(!super::is_keyword("%import"));
+        assert!(!super::is_keyword("foo"));
+        assert!(!super::is_keyword("import foo"));
+        assert!(!super::is_keyword(""));
+    }
+
     #[test]
     fn keywords() {
         assert_lex(
@@ -3,7 +3,10 @@
 #![deny(missing_docs)]
 
 pub mod ast;
+pub mod cst;
 pub mod lexer;
 mod resolution;
+pub mod source_map;
+pub mod sources;
 
 pub use resolution::*;
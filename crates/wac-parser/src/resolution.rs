@@ -1,20 +1,28 @@
 //! Module for resolving WAC documents.
 
-use self::{ast::AstResolver, encoding::Encoder, package::Package};
+use self::{ast::AstResolver, encoding::Encoder};
+use crate::sources::Sources;
 use id_arena::{Arena, Id};
 use indexmap::IndexMap;
 use miette::{Diagnostic, SourceSpan};
 use semver::Version;
 use serde::{Serialize, Serializer};
-use std::{fmt, sync::Arc};
+use std::fmt;
 
 mod ast;
+mod builder;
+mod decoding;
 mod encoding;
+mod graph;
+mod lints;
 mod package;
 mod types;
 
+pub use builder::CompositionBuilder;
+pub use decoding::DecodedDocument;
 pub use encoding::EncodingOptions;
-pub use package::PackageKey;
+pub use lints::*;
+pub use package::{Package, PackageKey, ResolvedPackage};
 pub use types::*;
 
 fn serialize_arena<T, S>(arena: &Arena<T>, serializer: S) -> std::result::Result<S::Ok, S::Error>
@@ -122,6 +130,8 @@ pub enum InstanceOperation {
     Access,
     /// The operation was a spread of the form `...id` or `<expr>...`.
     Spread,
+    /// The operation was a destructuring let binding of the form `let { name } = <expr>;`.
+    Destructure,
 }
 
 impl fmt::Display for InstanceOperation {
@@ -129,16 +139,17 @@ impl fmt::Display for InstanceOperation {
         match self {
             Self::Access => write!(f, "an access operation"),
             Self::Spread => write!(f, "a spread operation"),
+            Self::Destructure => write!(f, "a destructuring operation"),
         }
     }
 }
 
 /// Represents a resolution error.
 #[derive(thiserror::Error, Diagnostic, Debug)]
-#[diagnostic(code("failed to resolve document"))]
 pub enum Error {
     /// An undefined name was encountered.
     #[error("undefined name `{name}`")]
+    #[diagnostic(code(wac::resolve::undefined_name))]
     UndefinedName {
         /// The name that was undefined.
         name: String,
@@ -148,6 +159,7 @@ pub enum Error {
     },
     /// A duplicate name was encountered.
     #[error("`{name}` is already defined")]
+    #[diagnostic(code(wac::resolve::duplicate_name))]
     DuplicateName {
         /// The duplicate name.
         name: String,
@@ -160,6 +172,7 @@ pub enum Error {
     },
     /// Duplicate interface export.
     #[error("duplicate interface export `{name}`{iface}", iface = InterfaceNameDisplay(.interface_name))]
+    #[diagnostic(code(wac::resolve::duplicate_interface_export))]
     DuplicateInterfaceExport {
         /// The name of the duplicate export.
         name: String,
@@ -171,6 +184,7 @@ pub enum Error {
     },
     /// Duplicate world item.
     #[error("{kind} `{name}` conflicts with existing {kind} of the same name in world `{world}`")]
+    #[diagnostic(code(wac::resolve::duplicate_world_item))]
     DuplicateWorldItem {
         /// The extern kind of the item.
         kind: ExternKind,
@@ -184,6 +198,7 @@ pub enum Error {
     },
     /// The name is not a function type or interface.
     #[error("`{name}` ({kind}) is not a function type or interface")]
+    #[diagnostic(code(wac::resolve::not_func_or_interface))]
     NotFuncOrInterface {
         /// The name that is not a function type or interface.
         name: String,
@@ -195,6 +210,7 @@ pub enum Error {
     },
     /// The name is not an interface.
     #[error("`{name}` ({kind}) is not an interface")]
+    #[diagnostic(code(wac::resolve::not_interface))]
     NotInterface {
         /// The name that is not an interface.
         name: String,
@@ -206,6 +222,7 @@ pub enum Error {
     },
     /// Duplicate name in a world include.
     #[error("duplicate `{name}` in world include `with` clause")]
+    #[diagnostic(code(wac::resolve::duplicate_world_include_name))]
     DuplicateWorldIncludeName {
         /// The name of the duplicate include.
         name: String,
@@ -215,6 +232,7 @@ pub enum Error {
     },
     /// The name is not a world.
     #[error("`{name}` ({kind}) is not a world")]
+    #[diagnostic(code(wac::resolve::not_world))]
     NotWorld {
         /// The name that is not a world.
         name: String,
@@ -226,6 +244,7 @@ pub enum Error {
     },
     /// Missing source item for `with` clause in world include.
     #[error("world `{world}` does not have an import or export named `{name}`")]
+    #[diagnostic(code(wac::resolve::missing_world_include))]
     MissingWorldInclude {
         /// The name of the world.
         world: String,
@@ -237,6 +256,7 @@ pub enum Error {
     },
     /// A conflict was encountered in a world include.
     #[error("{kind} `{name}` from world `{from}` conflicts with {kind} of the same name in world `{to}`")]
+    #[diagnostic(code(wac::resolve::world_include_conflict))]
     WorldIncludeConflict {
         /// The extern kind of the item.
         kind: ExternKind,
@@ -255,6 +275,7 @@ pub enum Error {
     },
     /// A name is not a type defined in an interface.
     #[error("a type named `{name}` is not defined in interface `{interface_name}`")]
+    #[diagnostic(code(wac::resolve::undefined_interface_type))]
     UndefinedInterfaceType {
         /// The name of the type.
         name: String,
@@ -266,6 +287,7 @@ pub enum Error {
     },
     /// A name is not a value type defined in an interface.
     #[error("`{name}` ({kind}) is not a value type in interface `{interface_name}`")]
+    #[diagnostic(code(wac::resolve::not_interface_value_type))]
     NotInterfaceValueType {
         /// The name that is not a value type.
         name: String,
@@ -279,6 +301,7 @@ pub enum Error {
     },
     /// A duplicate resource constructor was encountered.
     #[error("duplicate constructor for resource `{resource}`")]
+    #[diagnostic(code(wac::resolve::duplicate_resource_constructor))]
     DuplicateResourceConstructor {
         /// The name of the resource.
         resource: String,
@@ -288,6 +311,7 @@ pub enum Error {
     },
     /// A duplicate resource method was encountered.
     #[error("duplicate method `{name}` for resource `{resource}`")]
+    #[diagnostic(code(wac::resolve::duplicate_resource_method))]
     DuplicateResourceMethod {
         /// The name of the method.
         name: String,
@@ -299,6 +323,7 @@ pub enum Error {
     },
     /// A duplicate variant case was encountered.
     #[error("duplicate case `{case}` for variant type `{name}`")]
+    #[diagnostic(code(wac::resolve::duplicate_variant_case))]
     DuplicateVariantCase {
         /// The name of the case.
         case: String,
@@ -310,6 +335,7 @@ pub enum Error {
     },
     /// A duplicate record field was encountered.
     #[error("duplicate field `{field}` for record type `{name}`")]
+    #[diagnostic(code(wac::resolve::duplicate_record_field))]
     DuplicateRecordField {
         /// The name of the field.
         field: String,
@@ -321,6 +347,7 @@ pub enum Error {
     },
     /// A duplicate enum case was encountered.
     #[error("duplicate case `{case}` for enum type `{name}`")]
+    #[diagnostic(code(wac::resolve::duplicate_enum_case))]
     DuplicateEnumCase {
         /// The name of the case.
         case: String,
@@ -332,6 +359,7 @@ pub enum Error {
     },
     /// A duplicate flag was encountered.
     #[error("duplicate flag `{flag}` for flags type `{name}`")]
+    #[diagnostic(code(wac::resolve::duplicate_flag))]
     DuplicateFlag {
         /// The name of the flag.
         flag: String,
@@ -343,6 +371,7 @@ pub enum Error {
     },
     /// The name cannot be used as an alias type.
     #[error("`{name}` ({kind}) cannot be used in a type alias")]
+    #[diagnostic(code(wac::resolve::invalid_alias_type))]
     InvalidAliasType {
         /// The name that cannot be used as an alias type.
         name: String,
@@ -354,6 +383,7 @@ pub enum Error {
     },
     /// The name is not a function type.
     #[error("`{name}` ({kind}) is not a function type")]
+    #[diagnostic(code(wac::resolve::not_func_type))]
     NotFuncType {
         /// The name that is not a function type.
         name: String,
@@ -365,6 +395,7 @@ pub enum Error {
     },
     /// The name is not a resource type.
     #[error("`{name}` ({kind}) is not a resource type")]
+    #[diagnostic(code(wac::resolve::not_resource_type))]
     NotResourceType {
         /// The name that is not a resource type.
         name: String,
@@ -376,6 +407,7 @@ pub enum Error {
     },
     /// The name is not a value type.
     #[error("`{name}` ({kind}) cannot be used as a value type")]
+    #[diagnostic(code(wac::resolve::not_value_type))]
     NotValueType {
         /// The name that is not a value type.
         name: String,
@@ -387,6 +419,7 @@ pub enum Error {
     },
     /// A duplicate function parameter was encountered.
     #[error("duplicate {kind} parameter `{name}`")]
+    #[diagnostic(code(wac::resolve::duplicate_parameter))]
     DuplicateParameter {
         /// The name of the parameter.
         name: String,
@@ -398,6 +431,7 @@ pub enum Error {
     },
     /// A duplicate result was encountered.
     #[error("duplicate {kind} result `{name}`")]
+    #[diagnostic(code(wac::resolve::duplicate_result))]
     DuplicateResult {
         /// The name of the result.
         name: String,
@@ -409,6 +443,7 @@ pub enum Error {
     },
     /// A borrow type was encountered in a function result.
     #[error("function result cannot recursively contain a borrow type")]
+    #[diagnostic(code(wac::resolve::borrow_in_result))]
     BorrowInResult {
         /// The span where the error occurred.
         #[label(primary, "borrow type in result")]
@@ -416,6 +451,7 @@ pub enum Error {
     },
     /// An unknown package was encountered.
     #[error("unknown package `{name}`")]
+    #[diagnostic(code(wac::resolve::unknown_package))]
     UnknownPackage {
         /// The name of the package.
         name: String,
@@ -425,6 +461,7 @@ pub enum Error {
     },
     /// A package failed to parse.
     #[error("failed to parse package `{name}`")]
+    #[diagnostic(code(wac::resolve::package_parse_failure))]
     PackageParseFailure {
         /// The name of the package.
         name: String,
@@ -437,6 +474,7 @@ pub enum Error {
     },
     /// A package is missing an export.
     #[error("{prev}package `{name}` has no export named `{export}`", prev = ParentPathDisplay(.kind, .path))]
+    #[diagnostic(code(wac::resolve::package_missing_export))]
     PackageMissingExport {
         /// The name of the package.
         name: String,
@@ -452,6 +490,7 @@ pub enum Error {
     },
     /// A missing export in a package path was encountered.
     #[error("`{name}` ({kind}) has no export named `{export}`")]
+    #[diagnostic(code(wac::resolve::package_path_missing_export))]
     PackagePathMissingExport {
         /// The name that has no matching export.
         name: String,
@@ -465,6 +504,7 @@ pub enum Error {
     },
     /// A missing import on a component was encountered.
     #[error("component `{package}` has no import named `{import}`")]
+    #[diagnostic(code(wac::resolve::missing_component_import))]
     MissingComponentImport {
         /// The name of the package.
         package: String,
@@ -476,6 +516,7 @@ pub enum Error {
     },
     /// A mismatched instantiation argument was encountered.
     #[error("mismatched instantiation argument `{name}`")]
+    #[diagnostic(code(wac::resolve::mismatched_instantiation_arg))]
     MismatchedInstantiationArg {
         /// The name of the argument.
         name: String,
@@ -488,6 +529,7 @@ pub enum Error {
     },
     /// A duplicate instantiation argument was encountered.
     #[error("duplicate instantiation argument `{name}`")]
+    #[diagnostic(code(wac::resolve::duplicate_instantiation_arg))]
     DuplicateInstantiationArg {
         /// The name of the argument.
         name: String,
@@ -497,6 +539,7 @@ pub enum Error {
     },
     /// A missing instantiation argument was encountered.
     #[error("missing instantiation argument `{name}` for package `{package}`")]
+    #[diagnostic(code(wac::resolve::missing_instantiation_arg))]
     MissingInstantiationArg {
         /// The name of the argument.
         name: String,
@@ -508,6 +551,7 @@ pub enum Error {
     },
     /// An instantiation argument conflict was encountered.
     #[error("implicit instantiation argument `{name}` ({kind}) conflicts with an explicit import")]
+    #[diagnostic(code(wac::resolve::instantiation_arg_conflict))]
     InstantiationArgConflict {
         /// The name of the argument.
         name: String,
@@ -522,6 +566,7 @@ pub enum Error {
     },
     /// An explicitly imported item conflicts with an implicit import from an instantiation.
     #[error("import name `{name}` conflicts with an instance that was implicitly imported by an instantiation of `{package}`")]
+    #[diagnostic(code(wac::resolve::import_conflict))]
     ImportConflict {
         /// The name of the argument.
         name: String,
@@ -536,6 +581,7 @@ pub enum Error {
     },
     /// An instantiation argument conflict was encountered.
     #[error("failed to merge instantiation argument `{name}` with an instance that was implicitly imported by the instantiation of `{package}`")]
+    #[diagnostic(code(wac::resolve::instantiation_arg_merge_failure))]
     InstantiationArgMergeFailure {
         /// The name of the argument.
         name: String,
@@ -555,6 +601,7 @@ pub enum Error {
     },
     /// An unmergeable instantiation argument was encountered.
     #[error("implicit instantiation argument `{name}` ({kind}) conflicts with an implicitly imported argument from the instantiation of `{package}`")]
+    #[diagnostic(code(wac::resolve::unmergeable_instantiation_arg))]
     UnmergeableInstantiationArg {
         /// The name of the argument.
         name: String,
@@ -571,6 +618,7 @@ pub enum Error {
     },
     /// An operation was performed on something that was not an instance.
     #[error("an instance is required to perform {operation}")]
+    #[diagnostic(code(wac::resolve::not_an_instance))]
     NotAnInstance {
         /// The kind of item that was not an instance.
         kind: String,
@@ -582,6 +630,7 @@ pub enum Error {
     },
     /// An instance is missing an export.
     #[error("the instance has no export named `{name}`")]
+    #[diagnostic(code(wac::resolve::missing_instance_export))]
     MissingInstanceExport {
         /// The name of the export.
         name: String,
@@ -591,6 +640,7 @@ pub enum Error {
     },
     /// An export requires an `as`` clause.
     #[error("export statement requires an `as` clause as the export name cannot be inferred")]
+    #[diagnostic(code(wac::resolve::export_requires_as))]
     ExportRequiresAs {
         /// The span where the error occurred.
         #[label(primary, "an `as` clause is required")]
@@ -598,6 +648,7 @@ pub enum Error {
     },
     /// An export conflicts with a definition.
     #[error("export `{name}` conflicts with {kind} definition")]
+    #[diagnostic(code(wac::resolve::export_conflict))]
     ExportConflict {
         /// The name of the export.
         name: String,
@@ -615,6 +666,7 @@ pub enum Error {
     },
     /// A duplicate extern name was encountered.
     #[error("duplicate {kind} `{name}`")]
+    #[diagnostic(code(wac::resolve::duplicate_extern_name))]
     DuplicateExternName {
         /// The name of the export.
         name: String,
@@ -632,6 +684,7 @@ pub enum Error {
     },
     /// An invalid extern name was encountered.
     #[error("{kind} name `{name}` is not valid")]
+    #[diagnostic(code(wac::resolve::invalid_extern_name))]
     InvalidExternName {
         /// The name of the export.
         name: String,
@@ -646,6 +699,7 @@ pub enum Error {
     },
     /// A use of a type conflicts with an extern item.
     #[error("use of type `{name}` conflicts with an {kind} of the same name")]
+    #[diagnostic(code(wac::resolve::use_conflict))]
     UseConflict {
         /// The name of the used type.
         name: String,
@@ -660,6 +714,7 @@ pub enum Error {
     },
     /// A fill argument (`...`) was not the last argument.
     #[error("implicit import argument `...` must be the last argument")]
+    #[diagnostic(code(wac::resolve::fill_argument_not_last))]
     FillArgumentNotLast {
         /// The span where the error occurred.
         #[label(primary, "must be last argument")]
@@ -667,6 +722,7 @@ pub enum Error {
     },
     /// A spread instantiation argument did not match any import names.
     #[error("the instance has no matching exports for the remaining unsatisfied arguments")]
+    #[diagnostic(code(wac::resolve::spread_instantiation_no_match))]
     SpreadInstantiationNoMatch {
         /// The span where the error occurred.
         #[label(primary, "no matching exports for the instance")]
@@ -676,6 +732,7 @@ pub enum Error {
     #[error(
         "instance has no exports or all exports of the instance match previously exported names"
     )]
+    #[diagnostic(code(wac::resolve::spread_export_no_effect))]
     SpreadExportNoEffect {
         /// The span where the error occurred.
         #[label(primary, "spreading the exports of this instance has no effect")]
@@ -683,6 +740,7 @@ pub enum Error {
     },
     /// An import is not in the target world.
     #[error("target world `{world}` does not have an import named `{name}`")]
+    #[diagnostic(code(wac::resolve::import_not_in_target))]
     ImportNotInTarget {
         /// The import name.
         name: String,
@@ -694,6 +752,7 @@ pub enum Error {
     },
     /// Missing an export for the target world.
     #[error("target world `{world}` requires an export named `{name}`")]
+    #[diagnostic(code(wac::resolve::missing_target_export))]
     MissingTargetExport {
         /// The export name.
         name: String,
@@ -707,6 +766,7 @@ pub enum Error {
     },
     /// An import or export has a mismatched type for the target world.
     #[error("{kind} `{name}` has a mismatched type for target world `{world}`")]
+    #[diagnostic(code(wac::resolve::target_mismatch))]
     TargetMismatch {
         /// The kind of mismatch.
         kind: ExternKind,
@@ -721,6 +781,24 @@ pub enum Error {
         #[source]
         source: anyhow::Error,
     },
+    /// An included document was not loaded.
+    #[error("included document `{path}` was not loaded")]
+    #[diagnostic(code(wac::resolve::include_not_loaded))]
+    IncludeNotLoaded {
+        /// The path of the included document.
+        path: String,
+        /// The span where the error occurred.
+        #[label(primary, "document included here")]
+        span: SourceSpan,
+    },
+    /// An export statement was encountered in an included document.
+    #[error("export statements are not allowed in included documents")]
+    #[diagnostic(code(wac::resolve::export_in_include))]
+    ExportInInclude {
+        /// The span where the error occurred.
+        #[label(primary, "export statement in included document")]
+        span: SourceSpan,
+    },
 }
 
 /// Represents a resolution result.
@@ -761,7 +839,8 @@ impl ItemKind {
         }
     }
 
-    fn as_str(&self, definitions: &Definitions) -> &'static str {
+    /// Gets a description of the item kind.
+    pub fn as_str(&self, definitions: &Definitions) -> &'static str {
         match self {
             ItemKind::Resource(_) => "resource",
             ItemKind::Func(_) => "function",
@@ -889,19 +968,69 @@ pub struct Composition {
     /// The map of export names to items.
     #[serde(serialize_with = "serialize_id_value_map")]
     pub exports: IndexMap<String, ItemId>,
+    /// The map of names defined in the composition to items and the span of the name.
+    #[serde(skip)]
+    pub names: IndexMap<String, (ItemId, SourceSpan)>,
+    /// The map of items to the span of the source that introduced them.
+    ///
+    /// This contains the spans of imports, instantiations, and aliases.
+    #[serde(skip)]
+    pub spans: IndexMap<ItemId, SourceSpan>,
+    /// The map of export names to the span of the source that introduced them.
+    #[serde(skip)]
+    pub export_spans: IndexMap<String, SourceSpan>,
+    /// The resolved references to names defined in the composition's document.
+    ///
+    /// Each entry is the span of the reference and the span of the referenced name.
+    #[serde(skip)]
+    pub references: Vec<(SourceSpan, SourceSpan)>,
+    /// The warnings reported by lints for the composition, in source order.
+    ///
+    /// Use [`LintLevels::apply`] to filter the warnings by lint level.
+    #[serde(skip)]
+    pub warnings: Vec<Warning>,
 }
 
 impl Composition {
     /// Creates a new composition from an AST document.
+    ///
+    /// Resolution stops at the first error.
     pub fn from_ast<'a>(
         document: &'a crate::ast::Document<'a>,
-        packages: IndexMap<PackageKey<'a>, Arc<Vec<u8>>>,
+        packages: IndexMap<PackageKey<'a>, ResolvedPackage>,
     ) -> ResolutionResult<Self> {
         AstResolver::new(document, packages).resolve()
     }
 
+    /// Creates a new composition from an AST document, reporting every error.
+    ///
+    /// Unlike [`Composition::from_ast`], resolution continues after an error.
+    /// Names bound by statements that fail to resolve are poisoned: errors
+    /// caused by references to poisoned names are not reported as they are
+    /// a consequence of an earlier error.
+    pub fn from_ast_recovering<'a>(
+        document: &'a crate::ast::Document<'a>,
+        packages: IndexMap<PackageKey<'a>, ResolvedPackage>,
+    ) -> Result<Self, Vec<Error>> {
+        AstResolver::new(document, packages).resolve_all()
+    }
+
     /// Encode the composition into a WebAssembly component.
     pub fn encode(&self, options: EncodingOptions) -> anyhow::Result<Vec<u8>> {
-        Encoder::new(self, options).encode()
+        Encoder::new(self, options, None).encode()
+    }
+
+    /// Encode the composition into a WebAssembly component with a source map.
+    ///
+    /// The encoded component contains a custom section mapping its items to
+    /// their locations in the given sources.
+    ///
+    /// See the [`source_map`](crate::source_map) module for more information.
+    pub fn encode_with_source_map(
+        &self,
+        options: EncodingOptions,
+        sources: &Sources,
+    ) -> anyhow::Result<Vec<u8>> {
+        Encoder::new(self, options, Some(sources)).encode()
     }
 }
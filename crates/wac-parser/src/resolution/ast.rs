@@ -1,18 +1,18 @@
 use super::{
-    package::{Package, PackageKey},
+    package::{Package, PackageKey, ResolvedPackage},
     Composition, DefinedType, Definitions, Enum, Error, ExternKind, Flags, Func, FuncId, FuncKind,
-    FuncResult, Interface, InterfaceId, ItemKind, PrimitiveType, Record, ResolutionResult,
-    Resource, ResourceId, SubtypeChecker, Type, ValueType, Variant, World, WorldId,
+    FuncResult, Interface, InterfaceId, ItemKind, Lint, PrimitiveType, Record, ResolutionResult,
+    Resource, ResourceId, SubtypeChecker, Type, ValueType, Variant, Warning, World, WorldId,
 };
 use crate::{ast, method_extern_name, InstanceOperation, Item, ItemId, PackageId, UsedType};
 use anyhow::Context;
 use id_arena::Arena;
 use indexmap::{IndexMap, IndexSet};
 use miette::SourceSpan;
-use semver::Version;
+use semver::{Version, VersionReq};
 use std::{
+    cell::RefCell,
     collections::{hash_map, HashMap, HashSet},
-    sync::Arc,
 };
 use wasmparser::names::{ComponentName, ComponentNameKind};
 
@@ -58,6 +58,21 @@ struct State<'a> {
     imports: IndexMap<String, Import<'a>>,
     /// The map of exported items.
     exports: IndexMap<String, Export>,
+    /// The resolved references to names.
+    ///
+    /// Each entry is the span of the reference and the span of the referenced name.
+    references: RefCell<Vec<(SourceSpan, SourceSpan)>>,
+    /// The names bound by statements that failed to resolve.
+    ///
+    /// Errors for undefined references to poisoned names are not reported as
+    /// they are a consequence of an earlier error.
+    poisoned: HashSet<&'a str>,
+    /// The warnings reported by lints.
+    warnings: Vec<Warning>,
+    /// The documents that have been included.
+    included: Vec<&'a ast::Document<'a>>,
+    /// The spans of the source that introduced the items in the root scope.
+    spans: IndexMap<ItemId, SourceSpan>,
 }
 
 impl<'a> State<'a> {
@@ -70,36 +85,41 @@ impl<'a> State<'a> {
             aliases: Default::default(),
             imports: Default::default(),
             exports: Default::default(),
+            references: Default::default(),
+            poisoned: Default::default(),
+            warnings: Default::default(),
+            included: Default::default(),
+            spans: Default::default(),
         }
     }
 
+    /// Records a reference to a name.
+    fn reference(&self, span: SourceSpan, definition: SourceSpan) {
+        self.references.borrow_mut().push((span, definition));
+    }
+
     // Gets an item by identifier from the root scope.
     fn root_item(&self, id: &ast::Ident<'a>) -> ResolutionResult<(ItemId, &Item)> {
         let scope = self.root_scope();
 
-        let id = scope
-            .get(id.string)
-            .ok_or(Error::UndefinedName {
-                name: id.string.to_owned(),
-                span: id.span,
-            })?
-            .0;
+        let (item, definition) = scope.get(id.string).ok_or(Error::UndefinedName {
+            name: id.string.to_owned(),
+            span: id.span,
+        })?;
 
-        Ok((id, &scope.items[id]))
+        self.reference(id.span, definition);
+        Ok((item, &scope.items[item]))
     }
 
     /// Gets an item by identifier from the local (current) scope.
     fn local_item(&self, id: &ast::Ident<'a>) -> ResolutionResult<(ItemId, &Item)> {
-        let id = self
-            .current
-            .get(id.string)
-            .ok_or(Error::UndefinedName {
-                name: id.string.to_owned(),
-                span: id.span,
-            })?
-            .0;
+        let (item, definition) = self.current.get(id.string).ok_or(Error::UndefinedName {
+            name: id.string.to_owned(),
+            span: id.span,
+        })?;
 
-        Ok((id, &self.current.items[id]))
+        self.reference(id.span, definition);
+        Ok((item, &self.current.items[item]))
     }
 
     /// Gets an item by identifier from the local (current) scope or the root scope.
@@ -108,8 +128,9 @@ impl<'a> State<'a> {
             return self.local_item(id);
         }
 
-        if let Some((id, _)) = self.current.get(id.string) {
-            return Ok((id, &self.current.items[id]));
+        if let Some((item, definition)) = self.current.get(id.string) {
+            self.reference(id.span, definition);
+            return Ok((item, &self.current.items[item]));
         }
 
         self.root_item(id)
@@ -151,62 +172,116 @@ impl<'a> State<'a> {
     }
 }
 
+/// Validates the given extern name.
+fn validate_extern_name(
+    name: &str,
+    kind: ExternKind,
+    span: SourceSpan,
+) -> ResolutionResult<ComponentName> {
+    ComponentName::new(name, 0).map_err(|e| {
+        let msg = e.to_string();
+        Error::InvalidExternName {
+            name: name.to_string(),
+            kind,
+            span,
+            source: anyhow::anyhow!(
+                "{msg}",
+                msg = msg.strip_suffix(" (at offset 0x0)").unwrap_or(&msg)
+            ),
+        }
+    })
+}
+
+/// Validates the given import name.
+pub(super) fn validate_import_name(name: &str, span: SourceSpan) -> ResolutionResult<()> {
+    validate_extern_name(name, ExternKind::Import, span)?;
+    Ok(())
+}
+
+/// Validates the given export name.
+pub(super) fn validate_export_name(name: &str, span: SourceSpan) -> ResolutionResult<()> {
+    match validate_extern_name(name, ExternKind::Export, span)?.kind() {
+        ComponentNameKind::Hash(_)
+        | ComponentNameKind::Url(_)
+        | ComponentNameKind::Dependency(_) => Err(Error::InvalidExternName {
+            name: name.to_string(),
+            kind: ExternKind::Export,
+            span,
+            source: anyhow::anyhow!("export name cannot be a hash, url, or dependency"),
+        }),
+        _ => Ok(()),
+    }
+}
+
 pub struct AstResolver<'a> {
     document: &'a ast::Document<'a>,
     definitions: Definitions,
-    packages: IndexMap<PackageKey<'a>, Arc<Vec<u8>>>,
+    packages: IndexMap<PackageKey<'a>, ResolvedPackage>,
+    /// Whether resolution stops at the first error.
+    fail_fast: bool,
 }
 
 impl<'a> AstResolver<'a> {
     pub fn new(
         document: &'a ast::Document<'a>,
-        packages: IndexMap<PackageKey<'a>, Arc<Vec<u8>>>,
+        packages: IndexMap<PackageKey<'a>, ResolvedPackage>,
     ) -> Self {
         Self {
             document,
             definitions: Default::default(),
             packages,
+            fail_fast: false,
         }
     }
 
     pub fn resolve(mut self) -> ResolutionResult<Composition> {
+        self.fail_fast = true;
+        self.resolve_all()
+            .map_err(|mut errors| errors.swap_remove(0))
+    }
+
+    pub fn resolve_all(mut self) -> Result<Composition, Vec<Error>> {
         let mut state = State::new();
+        let mut errors = Vec::new();
 
-        for stmt in &self.document.statements {
-            match stmt {
-                ast::Statement::Import(i) => self.import_statement(&mut state, i)?,
-                ast::Statement::Type(t) => self.type_statement(&mut state, t)?,
-                ast::Statement::Let(l) => self.let_statement(&mut state, l)?,
-                ast::Statement::Export(e) => self.export_statement(&mut state, e)?,
-            }
-        }
+        let document = self.document;
+        self.statements(&mut state, &document.statements, false, &mut errors);
 
         // If there's a target world in the directive, validate the composition
-        // conforms to the target
-        if let Some(path) = &self.document.directive.targets {
-            let item = self.resolve_package_export(&mut state, path)?;
-            match item {
-                ItemKind::Type(Type::World(world)) => {
-                    self.validate_target(&state, path, world)?;
-                }
-                _ => {
-                    return Err(Error::NotWorld {
-                        name: path.string.to_owned(),
-                        kind: item.as_str(&self.definitions).to_owned(),
-                        span: path.span,
-                    });
+        // conforms to the target; this is skipped if there were errors as
+        // statements that failed to resolve may be missing from the composition
+        if errors.is_empty() {
+            if let Some(path) = &self.document.directive.targets {
+                if let Err(e) = self.target(&mut state, path) {
+                    errors.push(e);
                 }
             }
         }
 
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+
         assert!(state.scopes.is_empty());
 
+        self.lint_unused(&mut state);
+        state.warnings.sort_by_key(|w| w.span().offset());
+
         Ok(Composition {
             package: self.document.directive.package.name.to_owned(),
             version: self.document.directive.package.version.clone(),
             definitions: self.definitions,
             packages: state.packages,
             items: state.current.items,
+            names: state.current.names,
+            spans: state.spans,
+            export_spans: state
+                .exports
+                .iter()
+                .map(|(k, v)| (k.clone(), v.span))
+                .collect(),
+            references: state.references.into_inner(),
+            warnings: state.warnings,
             imports: state
                 .imports
                 .into_iter()
@@ -220,6 +295,172 @@ impl<'a> AstResolver<'a> {
         })
     }
 
+    /// Resolves the given statements, recording any errors.
+    ///
+    /// The statements of included documents are resolved in place of the
+    /// include statement.
+    fn statements(
+        &mut self,
+        state: &mut State<'a>,
+        statements: &'a [ast::Statement<'a>],
+        included: bool,
+        errors: &mut Vec<Error>,
+    ) {
+        for stmt in statements {
+            if self.fail_fast && !errors.is_empty() {
+                return;
+            }
+
+            let result = match stmt {
+                ast::Statement::Include(i) => {
+                    self.include_statement(state, i, errors);
+                    continue;
+                }
+                ast::Statement::Import(i) => self.import_statement(state, i),
+                ast::Statement::Type(t) => self.type_statement(state, t),
+                ast::Statement::Let(l) => self.let_statement(state, l),
+                ast::Statement::Destructure(d) => self.destructure_statement(state, d),
+                ast::Statement::Export(e) if included => {
+                    Err(Error::ExportInInclude { span: e.expr.span })
+                }
+                ast::Statement::Export(e) => self.export_statement(state, e),
+            };
+
+            if let Err(e) = result {
+                // Restore the root scope if the error occurred in a nested scope
+                while !state.scopes.is_empty() {
+                    state.pop_scope();
+                }
+
+                match &e {
+                    Error::UndefinedName { name, .. } if state.poisoned.contains(name.as_str()) => {
+                        log::debug!("ignoring error for poisoned name `{name}`");
+                    }
+                    _ => errors.push(e),
+                }
+
+                for id in Self::statement_ids(stmt) {
+                    log::debug!("poisoning name `{id}`", id = id.string);
+                    state.poisoned.insert(id.string);
+                }
+            }
+        }
+    }
+
+    fn include_statement(
+        &mut self,
+        state: &mut State<'a>,
+        stmt: &'a ast::IncludeStatement<'a>,
+        errors: &mut Vec<Error>,
+    ) {
+        log::debug!(
+            "resolving include statement for `{path}`",
+            path = stmt.path.value
+        );
+
+        let document = match stmt.document.as_deref() {
+            Some(document) => document,
+            None => {
+                errors.push(Error::IncludeNotLoaded {
+                    path: stmt.path.value.to_owned(),
+                    span: stmt.path.span,
+                });
+                return;
+            }
+        };
+
+        // A document that is included more than once is only resolved once
+        if state.included.iter().any(|d| std::ptr::eq(*d, document)) {
+            return;
+        }
+
+        state.included.push(document);
+        self.statements(state, &document.statements, true, errors);
+    }
+
+    /// Gets the identifiers bound by the given statement.
+    fn statement_ids(stmt: &'a ast::Statement<'a>) -> Vec<&'a ast::Ident<'a>> {
+        match stmt {
+            ast::Statement::Import(i) => vec![&i.id],
+            ast::Statement::Type(ast::TypeStatement::Interface(i)) => vec![&i.id],
+            ast::Statement::Type(ast::TypeStatement::World(w)) => vec![&w.id],
+            ast::Statement::Type(ast::TypeStatement::Type(t)) => vec![t.id()],
+            ast::Statement::Let(l) => vec![&l.id],
+            ast::Statement::Destructure(d) => d.ids().collect(),
+            ast::Statement::Include(_) | ast::Statement::Export(_) => Vec::new(),
+        }
+    }
+
+    /// Reports warnings for `let` bindings and explicit imports that are never used.
+    fn lint_unused(&self, state: &mut State<'a>) {
+        let referenced: HashSet<usize> = state
+            .references
+            .borrow()
+            .iter()
+            .map(|(_, definition)| definition.offset())
+            .collect();
+
+        for stmt in &self.document.statements {
+            for id in Self::statement_ids(stmt) {
+                if referenced.contains(&id.span.offset()) {
+                    continue;
+                }
+
+                let (lint, message) = match stmt {
+                    ast::Statement::Import(_) => (
+                        Lint::UnusedImport,
+                        format!("import `{id}` is never used", id = id.string),
+                    ),
+                    ast::Statement::Let(_) | ast::Statement::Destructure(_) => {
+                        // The binding may be missing if its statement failed to resolve
+                        let item = match state.current.names.get(id.string) {
+                            Some((item, _)) => *item,
+                            None => continue,
+                        };
+
+                        match &state.current.items[item] {
+                            Item::Instantiation(_) => (
+                                Lint::UnusedInstantiation,
+                                format!(
+                                    "the exports of instantiation `{id}` are never used or exported",
+                                    id = id.string
+                                ),
+                            ),
+                            _ => (
+                                Lint::UnusedLet,
+                                format!("`{id}` is never used", id = id.string),
+                            ),
+                        }
+                    }
+                    ast::Statement::Include(_)
+                    | ast::Statement::Type(_)
+                    | ast::Statement::Export(_) => continue,
+                };
+
+                state
+                    .warnings
+                    .push(Warning::new(lint, message, "defined here", id.span));
+            }
+        }
+    }
+
+    /// Validates that the composition conforms to the target world.
+    fn target(
+        &mut self,
+        state: &mut State<'a>,
+        path: &'a ast::PackagePath<'a>,
+    ) -> ResolutionResult<()> {
+        let item = self.resolve_package_export(state, path)?;
+        match item {
+            ItemKind::Type(Type::World(world)) => self.validate_target(state, path, world),
+            _ => Err(Error::NotWorld {
+                name: path.string.to_owned(),
+                kind: item.as_str(&self.definitions).to_owned(),
+                span: path.span,
+            }),
+        }
+    }
+
     fn import_statement(
         &mut self,
         state: &mut State<'a>,
@@ -248,35 +489,32 @@ impl<'a> AstResolver<'a> {
         // Promote any types to their corresponding item kind
         let kind = kind.promote();
 
+        // If the item is an instance with an id, use the id
+        let default = match kind {
+            ItemKind::Instance(id) => self.definitions.interfaces[id]
+                .id
+                .as_deref()
+                .unwrap_or(stmt.id.string),
+            _ => stmt.id.string,
+        };
+
         let (name, span) = if let Some(name) = &stmt.name {
+            if name.as_str() == default {
+                state.warnings.push(Warning::new(
+                    Lint::RedundantRename,
+                    format!("the `as` clause does not change the import name `{default}`"),
+                    "redundant `as` clause",
+                    name.span(),
+                ));
+            }
+
             // Override the span to the `as` clause string
             (name.as_str(), name.span())
         } else {
-            // If the item is an instance with an id, use the id
-            if let ItemKind::Instance(id) = kind {
-                if let Some(id) = &self.definitions.interfaces[id].id {
-                    (id.as_str(), span)
-                } else {
-                    (stmt.id.string, span)
-                }
-            } else {
-                (stmt.id.string, span)
-            }
+            (default, span)
         };
 
-        // Validate the import name
-        ComponentName::new(name, 0).map_err(|e| {
-            let msg = e.to_string();
-            Error::InvalidExternName {
-                name: name.to_string(),
-                kind: ExternKind::Import,
-                span,
-                source: anyhow::anyhow!(
-                    "{msg}",
-                    msg = msg.strip_suffix(" (at offset 0x0)").unwrap_or(&msg)
-                ),
-            }
-        })?;
+        validate_import_name(name, span)?;
 
         if let Some(existing) = state.imports.get(name) {
             match &state.current.items[existing.item] {
@@ -315,6 +553,7 @@ impl<'a> AstResolver<'a> {
             name: name.to_owned(),
             kind,
         }));
+        state.spans.insert(id, stmt.id.span);
 
         state.imports.insert(
             name.to_owned(),
@@ -365,6 +604,39 @@ impl<'a> AstResolver<'a> {
         state.register_name(stmt.id, item)
     }
 
+    fn destructure_statement(
+        &mut self,
+        state: &mut State<'a>,
+        stmt: &'a ast::DestructureStatement<'a>,
+    ) -> ResolutionResult<()> {
+        log::debug!("resolving destructuring let statement");
+        let item = self.expr(state, &stmt.expr)?;
+        let exports =
+            self.instance_exports(state, item, stmt.expr.span, InstanceOperation::Destructure)?;
+
+        for destructure in &stmt.items {
+            if let Some(as_id) = &destructure.as_id {
+                if as_id.string == destructure.id.string {
+                    state.warnings.push(Warning::new(
+                        Lint::RedundantRename,
+                        format!(
+                            "the `as` clause does not change the name `{name}`",
+                            name = destructure.id.string
+                        ),
+                        "redundant `as` clause",
+                        as_id.span,
+                    ));
+                }
+            }
+
+            let aliased =
+                self.access_export(state, item, exports, &destructure.id, destructure.id.span)?;
+            state.register_name(*destructure.binding(), aliased)?;
+        }
+
+        Ok(())
+    }
+
     fn export_statement(
         &mut self,
         state: &mut State<'a>,
@@ -397,7 +669,7 @@ impl<'a> AstResolver<'a> {
                     }
 
                     let item = self
-                        .alias_export(state, item, exports, name)?
+                        .alias_export(state, item, exports, name, *span)?
                         .expect("expected a matching export name");
 
                     self.export_item(state, item, name.clone(), *span, false)?;
@@ -411,6 +683,18 @@ impl<'a> AstResolver<'a> {
                 }
             }
             ast::ExportOptions::Rename(name) => {
+                if self.infer_export_name(state, item) == Some(name.as_str()) {
+                    state.warnings.push(Warning::new(
+                        Lint::RedundantRename,
+                        format!(
+                            "the `as` clause does not change the export name `{name}`",
+                            name = name.as_str()
+                        ),
+                        "redundant `as` clause",
+                        name.span(),
+                    ));
+                }
+
                 self.export_item(state, item, name.as_str().to_owned(), name.span(), false)?;
             }
         }
@@ -426,33 +710,7 @@ impl<'a> AstResolver<'a> {
         span: SourceSpan,
         show_hint: bool,
     ) -> Result<(), Error> {
-        match ComponentName::new(&name, 0)
-            .map_err(|e| {
-                let msg = e.to_string();
-                Error::InvalidExternName {
-                    name: name.clone(),
-                    kind: ExternKind::Export,
-                    span,
-                    source: anyhow::anyhow!(
-                        "{msg}",
-                        msg = msg.strip_suffix(" (at offset 0x0)").unwrap_or(&msg)
-                    ),
-                }
-            })?
-            .kind()
-        {
-            ComponentNameKind::Hash(_)
-            | ComponentNameKind::Url(_)
-            | ComponentNameKind::Dependency(_) => {
-                return Err(Error::InvalidExternName {
-                    name: name.to_string(),
-                    kind: ExternKind::Export,
-                    span,
-                    source: anyhow::anyhow!("export name cannot be a hash, url, or dependency"),
-                });
-            }
-            _ => {}
-        }
+        validate_export_name(&name, span)?;
 
         if let Some((item_id, prev_span)) = state.root_scope().get(&name) {
             let item = &state.current.items[item_id];
@@ -940,6 +1198,20 @@ impl<'a> AstResolver<'a> {
         };
 
         for item in &use_type.items {
+            if let Some(as_id) = &item.as_id {
+                if as_id.string == item.id.string {
+                    state.warnings.push(Warning::new(
+                        Lint::RedundantRename,
+                        format!(
+                            "the `as` clause does not change the name `{name}`",
+                            name = item.id.string
+                        ),
+                        "redundant `as` clause",
+                        as_id.span,
+                    ));
+                }
+            }
+
             let ident = item.as_id.unwrap_or(item.id);
             let kind = self.definitions.interfaces[interface]
                 .exports
@@ -1528,14 +1800,21 @@ impl<'a> AstResolver<'a> {
         state: &mut State<'a>,
         name: &'a str,
         version: Option<&'a Version>,
+        requirement: Option<&'a VersionReq>,
         span: SourceSpan,
     ) -> ResolutionResult<PackageId> {
-        match state.package_map.entry(PackageKey { name, version }) {
+        let key = PackageKey {
+            name,
+            version,
+            requirement,
+        };
+
+        match state.package_map.entry(key) {
             hash_map::Entry::Occupied(e) => Ok(*e.get()),
             hash_map::Entry::Vacant(e) => {
-                log::debug!("resolving package `{name}`");
-                let bytes = match self.packages.remove(&PackageKey { name, version }) {
-                    Some(bytes) => bytes,
+                log::debug!("resolving package `{key}`");
+                let resolved = match self.packages.remove(&key) {
+                    Some(resolved) => resolved,
                     None => {
                         return Err(Error::UnknownPackage {
                             name: name.to_string(),
@@ -1544,14 +1823,21 @@ impl<'a> AstResolver<'a> {
                     }
                 };
 
+                // Prefer the version chosen by the resolver, if any
+                let version = resolved.version.as_ref().or(version);
                 let id = state.packages.alloc(
-                    Package::parse(&mut self.definitions, name, version, bytes).map_err(|e| {
-                        Error::PackageParseFailure {
-                            name: name.to_string(),
+                    Package::parse(&mut self.definitions, name, version, resolved.bytes).map_err(
+                        |e| Error::PackageParseFailure {
+                            name: PackageKey {
+                                name,
+                                version,
+                                requirement,
+                            }
+                            .to_string(),
                             span,
                             source: e,
-                        }
-                    })?,
+                        },
+                    )?,
                 );
                 Ok(*e.insert(id))
             }
@@ -1572,6 +1858,7 @@ impl<'a> AstResolver<'a> {
             state,
             path.name,
             path.version.as_ref(),
+            path.requirement.as_ref(),
             path.package_name_span(),
         )?;
 
@@ -1728,6 +2015,7 @@ impl<'a> AstResolver<'a> {
             state,
             expr.package.name,
             expr.package.version.as_ref(),
+            expr.package.requirement.as_ref(),
             expr.package.span,
         )?;
         let ty = state.packages[pkg].world;
@@ -1826,13 +2114,15 @@ impl<'a> AstResolver<'a> {
             arguments.insert(name, (item, expr.package.span));
         }
 
-        Ok(state
+        let id = state
             .current
             .items
             .alloc(Item::Instantiation(super::Instantiation {
                 package: pkg,
                 arguments: arguments.into_iter().map(|(n, (i, _))| (n, i)).collect(),
-            })))
+            }));
+        state.spans.insert(id, expr.span);
+        Ok(id)
     }
 
     fn implicit_import(
@@ -1920,6 +2210,7 @@ impl<'a> AstResolver<'a> {
             name: name.clone(),
             kind,
         }));
+        state.spans.insert(id, span);
 
         state.imports.insert(
             name,
@@ -2117,21 +2408,36 @@ impl<'a> AstResolver<'a> {
         let exports = self.instance_exports(state, item, id.span, InstanceOperation::Spread)?;
 
         let mut spread = false;
+        let mut redundant = false;
         for name in world.imports.keys() {
             // Check if the argument was already provided
             if arguments.contains_key(name) {
+                redundant |= exports.contains_key(name);
                 continue;
             }
 
             // Alias a matching export of the instance
-            if let Some(aliased) = self.alias_export(state, item, exports, name)? {
+            if let Some(aliased) = self.alias_export(state, item, exports, name, id.span)? {
                 spread = true;
                 arguments.insert(name.clone(), (aliased, id.span));
             }
         }
 
         if !spread {
-            return Err(Error::SpreadInstantiationNoMatch { span: id.span });
+            // A spread that only matches provided arguments is allowed, but has no effect
+            if !redundant {
+                return Err(Error::SpreadInstantiationNoMatch { span: id.span });
+            }
+
+            state.warnings.push(Warning::new(
+                Lint::RedundantSpread,
+                format!(
+                    "the exports of `{id}` only match arguments that were already provided",
+                    id = id.string
+                ),
+                "spread has no effect",
+                id.span,
+            ));
         }
 
         Ok(())
@@ -2197,17 +2503,10 @@ impl<'a> AstResolver<'a> {
 
         match expr {
             ast::PostfixExpr::Access(expr) => {
-                let name = Self::find_matching_interface_name(expr.id.string, exports)
-                    .unwrap_or(expr.id.string);
-
-                self.alias_export(state, item, exports, name)?
-                    .ok_or_else(|| Error::MissingInstanceExport {
-                        name: name.to_owned(),
-                        span: expr.span,
-                    })
+                self.access_export(state, item, exports, &expr.id, expr.span)
             }
             ast::PostfixExpr::NamedAccess(expr) => self
-                .alias_export(state, item, exports, expr.string.value)?
+                .alias_export(state, item, exports, expr.string.value, expr.span)?
                 .ok_or_else(|| Error::MissingInstanceExport {
                     name: expr.string.value.to_owned(),
                     span: expr.span,
@@ -2215,6 +2514,27 @@ impl<'a> AstResolver<'a> {
         }
     }
 
+    /// Aliases the export of an instance accessed by identifier.
+    ///
+    /// The identifier may also match the name of an exported interface,
+    /// e.g. `baz` matches `foo:bar/baz`.
+    fn access_export(
+        &self,
+        state: &mut State<'a>,
+        item: ItemId,
+        exports: &IndexMap<String, ItemKind>,
+        id: &ast::Ident,
+        span: SourceSpan,
+    ) -> ResolutionResult<ItemId> {
+        let name = Self::find_matching_interface_name(id.string, exports).unwrap_or(id.string);
+
+        self.alias_export(state, item, exports, name, span)?
+            .ok_or_else(|| Error::MissingInstanceExport {
+                name: name.to_owned(),
+                span,
+            })
+    }
+
     fn instance_exports(
         &self,
         state: &State,
@@ -2241,6 +2561,7 @@ impl<'a> AstResolver<'a> {
         item: ItemId,
         exports: &IndexMap<String, ItemKind>,
         name: &str,
+        span: SourceSpan,
     ) -> ResolutionResult<Option<ItemId>> {
         let kind = match exports.get(name) {
             Some(kind) => *kind,
@@ -2257,6 +2578,7 @@ impl<'a> AstResolver<'a> {
             export: name.to_owned(),
             kind,
         }));
+        state.spans.insert(id, span);
 
         aliases.insert(name.to_owned(), id);
         Ok(Some(id))
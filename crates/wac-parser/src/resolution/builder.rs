@@ -0,0 +1,323 @@
+//! Module for building compositions without a WAC document.
+
+use super::{
+    ast::{validate_export_name, validate_import_name},
+    package::Package,
+    Alias, Composition, Definitions, Error, ExternKind, Import, InstanceOperation, Instantiation,
+    Item, ItemId, ItemKind, PackageId, ResolutionResult, SubtypeChecker,
+};
+use indexmap::IndexMap;
+use miette::SourceSpan;
+use semver::Version;
+use std::{collections::HashMap, sync::Arc};
+
+/// A builder of compositions.
+///
+/// The builder is an alternative to resolving a WAC document: items are
+/// added with typed methods and referenced by [`ItemId`] rather than by name.
+///
+/// The builder performs the same validation as resolving a document, such as
+/// checking instantiation arguments against the imports of the package being
+/// instantiated. As there is no source, the spans of returned errors are empty.
+pub struct CompositionBuilder {
+    composition: Composition,
+    /// The map of package name and version to package id.
+    package_map: HashMap<(String, Option<Version>), PackageId>,
+    /// The map of instance items and their aliased items.
+    aliases: HashMap<ItemId, HashMap<String, ItemId>>,
+}
+
+impl CompositionBuilder {
+    /// Creates a new composition builder for the given package name and version.
+    pub fn new(package: impl Into<String>, version: Option<Version>) -> Self {
+        Self {
+            composition: Composition {
+                package: package.into(),
+                version,
+                ..Default::default()
+            },
+            package_map: Default::default(),
+            aliases: Default::default(),
+        }
+    }
+
+    /// Gets the definitions of the composition being built.
+    ///
+    /// The definitions include the types of the packages that have been added.
+    pub fn definitions(&self) -> &Definitions {
+        &self.composition.definitions
+    }
+
+    /// Gets a package that has been added to the composition.
+    pub fn package(&self, id: PackageId) -> &Package {
+        &self.composition.packages[id]
+    }
+
+    /// Gets the kind of an item in the composition.
+    pub fn kind(&self, id: ItemId) -> ItemKind {
+        self.composition.items[id].kind()
+    }
+
+    /// Adds a package to the composition from its encoded bytes.
+    ///
+    /// If a package with the same name and version was already added, its
+    /// id is returned.
+    pub fn add_package(
+        &mut self,
+        name: &str,
+        version: Option<&Version>,
+        bytes: Arc<Vec<u8>>,
+    ) -> ResolutionResult<PackageId> {
+        let key = (name.to_owned(), version.cloned());
+        if let Some(id) = self.package_map.get(&key) {
+            return Ok(*id);
+        }
+
+        log::debug!("adding package `{name}`");
+        let package = Package::parse(&mut self.composition.definitions, name, version, bytes)
+            .map_err(|e| Error::PackageParseFailure {
+                name: name.to_owned(),
+                span: empty(),
+                source: e,
+            })?;
+
+        let id = self.composition.packages.alloc(package);
+        self.package_map.insert(key, id);
+        Ok(id)
+    }
+
+    /// Adds an import of the given kind to the composition.
+    ///
+    /// Function, interface, and world types are imported as functions,
+    /// instances, and components, respectively.
+    pub fn import(&mut self, name: &str, kind: ItemKind) -> ResolutionResult<ItemId> {
+        validate_import_name(name, empty())?;
+
+        if self.composition.imports.contains_key(name) {
+            return Err(Error::DuplicateExternName {
+                name: name.to_owned(),
+                kind: ExternKind::Import,
+                span: empty(),
+                previous: empty(),
+                help: None,
+            });
+        }
+
+        let id = self.composition.items.alloc(Item::Import(Import {
+            name: name.to_owned(),
+            kind: kind.promote(),
+        }));
+        self.composition.imports.insert(name.to_owned(), id);
+        Ok(id)
+    }
+
+    /// Instantiates a package with the given arguments.
+    ///
+    /// Each argument is the name of an import of the package and the item
+    /// to satisfy it with; an argument must be given for every import.
+    pub fn instantiate<N: Into<String>>(
+        &mut self,
+        package: PackageId,
+        arguments: impl IntoIterator<Item = (N, ItemId)>,
+    ) -> ResolutionResult<ItemId> {
+        let pkg = &self.composition.packages[package];
+        let world = &self.composition.definitions.worlds[pkg.world];
+
+        let mut args = IndexMap::new();
+        for (name, item) in arguments {
+            let name = name.into();
+            let expected =
+                world
+                    .imports
+                    .get(&name)
+                    .ok_or_else(|| Error::MissingComponentImport {
+                        package: pkg.name.clone(),
+                        import: name.clone(),
+                        span: empty(),
+                    })?;
+
+            log::debug!(
+                "performing subtype check for argument `{name}` (item {item})",
+                item = item.index()
+            );
+
+            SubtypeChecker::new(&self.composition.definitions, &self.composition.packages)
+                .is_subtype(*expected, self.composition.items[item].kind())
+                .map_err(|e| Error::MismatchedInstantiationArg {
+                    name: name.clone(),
+                    span: empty(),
+                    source: e,
+                })?;
+
+            if args.insert(name.clone(), item).is_some() {
+                return Err(Error::DuplicateInstantiationArg {
+                    name,
+                    span: empty(),
+                });
+            }
+        }
+
+        if let Some(name) = world.imports.keys().find(|n| !args.contains_key(*n)) {
+            return Err(Error::MissingInstantiationArg {
+                name: name.clone(),
+                package: pkg.name.clone(),
+                span: empty(),
+            });
+        }
+
+        Ok(self
+            .composition
+            .items
+            .alloc(Item::Instantiation(Instantiation {
+                package,
+                arguments: args,
+            })))
+    }
+
+    /// Aliases an export of an instance.
+    ///
+    /// The item must be an instance or an instantiation of a package.
+    pub fn alias(&mut self, item: ItemId, export: &str) -> ResolutionResult<ItemId> {
+        let definitions = &self.composition.definitions;
+        let exports = match self.composition.items[item].kind() {
+            ItemKind::Instance(id) => &definitions.interfaces[id].exports,
+            ItemKind::Instantiation(id) => {
+                &definitions.worlds[self.composition.packages[id].world].exports
+            }
+            kind => {
+                return Err(Error::NotAnInstance {
+                    kind: kind.as_str(definitions).to_string(),
+                    operation: InstanceOperation::Access,
+                    span: empty(),
+                })
+            }
+        };
+
+        let kind = *exports
+            .get(export)
+            .ok_or_else(|| Error::MissingInstanceExport {
+                name: export.to_owned(),
+                span: empty(),
+            })?;
+
+        let aliases = self.aliases.entry(item).or_default();
+        if let Some(id) = aliases.get(export) {
+            return Ok(*id);
+        }
+
+        let id = self.composition.items.alloc(Item::Alias(Alias {
+            item,
+            export: export.to_owned(),
+            kind,
+        }));
+        aliases.insert(export.to_owned(), id);
+        Ok(id)
+    }
+
+    /// Exports an item from the composition with the given name.
+    pub fn export(&mut self, item: ItemId, name: &str) -> ResolutionResult<()> {
+        validate_export_name(name, empty())?;
+
+        if self.composition.exports.contains_key(name) {
+            return Err(Error::DuplicateExternName {
+                name: name.to_owned(),
+                kind: ExternKind::Export,
+                span: empty(),
+                previous: empty(),
+                help: None,
+            });
+        }
+
+        self.composition.exports.insert(name.to_owned(), item);
+        Ok(())
+    }
+
+    /// Binds a name to an item.
+    ///
+    /// This is equivalent to a `let` statement; the names are used when
+    /// printing the instantiation graph of the composition.
+    pub fn bind(&mut self, name: &str, item: ItemId) -> ResolutionResult<()> {
+        if self.composition.names.contains_key(name) {
+            return Err(Error::DuplicateName {
+                name: name.to_owned(),
+                span: empty(),
+                previous: empty(),
+            });
+        }
+
+        self.composition
+            .names
+            .insert(name.to_owned(), (item, empty()));
+        Ok(())
+    }
+
+    /// Builds the composition.
+    pub fn build(self) -> Composition {
+        self.composition
+    }
+}
+
+/// Gets the span used for errors reported by the builder.
+fn empty() -> SourceSpan {
+    SourceSpan::new(0.into(), 0.into())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{ast::Document, EncodingOptions};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn it_builds_compositions() {
+        // Use an encoded composition as the package to instantiate
+        let document =
+            Document::parse("package test:pkg;\nimport f: func();\nexport f as g;\n").unwrap();
+        let bytes = Composition::from_ast(&document, Default::default())
+            .unwrap()
+            .encode(EncodingOptions::default())
+            .unwrap();
+
+        let mut builder = CompositionBuilder::new("test:comp", None);
+        let pkg = builder
+            .add_package("test:pkg", None, Arc::new(bytes))
+            .unwrap();
+        let kind = builder.definitions().worlds[builder.package(pkg).world].imports["f"];
+
+        let f = builder.import("f", kind).unwrap();
+        let instance = builder.instantiate(pkg, [("f", f)]).unwrap();
+        let g = builder.alias(instance, "g").unwrap();
+        assert_eq!(builder.alias(instance, "g").unwrap(), g);
+        builder.export(g, "g").unwrap();
+        builder.bind("instance", instance).unwrap();
+
+        let errors = [
+            builder.import("f", kind).unwrap_err(),
+            builder
+                .instantiate(pkg, Vec::<(&str, ItemId)>::new())
+                .unwrap_err(),
+            builder.instantiate(pkg, [("f", instance)]).unwrap_err(),
+            builder.alias(f, "g").unwrap_err(),
+            builder.alias(instance, "x").unwrap_err(),
+            builder.export(g, "g").unwrap_err(),
+            builder.bind("instance", g).unwrap_err(),
+        ];
+
+        assert_eq!(
+            errors.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            [
+                "duplicate import `f`",
+                "missing instantiation argument `f` for package `test:pkg`",
+                "mismatched instantiation argument `f`",
+                "an instance is required to perform an access operation",
+                "the instance has no export named `x`",
+                "duplicate export `g`",
+                "`instance` is already defined",
+            ]
+        );
+
+        let composition = builder.build();
+        assert_eq!(composition.exports.len(), 1);
+        composition.encode(EncodingOptions::default()).unwrap();
+    }
+}
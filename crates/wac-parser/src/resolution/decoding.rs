@@ -0,0 +1,1056 @@
+//! Module for decoding WebAssembly compositions.
+
+use super::{
+    package::Package, DefinedType, DefinedTypeId, Definitions, Func, FuncResult, InterfaceId,
+    ItemKind, ResourceId, Type, ValueType, WorldId,
+};
+use crate::{
+    ast::{Document, ParseResult},
+    lexer::is_keyword,
+};
+use anyhow::{anyhow, bail, Context, Result};
+use indexmap::IndexMap;
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Write,
+    sync::Arc,
+};
+use wasmparser::{
+    CanonicalFunction, ComponentAlias, ComponentExternalKind, ComponentInstance, ComponentTypeRef,
+    Parser, Payload,
+};
+
+/// Determines if the given name is a valid WAC identifier (without the `%` prefix).
+fn is_ident(name: &str) -> bool {
+    !name.is_empty()
+        && name.split('-').all(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(c) if c.is_ascii_lowercase() => {
+                    chars.all(|c| c.is_ascii_lowercase() || c.is_ascii_digit())
+                }
+                Some(c) if c.is_ascii_uppercase() => {
+                    chars.all(|c| c.is_ascii_uppercase() || c.is_ascii_digit())
+                }
+                _ => false,
+            }
+        })
+}
+
+/// Formats the given name as a WAC identifier.
+fn ident(name: &str) -> Result<String> {
+    if !is_ident(name) {
+        bail!("`{name}` is not a valid identifier");
+    }
+
+    if is_keyword(name) {
+        Ok(format!("%{name}"))
+    } else {
+        Ok(name.to_string())
+    }
+}
+
+/// Formats the given extern name as either an identifier or a string.
+fn extern_name(name: &str) -> String {
+    ident(name).unwrap_or_else(|_| format!("\"{name}\""))
+}
+
+/// Parses a package name from a dependency import name.
+///
+/// Both `unlocked-dep=<a:b@{>=1.0.0}>` and `locked-dep=<a:b@1.0.0>` forms are supported;
+/// the lower bound of a version range is used as the version of the package and any
+/// `integrity` metadata is ignored.
+///
+/// Returns `None` for other import names, such as `url` imports.
+fn dependency_name(name: &str) -> Option<String> {
+    let dep = name
+        .strip_prefix("unlocked-dep=<")
+        .or_else(|| name.strip_prefix("locked-dep=<"))?;
+
+    // A version range may contain `>`, so the name ends at the first `>` after it
+    let start = match dep.find('{') {
+        Some(open) => open + dep[open..].find('}')?,
+        None => 0,
+    };
+    let dep = &dep[..start + dep[start..].find('>')?];
+
+    match dep.split_once('@') {
+        Some((name, version)) => {
+            let version = version
+                .strip_prefix("{>=")
+                .map(|v| v.split([' ', '}']).next().unwrap_or(v))
+                .unwrap_or(version);
+            if version.is_empty() || version.starts_with('{') || version.starts_with('*') {
+                Some(name.to_string())
+            } else {
+                Some(format!("{name}@{version}"))
+            }
+        }
+        None => Some(dep.to_string()),
+    }
+}
+
+/// Represents a WAC document decoded from a WebAssembly component.
+///
+/// Imports of the component are decoded as `import` statements, instantiations
+/// are decoded as `let` statements, and exports are decoded as `export` statements.
+///
+/// Packages defined (i.e. embedded) in the component are given placeholder
+/// package names as their original names are not encoded.
+pub struct DecodedDocument {
+    source: String,
+}
+
+impl DecodedDocument {
+    /// Decodes the given WebAssembly component into a WAC document.
+    ///
+    /// The given package name is used for the package directive of the document.
+    pub fn decode(package: &str, bytes: &[u8]) -> Result<Self> {
+        let mut definitions = Definitions::default();
+        let component = Package::parse(&mut definitions, package, None, Arc::new(bytes.to_vec()))
+            .context("failed to parse the component to decode")?;
+
+        let source = Decoder::new(package, bytes, &definitions, &component).decode()?;
+        Ok(Self { source })
+    }
+
+    /// Gets the source of the decoded document.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Parses the source of the decoded document.
+    pub fn document(&self) -> ParseResult<Document> {
+        Document::parse(&self.source)
+    }
+}
+
+/// Represents a WAC expression for an item in the component being decoded.
+#[derive(Debug, Clone)]
+struct Expr {
+    /// The source of the expression.
+    source: String,
+    /// The export name if the expression is an alias of an instance export.
+    export: Option<String>,
+}
+
+impl Expr {
+    fn new(source: String) -> Self {
+        Self {
+            source,
+            export: None,
+        }
+    }
+
+    fn access(&self, name: &str) -> Self {
+        let source = match ident(name) {
+            Ok(id) => format!("{source}.{id}", source = self.source),
+            Err(_) => format!("{source}[\"{name}\"]", source = self.source),
+        };
+
+        Self {
+            source,
+            export: Some(name.to_string()),
+        }
+    }
+}
+
+struct Decoder<'a> {
+    package: &'a str,
+    bytes: &'a [u8],
+    definitions: &'a Definitions,
+    component: &'a Package,
+    printer: TypePrinter<'a>,
+    source: String,
+    names: HashSet<String>,
+    defined: usize,
+    modules: Vec<Option<Expr>>,
+    funcs: Vec<Option<Expr>>,
+    values: Vec<Option<Expr>>,
+    types: Vec<Option<Expr>>,
+    instances: Vec<Option<Expr>>,
+    components: Vec<Option<String>>,
+}
+
+impl<'a> Decoder<'a> {
+    fn new(
+        package: &'a str,
+        bytes: &'a [u8],
+        definitions: &'a Definitions,
+        component: &'a Package,
+    ) -> Self {
+        let world = &definitions.worlds[component.world];
+
+        // Names of types exported from the component are reserved
+        let mut names = HashSet::new();
+        let mut printer = TypePrinter::new(definitions);
+        for (name, kind) in &world.exports {
+            if let ItemKind::Type(ty) = kind {
+                names.insert(name.clone());
+                if let Type::Value(ValueType::Defined { id, .. }) = ty {
+                    printer.names.types.insert(*id, name.clone());
+                }
+            }
+        }
+
+        // Interfaces defined in the component may be referenced by name
+        for (name, kind) in &component.definitions {
+            if let ItemKind::Type(Type::Interface(id)) = kind {
+                if let Some(interface) = &definitions.interfaces[*id].id {
+                    printer.interfaces.insert(interface.clone(), name.clone());
+                }
+            }
+        }
+
+        Self {
+            package,
+            bytes,
+            definitions,
+            component,
+            printer,
+            source: String::new(),
+            names,
+            defined: 0,
+            modules: Default::default(),
+            funcs: Default::default(),
+            values: Default::default(),
+            types: Default::default(),
+            instances: Default::default(),
+            components: Default::default(),
+        }
+    }
+
+    fn decode(mut self) -> Result<String> {
+        log::debug!(
+            "decoding component as package `{package}`",
+            package = self.package
+        );
+        writeln!(self.source, "package {package};", package = self.package)?;
+
+        let mut depth = 0;
+        for payload in Parser::new(0).parse_all(self.bytes) {
+            let payload = payload?;
+
+            // Skip over the contents of nested modules and components
+            if depth > 0 {
+                match payload {
+                    Payload::ModuleSection { .. } | Payload::ComponentSection { .. } => depth += 1,
+                    Payload::End(_) => depth -= 1,
+                    _ => {}
+                }
+                continue;
+            }
+
+            match payload {
+                Payload::ModuleSection { .. } => {
+                    depth += 1;
+                    self.modules.push(None);
+                }
+                Payload::ComponentSection { .. } => {
+                    depth += 1;
+                    self.defined += 1;
+                    let name = format!("defined:component{n}", n = self.defined);
+                    log::debug!("decoding defined component as package `{name}`");
+                    self.components.push(Some(name));
+                }
+                Payload::ComponentImportSection(reader) => {
+                    for import in reader {
+                        let import = import?;
+                        self.import(import.name.0, import.ty)?;
+                    }
+                }
+                Payload::ComponentInstanceSection(reader) => {
+                    for instance in reader {
+                        self.instance(instance?)?;
+                    }
+                }
+                Payload::ComponentAliasSection(reader) => {
+                    for alias in reader {
+                        self.alias(alias?);
+                    }
+                }
+                Payload::ComponentTypeSection(reader) => {
+                    for _ in 0..reader.count() {
+                        self.types.push(None);
+                    }
+                }
+                Payload::ComponentCanonicalSection(reader) => {
+                    for func in reader {
+                        if let CanonicalFunction::Lift { .. } = func? {
+                            self.funcs.push(None);
+                        }
+                    }
+                }
+                Payload::ComponentExportSection(reader) => {
+                    for export in reader {
+                        let export = export?;
+                        self.export(export.name.0, export.kind, export.index)?;
+                    }
+                }
+                Payload::ComponentStartSection { .. } => {
+                    bail!("cannot decode a component with a start function")
+                }
+                Payload::End(_) => break,
+                _ => {}
+            }
+        }
+
+        Ok(self.source)
+    }
+
+    fn unique(&mut self, base: &str) -> String {
+        let base = match ident(base) {
+            Ok(base) => base,
+            Err(_) => "item".to_string(),
+        };
+
+        let mut name = base.clone();
+        let mut i = 1;
+        while !self.names.insert(name.clone()) {
+            name = format!("{base}{i}");
+            i += 1;
+        }
+
+        name
+    }
+
+    fn import(&mut self, name: &str, ty: ComponentTypeRef) -> Result<()> {
+        log::debug!("decoding import `{name}`");
+
+        let kind = || {
+            self.definitions.worlds[self.component.world]
+                .imports
+                .get(name)
+                .copied()
+                .ok_or_else(|| anyhow!("component does not import `{name}`"))
+        };
+
+        match ty {
+            ComponentTypeRef::Component(_) => {
+                let package = dependency_name(name).with_context(|| {
+                    format!("cannot decode import `{name}`: component imports must be package dependencies")
+                })?;
+                self.components.push(Some(package));
+            }
+            ComponentTypeRef::Instance(_) => {
+                let ItemKind::Instance(interface) = kind()? else {
+                    bail!("expected import `{name}` to be an instance");
+                };
+
+                let id = if name.contains('/') {
+                    // The import is of an interface; import it by path
+                    let base = name.rsplit('/').next().unwrap();
+                    let base = base.split('@').next().unwrap();
+                    let id = self.unique(base);
+                    writeln!(self.source, "import {id}: {name};")?;
+                    id
+                } else {
+                    let id = self.unique(name);
+                    let body = self.printer.interface(interface)?;
+                    if id == extern_name(name) {
+                        writeln!(self.source, "import {id}: interface {{\n{body}}};")?;
+                    } else {
+                        writeln!(
+                            self.source,
+                            "import {id} as \"{name}\": interface {{\n{body}}};"
+                        )?;
+                    }
+                    id
+                };
+
+                self.instances.push(Some(Expr::new(id)));
+            }
+            ComponentTypeRef::Func(_) => {
+                let ItemKind::Func(func) = kind()? else {
+                    bail!("expected import `{name}` to be a function");
+                };
+
+                let id = self.unique(name);
+                let ty = self.printer.func(&self.definitions.funcs[func])?;
+                if id == extern_name(name) {
+                    writeln!(self.source, "import {id}: {ty};")?;
+                } else {
+                    writeln!(self.source, "import {id} as \"{name}\": {ty};")?;
+                }
+
+                self.funcs.push(Some(Expr::new(id)));
+            }
+            ComponentTypeRef::Type(_) => {
+                bail!("cannot decode import `{name}`: type imports are not supported")
+            }
+            ComponentTypeRef::Module(_) => {
+                bail!("cannot decode import `{name}`: module imports are not supported")
+            }
+            ComponentTypeRef::Value(_) => {
+                bail!("cannot decode import `{name}`: value imports are not supported")
+            }
+        }
+
+        Ok(())
+    }
+
+    fn instance(&mut self, instance: ComponentInstance) -> Result<()> {
+        match instance {
+            ComponentInstance::Instantiate {
+                component_index,
+                args,
+            } => {
+                let package = self
+                    .components
+                    .get(component_index as usize)
+                    .cloned()
+                    .flatten()
+                    .ok_or_else(|| {
+                        anyhow!("cannot decode an instantiation of component {component_index}")
+                    })?;
+
+                log::debug!("decoding instantiation of package `{package}`");
+
+                let base = package.split('@').next().unwrap();
+                let base = base.rsplit(':').next().unwrap();
+                let id = self.unique(base);
+
+                let mut s = format!("let {id} = new {package} {{\n");
+                for arg in args.iter() {
+                    let expr = self.expr(arg.kind, arg.index)?;
+                    writeln!(
+                        s,
+                        "{name}: {expr},",
+                        name = extern_name(arg.name),
+                        expr = expr.source
+                    )?;
+                }
+                s.push_str("};\n");
+
+                self.source.push_str(&s);
+                self.instances.push(Some(Expr::new(id)));
+            }
+            ComponentInstance::FromExports(_) => self.instances.push(None),
+        }
+
+        Ok(())
+    }
+
+    fn alias(&mut self, alias: ComponentAlias) {
+        match alias {
+            ComponentAlias::InstanceExport {
+                kind,
+                instance_index,
+                name,
+            } => {
+                let expr = self
+                    .instances
+                    .get(instance_index as usize)
+                    .cloned()
+                    .flatten()
+                    .map(|e| e.access(name));
+
+                match self.space(kind) {
+                    Some(space) => space.push(expr),
+                    None => self.components.push(None),
+                }
+            }
+            ComponentAlias::Outer { kind, .. } => match kind {
+                wasmparser::ComponentOuterAliasKind::CoreModule => self.modules.push(None),
+                wasmparser::ComponentOuterAliasKind::Type => self.types.push(None),
+                wasmparser::ComponentOuterAliasKind::Component => self.components.push(None),
+                wasmparser::ComponentOuterAliasKind::CoreType => {}
+            },
+            ComponentAlias::CoreInstanceExport { .. } => {}
+        }
+    }
+
+    fn export(&mut self, name: &str, kind: ComponentExternalKind, index: u32) -> Result<()> {
+        log::debug!("decoding export `{name}`");
+
+        if kind == ComponentExternalKind::Component {
+            bail!("cannot decode export `{name}`: component exports are not supported");
+        }
+
+        if kind == ComponentExternalKind::Type
+            && self
+                .types
+                .get(index as usize)
+                .map(Option::is_none)
+                .unwrap_or(true)
+        {
+            // The export is of a type defined in the component
+            self.type_definition(name)?;
+            self.types.push(Some(Expr::new(ident(name)?)));
+            return Ok(());
+        }
+
+        let expr = self.expr(kind, index)?;
+        if expr.export.as_deref() == Some(name) {
+            writeln!(self.source, "export {expr};", expr = expr.source)?;
+        } else {
+            writeln!(
+                self.source,
+                "export {expr} as {name};",
+                expr = expr.source,
+                name = extern_name(name)
+            )?;
+        }
+
+        self.space(kind).unwrap().push(Some(expr));
+        Ok(())
+    }
+
+    fn type_definition(&mut self, name: &str) -> Result<()> {
+        let id = ident(name)?;
+        let s = match self.component.definitions.get(name) {
+            Some(ItemKind::Type(Type::Interface(interface))) => {
+                format!(
+                    "interface {id} {{\n{body}}}\n",
+                    body = self.printer.interface(*interface)?
+                )
+            }
+            Some(ItemKind::Type(Type::World(world))) => {
+                format!(
+                    "world {id} {{\n{body}}}\n",
+                    body = self.printer.world(*world)?
+                )
+            }
+            _ => match self.definitions.worlds[self.component.world]
+                .exports
+                .get(name)
+            {
+                Some(ItemKind::Type(Type::Value(ty))) => {
+                    self.printer.type_decl(&self.printer.names, name, *ty)?
+                }
+                Some(ItemKind::Type(Type::Func(func))) => format!(
+                    "type {id} = {ty};\n",
+                    ty = self.printer.func(&self.definitions.funcs[*func])?
+                ),
+                _ => bail!("cannot decode export `{name}`: unsupported type export"),
+            },
+        };
+
+        self.source.push_str(&s);
+        Ok(())
+    }
+
+    fn space(&mut self, kind: ComponentExternalKind) -> Option<&mut Vec<Option<Expr>>> {
+        match kind {
+            ComponentExternalKind::Module => Some(&mut self.modules),
+            ComponentExternalKind::Func => Some(&mut self.funcs),
+            ComponentExternalKind::Value => Some(&mut self.values),
+            ComponentExternalKind::Type => Some(&mut self.types),
+            ComponentExternalKind::Instance => Some(&mut self.instances),
+            ComponentExternalKind::Component => None,
+        }
+    }
+
+    fn expr(&mut self, kind: ComponentExternalKind, index: u32) -> Result<Expr> {
+        self.space(kind)
+            .and_then(|space| space.get(index as usize).cloned().flatten())
+            .ok_or_else(|| anyhow!("cannot decode a reference to {kind:?} index {index}"))
+    }
+}
+
+/// Represents the names of types in scope when printing types.
+#[derive(Default, Clone)]
+struct Names {
+    types: HashMap<DefinedTypeId, String>,
+    resources: HashMap<ResourceId, String>,
+}
+
+/// Responsible for printing types as WAC source.
+struct TypePrinter<'a> {
+    definitions: &'a Definitions,
+    /// The names of the types at the top-level of the document.
+    names: Names,
+    /// Map of interface ids to the names of interfaces defined in the document.
+    interfaces: HashMap<String, String>,
+}
+
+impl<'a> TypePrinter<'a> {
+    fn new(definitions: &'a Definitions) -> Self {
+        Self {
+            definitions,
+            names: Default::default(),
+            interfaces: Default::default(),
+        }
+    }
+
+    fn func(&self, func: &Func) -> Result<String> {
+        self.func_with(&self.names, func)
+    }
+
+    fn func_with(&self, names: &Names, func: &Func) -> Result<String> {
+        self.func_type(names, func, 0, true)
+    }
+
+    fn func_type(&self, names: &Names, func: &Func, skip: usize, results: bool) -> Result<String> {
+        let mut s = "func(".to_string();
+        for (i, (name, ty)) in func.params.iter().skip(skip).enumerate() {
+            if i > 0 {
+                s.push_str(", ");
+            }
+
+            write!(
+                s,
+                "{name}: {ty}",
+                name = ident(name)?,
+                ty = self.ty(names, *ty)?
+            )?;
+        }
+        s.push(')');
+
+        if !results {
+            return Ok(s);
+        }
+
+        match &func.results {
+            None => {}
+            Some(FuncResult::Scalar(ty)) => write!(s, " -> {ty}", ty = self.ty(names, *ty)?)?,
+            Some(FuncResult::List(results)) => {
+                s.push_str(" -> (");
+                for (i, (name, ty)) in results.iter().enumerate() {
+                    if i > 0 {
+                        s.push_str(", ");
+                    }
+
+                    write!(
+                        s,
+                        "{name}: {ty}",
+                        name = ident(name)?,
+                        ty = self.ty(names, *ty)?
+                    )?;
+                }
+                s.push(')');
+            }
+        }
+
+        Ok(s)
+    }
+
+    fn resource_name(&self, names: &Names, id: ResourceId) -> Result<String> {
+        if let Some(name) = names.resources.get(&id).or_else(|| {
+            names
+                .resources
+                .get(&self.definitions.resolve_resource_id(id))
+        }) {
+            return Ok(name.clone());
+        }
+
+        ident(&self.definitions.resources[id].name)
+    }
+
+    fn ty(&self, names: &Names, ty: ValueType) -> Result<String> {
+        match ty {
+            ValueType::Primitive(ty) => Ok(ty.as_str().to_string()),
+            ValueType::Borrow(id) => {
+                Ok(format!("borrow<{id}>", id = self.resource_name(names, id)?))
+            }
+            ValueType::Own(id) => self.resource_name(names, id),
+            ValueType::Defined { id, .. } => {
+                if let Some(name) = self.named(names, id) {
+                    return Ok(name.clone());
+                }
+
+                match &self.definitions.types[id] {
+                    DefinedType::Tuple(types) => {
+                        let types = types
+                            .iter()
+                            .map(|ty| self.ty(names, *ty))
+                            .collect::<Result<Vec<_>>>()?;
+                        Ok(format!("tuple<{types}>", types = types.join(", ")))
+                    }
+                    DefinedType::List(ty) => Ok(format!("list<{ty}>", ty = self.ty(names, *ty)?)),
+                    DefinedType::Option(ty) => {
+                        Ok(format!("option<{ty}>", ty = self.ty(names, *ty)?))
+                    }
+                    DefinedType::Result { ok, err } => match (ok, err) {
+                        (None, None) => Ok("result".to_string()),
+                        (None, Some(err)) => {
+                            Ok(format!("result<_, {err}>", err = self.ty(names, *err)?))
+                        }
+                        (Some(ok), None) => Ok(format!("result<{ok}>", ok = self.ty(names, *ok)?)),
+                        (Some(ok), Some(err)) => Ok(format!(
+                            "result<{ok}, {err}>",
+                            ok = self.ty(names, *ok)?,
+                            err = self.ty(names, *err)?
+                        )),
+                    },
+                    DefinedType::Alias(ty) => self.ty(names, *ty),
+                    DefinedType::Variant(_)
+                    | DefinedType::Record(_)
+                    | DefinedType::Flags(_)
+                    | DefinedType::Enum(_) => bail!(
+                        "cannot decode an unnamed {kind} type",
+                        kind = self.definitions.types[id].as_str(self.definitions)
+                    ),
+                }
+            }
+        }
+    }
+
+    /// Finds the name of a defined type in scope.
+    ///
+    /// Types are first found by identity and then by structural equality.
+    fn named<'b>(&self, names: &'b Names, id: DefinedTypeId) -> Option<&'b String> {
+        names.types.get(&id).or_else(|| {
+            names
+                .types
+                .iter()
+                .find(|(other, _)| self.same(**other, id))
+                .map(|(_, name)| name)
+        })
+    }
+
+    fn same(&self, a: DefinedTypeId, b: DefinedTypeId) -> bool {
+        fn same_ty(definitions: &Definitions, a: ValueType, b: ValueType) -> bool {
+            match (a, b) {
+                (ValueType::Primitive(a), ValueType::Primitive(b)) => a == b,
+                (ValueType::Borrow(a), ValueType::Borrow(b))
+                | (ValueType::Own(a), ValueType::Own(b)) => {
+                    definitions.resolve_resource_id(a) == definitions.resolve_resource_id(b)
+                }
+                (ValueType::Defined { id: a, .. }, ValueType::Defined { id: b, .. }) => {
+                    same_defined(definitions, a, b)
+                }
+                _ => false,
+            }
+        }
+
+        fn same_defined(definitions: &Definitions, a: DefinedTypeId, b: DefinedTypeId) -> bool {
+            if a == b {
+                return true;
+            }
+
+            match (&definitions.types[a], &definitions.types[b]) {
+                (DefinedType::Record(a), DefinedType::Record(b)) => {
+                    a.fields.len() == b.fields.len()
+                        && a.fields
+                            .iter()
+                            .zip(&b.fields)
+                            .all(|((an, at), (bn, bt))| an == bn && same_ty(definitions, *at, *bt))
+                }
+                (DefinedType::Variant(a), DefinedType::Variant(b)) => {
+                    a.cases.len() == b.cases.len()
+                        && a.cases.iter().zip(&b.cases).all(|((an, at), (bn, bt))| {
+                            an == bn
+                                && match (at, bt) {
+                                    (Some(at), Some(bt)) => same_ty(definitions, *at, *bt),
+                                    (None, None) => true,
+                                    _ => false,
+                                }
+                        })
+                }
+                (DefinedType::Flags(a), DefinedType::Flags(b)) => a.0 == b.0,
+                (DefinedType::Enum(a), DefinedType::Enum(b)) => a.0 == b.0,
+                _ => false,
+            }
+        }
+
+        same_defined(self.definitions, a, b)
+    }
+
+    fn type_decl(&self, names: &Names, name: &str, ty: ValueType) -> Result<String> {
+        let id = ident(name)?;
+        let defined = match ty {
+            ValueType::Defined { id, .. } => &self.definitions.types[id],
+            _ => return Ok(format!("type {id} = {ty};\n", ty = self.ty(names, ty)?)),
+        };
+
+        let mut s = String::new();
+        match defined {
+            DefinedType::Record(record) => {
+                writeln!(s, "record {id} {{")?;
+                for (name, ty) in &record.fields {
+                    writeln!(
+                        s,
+                        "{name}: {ty},",
+                        name = ident(name)?,
+                        ty = self.ty(names, *ty)?
+                    )?;
+                }
+                s.push_str("}\n");
+            }
+            DefinedType::Variant(variant) => {
+                writeln!(s, "variant {id} {{")?;
+                for (name, ty) in &variant.cases {
+                    match ty {
+                        Some(ty) => writeln!(
+                            s,
+                            "{name}({ty}),",
+                            name = ident(name)?,
+                            ty = self.ty(names, *ty)?
+                        )?,
+                        None => writeln!(s, "{name},", name = ident(name)?)?,
+                    }
+                }
+                s.push_str("}\n");
+            }
+            DefinedType::Flags(flags) => {
+                writeln!(s, "flags {id} {{")?;
+                for flag in &flags.0 {
+                    writeln!(s, "{flag},", flag = ident(flag)?)?;
+                }
+                s.push_str("}\n");
+            }
+            DefinedType::Enum(cases) => {
+                writeln!(s, "enum {id} {{")?;
+                for case in &cases.0 {
+                    writeln!(s, "{case},", case = ident(case)?)?;
+                }
+                s.push_str("}\n");
+            }
+            DefinedType::Alias(ty) => writeln!(s, "type {id} = {ty};", ty = self.ty(names, *ty)?)?,
+            DefinedType::Tuple(_)
+            | DefinedType::List(_)
+            | DefinedType::Option(_)
+            | DefinedType::Result { .. } => {
+                // Print the structure of the type rather than its own name
+                let mut names = names.clone();
+                if let ValueType::Defined { id, .. } = ty {
+                    names.types.remove(&id);
+                }
+
+                writeln!(s, "type {id} = {ty};", ty = self.ty(&names, ty)?)?;
+            }
+        }
+
+        Ok(s)
+    }
+
+    fn uses(&self, names: &mut Names, uses: &IndexMap<String, super::UsedType>) -> Result<String> {
+        let mut grouped: IndexMap<InterfaceId, Vec<String>> = IndexMap::new();
+        for (name, used) in uses {
+            let interface = &self.definitions.interfaces[used.interface];
+            if interface.id.is_none() {
+                bail!("cannot decode a use of type `{name}` from an unnamed interface");
+            }
+
+            let orig = used.name.as_deref().unwrap_or(name);
+            match interface.exports.get(orig) {
+                Some(ItemKind::Type(Type::Value(ValueType::Defined { id, .. }))) => {
+                    names.types.insert(*id, ident(name)?);
+                }
+                Some(ItemKind::Resource(id)) => {
+                    names.resources.insert(*id, ident(name)?);
+                }
+                _ => {}
+            }
+
+            let item = match &used.name {
+                Some(orig) => format!("{orig} as {name}", orig = ident(orig)?, name = ident(name)?),
+                None => ident(name)?,
+            };
+
+            grouped.entry(used.interface).or_default().push(item);
+        }
+
+        let mut s = String::new();
+        for (interface, items) in grouped {
+            let path = self.definitions.interfaces[interface]
+                .id
+                .as_deref()
+                .unwrap();
+            let path = self
+                .interfaces
+                .get(path)
+                .map(String::as_str)
+                .unwrap_or(path);
+            writeln!(s, "use {path}.{{ {items} }};", items = items.join(", "))?;
+        }
+
+        Ok(s)
+    }
+
+    fn interface(&self, id: InterfaceId) -> Result<String> {
+        let interface = &self.definitions.interfaces[id];
+        let mut names = Names::default();
+        let mut s = self.uses(&mut names, &interface.uses)?;
+
+        // Register the names of the types first so that they may be referenced
+        for (name, kind) in &interface.exports {
+            match kind {
+                ItemKind::Type(Type::Value(ValueType::Defined { id, .. })) => {
+                    names.types.entry(*id).or_insert(ident(name)?);
+                }
+                ItemKind::Resource(id) => {
+                    names.resources.entry(*id).or_insert(ident(name)?);
+                }
+                _ => {}
+            }
+        }
+
+        for (name, kind) in &interface.exports {
+            if interface.uses.contains_key(name) {
+                continue;
+            }
+
+            s.push_str(&self.item(&names, &interface.exports, name, *kind, None)?);
+        }
+
+        Ok(s)
+    }
+
+    fn world(&self, id: WorldId) -> Result<String> {
+        let world = &self.definitions.worlds[id];
+        let mut names = Names::default();
+        let mut s = self.uses(&mut names, &world.uses)?;
+
+        for (name, kind) in world.imports.iter().chain(&world.exports) {
+            match kind {
+                ItemKind::Type(Type::Value(ValueType::Defined { id, .. })) => {
+                    names.types.entry(*id).or_insert(ident(name)?);
+                }
+                ItemKind::Resource(id) => {
+                    names.resources.entry(*id).or_insert(ident(name)?);
+                }
+                _ => {}
+            }
+        }
+
+        for (name, kind) in &world.imports {
+            if world.uses.contains_key(name) {
+                continue;
+            }
+
+            s.push_str(&self.item(&names, &world.imports, name, *kind, Some("import"))?);
+        }
+
+        for (name, kind) in &world.exports {
+            s.push_str(&self.item(&names, &world.exports, name, *kind, Some("export"))?);
+        }
+
+        Ok(s)
+    }
+
+    /// Prints an item of an interface or world.
+    ///
+    /// The `keyword` is `import` or `export` for world items.
+    fn item(
+        &self,
+        names: &Names,
+        items: &IndexMap<String, ItemKind>,
+        name: &str,
+        kind: ItemKind,
+        keyword: Option<&str>,
+    ) -> Result<String> {
+        let prefix = keyword.map(|k| format!("{k} ")).unwrap_or_default();
+        match kind {
+            ItemKind::Type(Type::Value(ty)) => self.type_decl(names, name, ty),
+            ItemKind::Type(Type::Func(id)) => Ok(format!(
+                "type {id} = {ty};\n",
+                id = ident(name)?,
+                ty = self.func_with(names, &self.definitions.funcs[id])?
+            )),
+            ItemKind::Resource(_) => self.resource(names, items, name),
+            ItemKind::Func(_) if name.starts_with('[') => {
+                // Resource functions are printed with the resource
+                Ok(String::new())
+            }
+            ItemKind::Func(id) => Ok(format!(
+                "{prefix}{id}: {ty};\n",
+                id = ident(name)?,
+                ty = self.func_with(names, &self.definitions.funcs[id])?
+            )),
+            ItemKind::Instance(id) if keyword.is_some() => {
+                match &self.definitions.interfaces[id].id {
+                    Some(path) if path == name => Ok(format!("{prefix}{path};\n")),
+                    _ => Ok(format!(
+                        "{prefix}{id}: interface {{\n{body}}};\n",
+                        id = ident(name)?,
+                        body = self.interface(id)?
+                    )),
+                }
+            }
+            _ => bail!(
+                "cannot decode item `{name}` of kind {kind}",
+                kind = kind.as_str(self.definitions)
+            ),
+        }
+    }
+
+    fn resource(
+        &self,
+        names: &Names,
+        items: &IndexMap<String, ItemKind>,
+        name: &str,
+    ) -> Result<String> {
+        let resource_id = ident(name)?;
+        let mut s = format!("resource {resource_id} {{\n");
+
+        for (item, kind) in items {
+            let func = match kind {
+                ItemKind::Func(func) => &self.definitions.funcs[*func],
+                _ => continue,
+            };
+
+            if item.strip_prefix("[constructor]") == Some(name) {
+                writeln!(
+                    s,
+                    "constructor{ty};",
+                    ty = &self.func_type(names, func, 0, false)?[4..]
+                )?;
+            } else if let Some(method) = item
+                .strip_prefix("[method]")
+                .and_then(|m| m.strip_prefix(name))
+                .and_then(|m| m.strip_prefix('.'))
+            {
+                writeln!(
+                    s,
+                    "{method}: {ty};",
+                    method = ident(method)?,
+                    ty = self.func_type(names, func, 1, true)?
+                )?;
+            } else if let Some(method) = item
+                .strip_prefix("[static]")
+                .and_then(|m| m.strip_prefix(name))
+                .and_then(|m| m.strip_prefix('.'))
+            {
+                writeln!(
+                    s,
+                    "{method}: static {ty};",
+                    method = ident(method)?,
+                    ty = self.func_type(names, func, 0, true)?
+                )?;
+            }
+        }
+
+        s.push_str("}\n");
+        Ok(s)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn it_parses_dependency_names() {
+        for (name, expected) in [
+            ("unlocked-dep=<foo:bar>", Some("foo:bar")),
+            ("unlocked-dep=<foo:bar@*>", Some("foo:bar")),
+            ("unlocked-dep=<foo:bar@{>=1.2.0}>", Some("foo:bar@1.2.0")),
+            (
+                "unlocked-dep=<foo:bar@{>=1.2.0 <2.0.0}>",
+                Some("foo:bar@1.2.0"),
+            ),
+            ("unlocked-dep=<foo:bar@{<2.0.0}>", Some("foo:bar")),
+            ("locked-dep=<foo:bar@1.2.0>", Some("foo:bar@1.2.0")),
+            (
+                "locked-dep=<foo:bar@1.2.0>,integrity=<sha256-47DEQpj8HBSa+/TImW+5JCeuQeRkm5NMpJWZG3hSuFU=>",
+                Some("foo:bar@1.2.0"),
+            ),
+            ("url=<https://example.com/foo.wasm>", None),
+            (
+                "url=<https://example.com/foo.wasm>,integrity=<sha256-47DEQpj8HBSa+/TImW+5JCeuQeRkm5NMpJWZG3hSuFU=>",
+                None,
+            ),
+            (
+                "integrity=<sha256-47DEQpj8HBSa+/TImW+5JCeuQeRkm5NMpJWZG3hSuFU=>",
+                None,
+            ),
+            ("unlocked-dep=<foo:bar@{>=1.2.0}", None),
+            ("foo:bar", None),
+        ] {
+            assert_eq!(dependency_name(name).as_deref(), expected, "for `{name}`");
+        }
+    }
+}
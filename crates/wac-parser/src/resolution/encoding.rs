@@ -6,17 +6,20 @@ use super::{
     WorldId,
 };
 use crate::{
-    resolution::FuncResult, CoreExtern, Definition, Import, Instantiation, Item, ItemId, PackageId,
-    UsedType,
+    resolution::FuncResult,
+    source_map::{self, ItemSort, SourceMap},
+    sources::Sources,
+    CoreExtern, Definition, Import, Instantiation, Item, ItemId, PackageId, UsedType,
 };
 use anyhow::Result;
 use indexmap::{map::Entry, IndexMap, IndexSet};
+use miette::SourceSpan;
 use std::fmt::Write;
 use wasm_encoder::{
     Alias, ComponentBuilder, ComponentExportKind, ComponentOuterAliasKind, ComponentType,
-    ComponentTypeEncoder, ComponentTypeRef, ComponentValType, CoreTypeEncoder, EntityType,
-    GlobalType, InstanceType, MemoryType, ModuleType, PrimitiveValType, TableType, TagKind,
-    TagType, TypeBounds,
+    ComponentTypeEncoder, ComponentTypeRef, ComponentValType, CoreTypeEncoder, CustomSection,
+    EntityType, GlobalType, InstanceType, MemoryType, ModuleType, PrimitiveValType, TableType,
+    TagKind, TagType, TypeBounds,
 };
 
 fn package_import_name(package: &Package) -> String {
@@ -41,17 +44,25 @@ pub struct EncodingOptions {
 pub struct Encoder<'a> {
     composition: &'a Composition,
     options: EncodingOptions,
+    sources: Option<&'a Sources>,
     packages: IndexMap<PackageId, u32>,
     exports: IndexSet<&'a str>,
+    source_map: SourceMap,
 }
 
 impl<'a> Encoder<'a> {
-    pub fn new(composition: &'a Composition, options: EncodingOptions) -> Self {
+    pub fn new(
+        composition: &'a Composition,
+        options: EncodingOptions,
+        sources: Option<&'a Sources>,
+    ) -> Self {
         Self {
             composition,
             options,
+            sources,
             packages: Default::default(),
             exports: Default::default(),
+            source_map: Default::default(),
         }
     }
 
@@ -77,14 +88,27 @@ impl<'a> Encoder<'a> {
 
             let index = state.item_indexes[id];
             let item = &self.composition.items[*id];
-            state
+            let index = state
                 .builder()
                 .export(name, item.kind().into(), index, None);
+            self.map(
+                item.kind().into(),
+                index,
+                self.composition.export_spans.get(name),
+            );
         }
 
         assert!(state.scopes.is_empty());
         match state.current.encodable {
             Encodable::Builder(mut builder) => {
+                if self.sources.is_some() {
+                    let data = self.source_map.encode();
+                    builder.custom_section(&CustomSection {
+                        name: source_map::SECTION_NAME.into(),
+                        data: data.as_slice().into(),
+                    });
+                }
+
                 let mut producer = wasm_metadata::Producers::empty();
                 producer.add(
                     "processed-by",
@@ -115,9 +139,18 @@ impl<'a> Encoder<'a> {
         let index = encode()?;
         let prev = state.item_indexes.insert(id, index);
         assert!(prev.is_none());
+
+        self.map(item.kind().into(), index, self.composition.spans.get(&id));
         Ok(index)
     }
 
+    /// Adds an encoded item to the source map, if one is being emitted.
+    fn map(&mut self, sort: ItemSort, index: u32, span: Option<&SourceSpan>) {
+        if let (Some(sources), Some(span)) = (self.sources, span) {
+            self.source_map.insert(sources, sort, index, *span);
+        }
+    }
+
     fn definition(&mut self, state: &mut State<'a>, definition: &'a Definition) -> Result<u32> {
         log::debug!(
             "encoding definition `{name}` ({kind})",
@@ -145,6 +178,12 @@ impl<'a> Encoder<'a> {
         let inserted = self.exports.insert(&definition.name);
         assert!(inserted);
 
+        self.map(
+            ItemSort::Type,
+            index,
+            self.composition.export_spans.get(&definition.name),
+        );
+
         // Remap to the exported index
         state.current.type_indexes.insert(ty, index);
 
@@ -360,6 +399,19 @@ impl Encodable {
     }
 }
 
+impl From<ItemKind> for ItemSort {
+    fn from(value: ItemKind) -> Self {
+        match ComponentExportKind::from(value) {
+            ComponentExportKind::Module => Self::Module,
+            ComponentExportKind::Func => Self::Func,
+            ComponentExportKind::Value => Self::Value,
+            ComponentExportKind::Type => Self::Type,
+            ComponentExportKind::Instance => Self::Instance,
+            ComponentExportKind::Component => Self::Component,
+        }
+    }
+}
+
 impl Default for Encodable {
     fn default() -> Self {
         Self::Builder(Default::default())
@@ -0,0 +1,282 @@
+//! Module for exporting the instantiation graph of a composition.
+
+use super::{Composition, Item, ItemId};
+use std::{collections::HashMap, fmt::Write};
+
+/// Represents the kind of a node in an instantiation graph.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum NodeKind {
+    Import,
+    Instantiation,
+    Definition,
+    Export,
+}
+
+/// Represents a node in an instantiation graph.
+struct Node {
+    id: String,
+    label: String,
+    kind: NodeKind,
+}
+
+/// Represents an edge in an instantiation graph.
+struct Edge {
+    from: String,
+    to: String,
+    label: String,
+}
+
+/// Represents the instantiation graph of a composition.
+///
+/// Nodes are the imports, instantiations, and exports of the composition.
+///
+/// Aliases of instance exports are not nodes in the graph; instead, an edge
+/// from the aliased instance is labeled with the name of the export.
+struct Graph {
+    nodes: Vec<Node>,
+    edges: Vec<Edge>,
+}
+
+impl Graph {
+    fn new(composition: &Composition) -> Self {
+        let mut graph = Self {
+            nodes: Default::default(),
+            edges: Default::default(),
+        };
+
+        // Map items to the names they were given in the document
+        let names: HashMap<ItemId, &str> = composition
+            .names
+            .iter()
+            .map(|(name, (id, _))| (*id, name.as_str()))
+            .collect();
+
+        let mut added = HashMap::new();
+        for (id, item) in &composition.items {
+            match item {
+                Item::Import(import) => {
+                    graph.node(
+                        &mut added,
+                        id,
+                        format!("import {name}", name = import.name),
+                        NodeKind::Import,
+                    );
+                }
+                Item::Instantiation(instantiation) => {
+                    let package = &composition.packages[instantiation.package];
+                    let package = match &package.version {
+                        Some(version) => format!("{name}@{version}", name = package.name),
+                        None => package.name.clone(),
+                    };
+
+                    let label = match names.get(&id) {
+                        Some(name) => format!("{name} = new {package}"),
+                        None => format!("new {package}"),
+                    };
+
+                    graph.node(&mut added, id, label, NodeKind::Instantiation);
+
+                    for (arg, item) in &instantiation.arguments {
+                        let (from, label) = graph.source(composition, &names, &mut added, *item);
+                        graph.edges.push(Edge {
+                            from,
+                            to: Self::id(id),
+                            label: match label {
+                                Some(export) if export != *arg => format!("{export} as {arg}"),
+                                _ => arg.clone(),
+                            },
+                        });
+                    }
+                }
+                Item::Use(_) | Item::Definition(_) | Item::Alias(_) => continue,
+            }
+        }
+
+        for (index, (name, item)) in composition.exports.iter().enumerate() {
+            let to = format!("export{index}");
+            graph.nodes.push(Node {
+                id: to.clone(),
+                label: format!("export {name}"),
+                kind: NodeKind::Export,
+            });
+
+            let (from, label) = graph.source(composition, &names, &mut added, *item);
+            graph.edges.push(Edge {
+                from,
+                to,
+                label: label.unwrap_or_default(),
+            });
+        }
+
+        graph
+    }
+
+    fn id(item: ItemId) -> String {
+        format!("item{index}", index = item.index())
+    }
+
+    fn node(
+        &mut self,
+        added: &mut HashMap<ItemId, String>,
+        item: ItemId,
+        label: String,
+        kind: NodeKind,
+    ) -> String {
+        added
+            .entry(item)
+            .or_insert_with(|| {
+                let id = Self::id(item);
+                self.nodes.push(Node {
+                    id: id.clone(),
+                    label,
+                    kind,
+                });
+                id
+            })
+            .clone()
+    }
+
+    /// Gets the node that is the source of the given item.
+    ///
+    /// For aliases, the source is the aliased instance and the export name is returned.
+    fn source(
+        &mut self,
+        composition: &Composition,
+        names: &HashMap<ItemId, &str>,
+        added: &mut HashMap<ItemId, String>,
+        mut item: ItemId,
+    ) -> (String, Option<String>) {
+        let mut exports = Vec::new();
+        while let Item::Alias(alias) = &composition.items[item] {
+            exports.push(alias.export.as_str());
+            item = alias.item;
+        }
+
+        let label = if exports.is_empty() {
+            None
+        } else {
+            exports.reverse();
+            Some(exports.join("."))
+        };
+
+        // Definitions and uses are only added to the graph when referenced
+        let id = match &composition.items[item] {
+            Item::Definition(definition) => self.node(
+                added,
+                item,
+                format!("type {name}", name = definition.name),
+                NodeKind::Definition,
+            ),
+            Item::Use(_) => self.node(
+                added,
+                item,
+                names
+                    .get(&item)
+                    .map(|name| format!("use {name}"))
+                    .unwrap_or_else(|| "use".to_string()),
+                NodeKind::Definition,
+            ),
+            _ => Self::id(item),
+        };
+
+        (id, label)
+    }
+
+    fn dot(&self) -> String {
+        fn escape(s: &str) -> String {
+            s.replace('\\', "\\\\").replace('"', "\\\"")
+        }
+
+        let mut s = String::new();
+        s.push_str("digraph {\n");
+        s.push_str("  rankdir=LR;\n");
+        for node in &self.nodes {
+            let shape = match node.kind {
+                NodeKind::Import => "invhouse",
+                NodeKind::Instantiation => "box",
+                NodeKind::Definition => "note",
+                NodeKind::Export => "house",
+            };
+
+            writeln!(
+                s,
+                "  {id} [label=\"{label}\", shape={shape}];",
+                id = node.id,
+                label = escape(&node.label)
+            )
+            .unwrap();
+        }
+
+        for edge in &self.edges {
+            if edge.label.is_empty() {
+                writeln!(s, "  {from} -> {to};", from = edge.from, to = edge.to).unwrap();
+            } else {
+                writeln!(
+                    s,
+                    "  {from} -> {to} [label=\"{label}\"];",
+                    from = edge.from,
+                    to = edge.to,
+                    label = escape(&edge.label)
+                )
+                .unwrap();
+            }
+        }
+
+        s.push_str("}\n");
+        s
+    }
+
+    fn mermaid(&self) -> String {
+        fn escape(s: &str) -> String {
+            s.replace('"', "#quot;")
+        }
+
+        let mut s = String::new();
+        s.push_str("flowchart LR\n");
+        for node in &self.nodes {
+            let label = escape(&node.label);
+            match node.kind {
+                NodeKind::Import => writeln!(s, "  {id}[/\"{label}\"/]", id = node.id),
+                NodeKind::Instantiation => writeln!(s, "  {id}[\"{label}\"]", id = node.id),
+                NodeKind::Definition => writeln!(s, "  {id}[(\"{label}\")]", id = node.id),
+                NodeKind::Export => writeln!(s, "  {id}[\\\"{label}\"\\]", id = node.id),
+            }
+            .unwrap();
+        }
+
+        for edge in &self.edges {
+            if edge.label.is_empty() {
+                writeln!(s, "  {from} --> {to}", from = edge.from, to = edge.to).unwrap();
+            } else {
+                writeln!(
+                    s,
+                    "  {from} -->|\"{label}\"| {to}",
+                    from = edge.from,
+                    to = edge.to,
+                    label = escape(&edge.label)
+                )
+                .unwrap();
+            }
+        }
+
+        s
+    }
+}
+
+impl Composition {
+    /// Gets the instantiation graph of the composition in the Graphviz DOT format.
+    ///
+    /// Nodes in the graph are the composition's imports, instantiations, and exports;
+    /// edges are the instantiation arguments and exported items.
+    pub fn to_dot(&self) -> String {
+        Graph::new(self).dot()
+    }
+
+    /// Gets the instantiation graph of the composition as a Mermaid flowchart.
+    ///
+    /// Nodes in the graph are the composition's imports, instantiations, and exports;
+    /// edges are the instantiation arguments and exported items.
+    pub fn to_mermaid(&self) -> String {
+        Graph::new(self).mermaid()
+    }
+}
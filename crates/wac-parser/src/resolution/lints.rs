@@ -0,0 +1,366 @@
+//! Module for the lints reported as warnings during resolution.
+
+use crate::{
+    ast::starts_statement,
+    lexer::{regular_comments, Lexer, Token},
+    sources::Sources,
+};
+use anyhow::{bail, Result};
+use miette::{Diagnostic, LabeledSpan, Severity, SourceSpan};
+use std::{collections::HashMap, fmt, ops::Range, str::FromStr};
+
+/// Represents a lint that may report warnings for a composition.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Lint {
+    /// A `let` binding that is never used.
+    UnusedLet,
+    /// An explicit import that is never used.
+    UnusedImport,
+    /// An instantiation whose exports are never used or exported.
+    UnusedInstantiation,
+    /// A spread instantiation argument that only matches arguments that were
+    /// already provided.
+    RedundantSpread,
+    /// An `as` clause that does not change the name.
+    RedundantRename,
+}
+
+impl Lint {
+    /// All of the lints.
+    pub const ALL: [Self; 5] = [
+        Self::UnusedLet,
+        Self::UnusedImport,
+        Self::UnusedInstantiation,
+        Self::RedundantSpread,
+        Self::RedundantRename,
+    ];
+
+    /// Gets the name of the lint.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::UnusedLet => "unused-let",
+            Self::UnusedImport => "unused-import",
+            Self::UnusedInstantiation => "unused-instantiation",
+            Self::RedundantSpread => "redundant-spread",
+            Self::RedundantRename => "redundant-rename",
+        }
+    }
+}
+
+impl fmt::Display for Lint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{name}", name = self.name())
+    }
+}
+
+impl FromStr for Lint {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match Self::ALL.iter().find(|l| l.name() == s) {
+            Some(lint) => Ok(*lint),
+            None => bail!("unknown lint `{s}`"),
+        }
+    }
+}
+
+/// Represents the level at which a lint is reported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LintLevel {
+    /// The lint is not reported.
+    Allow,
+    /// The lint is reported as a warning.
+    Warn,
+    /// The lint is reported as an error.
+    Deny,
+}
+
+/// Represents a warning reported by a lint.
+#[derive(Debug, Clone)]
+pub struct Warning {
+    lint: Lint,
+    message: String,
+    label: String,
+    span: SourceSpan,
+    level: LintLevel,
+}
+
+impl Warning {
+    pub(crate) fn new(
+        lint: Lint,
+        message: impl Into<String>,
+        label: impl Into<String>,
+        span: SourceSpan,
+    ) -> Self {
+        Self {
+            lint,
+            message: message.into(),
+            label: label.into(),
+            span,
+            level: LintLevel::Warn,
+        }
+    }
+
+    /// Gets the lint that reported the warning.
+    pub fn lint(&self) -> Lint {
+        self.lint
+    }
+
+    /// Gets the span of the warning.
+    pub fn span(&self) -> SourceSpan {
+        self.span
+    }
+
+    /// Gets the level of the warning.
+    ///
+    /// This is [`LintLevel::Deny`] if the warning should be treated as an error.
+    pub fn level(&self) -> LintLevel {
+        self.level
+    }
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{message}", message = self.message)
+    }
+}
+
+impl std::error::Error for Warning {}
+
+impl Diagnostic for Warning {
+    fn code<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        Some(Box::new(self.lint))
+    }
+
+    fn severity(&self) -> Option<Severity> {
+        match self.level {
+            LintLevel::Deny => Some(Severity::Error),
+            LintLevel::Allow | LintLevel::Warn => Some(Severity::Warning),
+        }
+    }
+
+    fn help<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        Some(Box::new(format!(
+            "add `// wac: allow({lint})` before the statement to allow this",
+            lint = self.lint
+        )))
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = LabeledSpan> + '_>> {
+        Some(Box::new(std::iter::once(LabeledSpan::new_with_span(
+            Some(self.label.clone()),
+            self.span,
+        ))))
+    }
+}
+
+/// Represents the configured levels of lints.
+///
+/// Lints are reported as warnings unless configured otherwise.
+#[derive(Debug, Clone, Default)]
+pub struct LintLevels(HashMap<Lint, LintLevel>);
+
+impl LintLevels {
+    /// Gets the level of the given lint.
+    pub fn level(&self, lint: Lint) -> LintLevel {
+        self.0.get(&lint).copied().unwrap_or(LintLevel::Warn)
+    }
+
+    /// Sets the level of the given lint.
+    pub fn set(&mut self, lint: Lint, level: LintLevel) {
+        self.0.insert(lint, level);
+    }
+
+    /// Sets the level of the lint with the given name.
+    ///
+    /// The name `warnings` sets the level of every lint.
+    pub fn set_by_name(&mut self, name: &str, level: LintLevel) -> Result<()> {
+        if name == "warnings" {
+            for lint in Lint::ALL {
+                self.set(lint, level);
+            }
+
+            return Ok(());
+        }
+
+        self.set(name.parse()?, level);
+        Ok(())
+    }
+
+    /// Applies the lint levels to the given warnings.
+    ///
+    /// Lints may also be allowed in the source with a `// wac: allow(<lint>, ...)`
+    /// comment, which applies to the statement following it; if the comment
+    /// precedes the package directive, it applies to the entire document.
+    ///
+    /// Returns the warnings that are not allowed.
+    pub fn apply(&self, source: &str, warnings: &[Warning]) -> Vec<Warning> {
+        self.apply_directives(&allow_directives(source), warnings)
+    }
+
+    /// Applies the lint levels to the warnings of a composition spanning the
+    /// given sources.
+    ///
+    /// This is like [`LintLevels::apply`], except the `// wac: allow` comments
+    /// of each source file apply to the statements of that file.
+    pub fn apply_sources(&self, sources: &Sources, warnings: &[Warning]) -> Vec<Warning> {
+        let allowed: Vec<_> = sources
+            .files()
+            .flat_map(|file| {
+                allow_directives(file.text())
+                    .into_iter()
+                    .map(|(range, lints)| {
+                        (
+                            range.start + file.offset()..range.end + file.offset(),
+                            lints,
+                        )
+                    })
+            })
+            .collect();
+
+        self.apply_directives(&allowed, warnings)
+    }
+
+    fn apply_directives(
+        &self,
+        allowed: &[(Range<usize>, Vec<Lint>)],
+        warnings: &[Warning],
+    ) -> Vec<Warning> {
+        warnings
+            .iter()
+            .filter(|w| {
+                !allowed.iter().any(|(range, lints)| {
+                    range.contains(&w.span.offset()) && lints.contains(&w.lint)
+                })
+            })
+            .filter_map(|w| match self.level(w.lint) {
+                LintLevel::Allow => None,
+                level => Some(Warning { level, ..w.clone() }),
+            })
+            .collect()
+    }
+}
+
+/// Gets the `// wac: allow(...)` directives in the given source.
+///
+/// Each directive is the range of the source it applies to and the allowed lints.
+fn allow_directives(source: &str) -> Vec<(Range<usize>, Vec<Lint>)> {
+    let mut directives = Vec::new();
+    let mut starts = None;
+    for (comment, span) in regular_comments(source) {
+        let lints = match comment
+            .strip_prefix("//")
+            .map(str::trim)
+            .and_then(|c| c.strip_prefix("wac:"))
+            .map(str::trim)
+            .and_then(|c| c.strip_prefix("allow("))
+            .and_then(|c| c.strip_suffix(')'))
+        {
+            Some(lints) => lints,
+            None => continue,
+        };
+
+        let lints: Vec<Lint> = lints
+            .split(',')
+            .filter_map(|l| match l.trim().parse() {
+                Ok(lint) => Some(lint),
+                Err(e) => {
+                    log::debug!("ignoring lint directive: {e}");
+                    None
+                }
+            })
+            .collect();
+
+        // The statement offsets are only needed if there is a directive
+        let starts: &Vec<usize> = starts.get_or_insert_with(|| statement_starts(source));
+        let range = match starts.iter().position(|s| *s > span.offset()) {
+            // A directive preceding the package directive applies to the document
+            Some(0) => 0..source.len(),
+            Some(i) => starts[i]..starts.get(i + 1).copied().unwrap_or(source.len()),
+            None => continue,
+        };
+
+        directives.push((range, lints));
+    }
+
+    directives
+}
+
+/// Gets the offsets of the package directive and each top-level statement in the source.
+fn statement_starts(source: &str) -> Vec<usize> {
+    let lexer = match Lexer::new(source) {
+        Ok(lexer) => lexer,
+        Err(_) => return Vec::new(),
+    };
+
+    let mut starts = Vec::new();
+    let mut depth = 0usize;
+    for (token, span) in lexer {
+        match token {
+            Ok(Token::OpenBrace) => depth += 1,
+            Ok(Token::CloseBrace) => depth = depth.saturating_sub(1),
+            Ok(token)
+                if depth == 0 && (token == Token::PackageKeyword || starts_statement(token)) =>
+            {
+                starts.push(span.offset())
+            }
+            _ => {}
+        }
+    }
+
+    starts
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{ast::Document, Composition};
+
+    #[test]
+    fn it_applies_lint_levels() {
+        let source = r#"package test:comp;
+import f: func();
+import u: func();
+interface i {
+    type t = u32;
+}
+interface j {
+    use i.{t as t};
+}
+export f as "f";
+"#;
+
+        let document = Document::parse(source).unwrap();
+        let composition = Composition::from_ast(&document, Default::default()).unwrap();
+        let warnings: Vec<_> = composition.warnings.iter().map(Warning::lint).collect();
+        assert_eq!(
+            warnings,
+            [
+                Lint::UnusedImport,
+                Lint::RedundantRename,
+                Lint::RedundantRename
+            ]
+        );
+
+        let mut levels = LintLevels::default();
+        levels.set(Lint::UnusedImport, LintLevel::Allow);
+        levels.set(Lint::RedundantRename, LintLevel::Deny);
+        let warnings: Vec<_> = levels
+            .apply(source, &composition.warnings)
+            .iter()
+            .map(|w| (w.lint(), w.level()))
+            .collect();
+
+        assert_eq!(
+            warnings,
+            [
+                (Lint::RedundantRename, LintLevel::Deny),
+                (Lint::RedundantRename, LintLevel::Deny),
+            ]
+        );
+
+        let mut levels = LintLevels::default();
+        levels.set_by_name("warnings", LintLevel::Allow).unwrap();
+        assert!(levels.apply(source, &composition.warnings).is_empty());
+    }
+}
@@ -6,7 +6,7 @@ use super::{
 use crate::{Resource, ResourceId, UsedType};
 use anyhow::{bail, Result};
 use indexmap::IndexMap;
-use semver::Version;
+use semver::{Version, VersionReq};
 use serde::Serialize;
 use std::{collections::HashMap, fmt, rc::Rc, sync::Arc};
 use wasmparser::{
@@ -16,12 +16,37 @@ use wasmparser::{
 };
 
 /// Represents a package key that can be used in associative containers.
-#[derive(Copy, Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
 pub struct PackageKey<'a> {
     /// The name of the package,
     pub name: &'a str,
     /// The version of the package.
     pub version: Option<&'a Version>,
+    /// The version requirement of the package.
+    ///
+    /// This is only `Some` when `version` is `None`; resolvers are expected
+    /// to resolve the highest version of the package matching the requirement.
+    pub requirement: Option<&'a VersionReq>,
+}
+
+impl PartialOrd for PackageKey<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for PackageKey<'_> {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        // `VersionReq` is not ordered, so requirements compare by their text
+        self.name
+            .cmp(other.name)
+            .then_with(|| self.version.cmp(&other.version))
+            .then_with(|| {
+                self.requirement
+                    .map(ToString::to_string)
+                    .cmp(&other.requirement.map(ToString::to_string))
+            })
+    }
 }
 
 impl fmt::Display for PackageKey<'_> {
@@ -29,11 +54,35 @@ impl fmt::Display for PackageKey<'_> {
         write!(f, "{name}", name = self.name)?;
         if let Some(version) = self.version {
             write!(f, "@{version}")?;
+        } else if let Some(requirement) = self.requirement {
+            write!(f, "@{requirement}")?;
         }
         Ok(())
     }
 }
 
+/// Represents a package resolved by a package resolver.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolvedPackage {
+    /// The version of the package, if known.
+    ///
+    /// Resolvers report the version they chose for a package that was
+    /// requested without an exact version; the version is used in place of
+    /// the requested version.
+    pub version: Option<Version>,
+    /// The bytes of the package.
+    pub bytes: Arc<Vec<u8>>,
+}
+
+impl From<Arc<Vec<u8>>> for ResolvedPackage {
+    fn from(bytes: Arc<Vec<u8>>) -> Self {
+        Self {
+            version: None,
+            bytes,
+        }
+    }
+}
+
 /// Represents information about a package.
 ///
 /// A package is expected to be a valid WebAssembly component.
@@ -144,7 +144,7 @@ pub enum PrimitiveType {
 }
 
 impl PrimitiveType {
-    fn as_str(&self) -> &'static str {
+    pub(crate) fn as_str(&self) -> &'static str {
         match self {
             Self::U8 => "u8",
             Self::S8 => "s8",
@@ -266,7 +266,7 @@ pub enum DefinedType {
 }
 
 impl DefinedType {
-    fn as_str(&self, definitions: &Definitions) -> &'static str {
+    pub(crate) fn as_str(&self, definitions: &Definitions) -> &'static str {
         match self {
             DefinedType::Tuple(_) => "tuple",
             DefinedType::List(_) => "list",
@@ -0,0 +1,323 @@
+//! Module for the source maps of encoded compositions.
+//!
+//! A source map is a custom section of an encoded composition that maps the
+//! indexes of the component's imports, instances, aliases, and exports to the
+//! location in the WAC source that introduced them.
+//!
+//! Source maps are emitted with [`Composition::encode_with_source_map`](crate::Composition::encode_with_source_map).
+
+use crate::sources::Sources;
+use anyhow::{bail, Result};
+use miette::SourceSpan;
+use std::{fmt, str::FromStr};
+use wasm_encoder::Encode;
+use wasmparser::{BinaryReader, Parser, Payload};
+
+/// The name of the custom section containing the source map.
+pub const SECTION_NAME: &str = "wac-source-map";
+
+/// Represents the index space of a component item.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ItemSort {
+    /// The item is a function.
+    Func,
+    /// The item is a value.
+    Value,
+    /// The item is a type.
+    Type,
+    /// The item is an instance.
+    Instance,
+    /// The item is a component.
+    Component,
+    /// The item is a core module.
+    Module,
+}
+
+impl ItemSort {
+    fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(Self::Func),
+            1 => Some(Self::Value),
+            2 => Some(Self::Type),
+            3 => Some(Self::Instance),
+            4 => Some(Self::Component),
+            5 => Some(Self::Module),
+            _ => None,
+        }
+    }
+
+    fn as_u8(self) -> u8 {
+        match self {
+            Self::Func => 0,
+            Self::Value => 1,
+            Self::Type => 2,
+            Self::Instance => 3,
+            Self::Component => 4,
+            Self::Module => 5,
+        }
+    }
+
+    /// Gets the string representation of the sort.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Func => "func",
+            Self::Value => "value",
+            Self::Type => "type",
+            Self::Instance => "instance",
+            Self::Component => "component",
+            Self::Module => "module",
+        }
+    }
+}
+
+impl fmt::Display for ItemSort {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for ItemSort {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "func" => Ok(Self::Func),
+            "value" => Ok(Self::Value),
+            "type" => Ok(Self::Type),
+            "instance" => Ok(Self::Instance),
+            "component" => Ok(Self::Component),
+            "module" => Ok(Self::Module),
+            _ => bail!("unknown item sort `{s}`; expected `func`, `value`, `type`, `instance`, `component`, or `module`"),
+        }
+    }
+}
+
+/// Represents the location of a component item in the WAC source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SourceLocation<'a> {
+    /// The path of the source file.
+    pub path: &'a str,
+    /// The span of the item in the source file.
+    pub span: SourceSpan,
+    /// The one-based line of the start of the span.
+    pub line: u32,
+    /// The one-based column of the start of the span.
+    pub column: u32,
+}
+
+impl fmt::Display for SourceLocation<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{path}:{line}:{column}",
+            path = self.path,
+            line = self.line,
+            column = self.column
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    sort: ItemSort,
+    index: u32,
+    file: u32,
+    offset: u32,
+    len: u32,
+    line: u32,
+    column: u32,
+}
+
+/// Represents the source map of an encoded composition.
+#[derive(Debug, Default, Clone)]
+pub struct SourceMap {
+    files: Vec<String>,
+    entries: Vec<Entry>,
+}
+
+impl SourceMap {
+    /// Parses a source map from the data of its custom section.
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        let mut reader = BinaryReader::new(data);
+
+        let count = reader.read_var_u32()?;
+        let mut files = Vec::new();
+        for _ in 0..count {
+            files.push(reader.read_string()?.to_owned());
+        }
+
+        let count = reader.read_var_u32()?;
+        let mut entries = Vec::new();
+        for _ in 0..count {
+            let sort = reader.read_u8()?;
+            let entry = Entry {
+                sort: match ItemSort::from_u8(sort) {
+                    Some(sort) => sort,
+                    None => bail!("invalid item sort {sort} in source map"),
+                },
+                index: reader.read_var_u32()?,
+                file: reader.read_var_u32()?,
+                offset: reader.read_var_u32()?,
+                len: reader.read_var_u32()?,
+                line: reader.read_var_u32()?,
+                column: reader.read_var_u32()?,
+            };
+
+            if entry.file as usize >= files.len() {
+                bail!("invalid file index {file} in source map", file = entry.file);
+            }
+
+            entries.push(entry);
+        }
+
+        if !reader.eof() {
+            bail!("unexpected data at the end of the source map");
+        }
+
+        Ok(Self { files, entries })
+    }
+
+    /// Reads the source map from an encoded component.
+    ///
+    /// Returns `Ok(None)` if the component does not contain a source map.
+    ///
+    /// Only the source map of the outermost component is read; the source
+    /// maps of nested components are ignored.
+    pub fn from_component(bytes: &[u8]) -> Result<Option<Self>> {
+        let mut depth = 0;
+        for payload in Parser::new(0).parse_all(bytes) {
+            match payload? {
+                Payload::ModuleSection { .. } | Payload::ComponentSection { .. } => depth += 1,
+                Payload::End(_) => depth -= 1,
+                Payload::CustomSection(section) if depth == 0 && section.name() == SECTION_NAME => {
+                    return Self::parse(section.data()).map(Some);
+                }
+                _ => {}
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Looks up the source location of a component item.
+    pub fn lookup(&self, sort: ItemSort, index: u32) -> Option<SourceLocation<'_>> {
+        self.entries
+            .iter()
+            .find(|e| e.sort == sort && e.index == index)
+            .map(|e| self.location(e))
+    }
+
+    /// Gets the component items in the source map and their source locations.
+    pub fn items(&self) -> impl ExactSizeIterator<Item = (ItemSort, u32, SourceLocation<'_>)> {
+        self.entries
+            .iter()
+            .map(|e| (e.sort, e.index, self.location(e)))
+    }
+
+    /// Adds a component item to the source map.
+    ///
+    /// The span is a span of the given sources.
+    pub(crate) fn insert(
+        &mut self,
+        sources: &Sources,
+        sort: ItemSort,
+        index: u32,
+        span: SourceSpan,
+    ) {
+        let file = match sources.file(span.offset()) {
+            Some(file) => file,
+            None => return,
+        };
+
+        let path = file.path().display().to_string();
+        let file_index = match self.files.iter().position(|f| *f == path) {
+            Some(index) => index,
+            None => {
+                self.files.push(path);
+                self.files.len() - 1
+            }
+        };
+
+        let offset = span.offset() - file.offset();
+        let before = &file.text()[..offset];
+        let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+
+        self.entries.push(Entry {
+            sort,
+            index,
+            file: file_index as u32,
+            offset: offset as u32,
+            len: span.len() as u32,
+            line: before.matches('\n').count() as u32 + 1,
+            column: before[line_start..].chars().count() as u32 + 1,
+        });
+    }
+
+    /// Encodes the data of the source map's custom section.
+    pub(crate) fn encode(&self) -> Vec<u8> {
+        let mut data = Vec::new();
+        self.files.len().encode(&mut data);
+        for file in &self.files {
+            file.as_str().encode(&mut data);
+        }
+
+        self.entries.len().encode(&mut data);
+        for entry in &self.entries {
+            data.push(entry.sort.as_u8());
+            entry.index.encode(&mut data);
+            entry.file.encode(&mut data);
+            entry.offset.encode(&mut data);
+            entry.len.encode(&mut data);
+            entry.line.encode(&mut data);
+            entry.column.encode(&mut data);
+        }
+
+        data
+    }
+
+    fn location(&self, entry: &Entry) -> SourceLocation<'_> {
+        SourceLocation {
+            path: &self.files[entry.file as usize],
+            span: SourceSpan::new((entry.offset as usize).into(), (entry.len as usize).into()),
+            line: entry.line,
+            column: entry.column,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Composition, EncodingOptions};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn it_maps_items_to_sources() {
+        let sources = Sources::new(
+            "test.wac",
+            "package test:comp;\nimport f: func();\nexport f as g;\n",
+        );
+        let document = sources.parse().unwrap();
+        let composition = Composition::from_ast(&document, Default::default()).unwrap();
+
+        let bytes = composition.encode(EncodingOptions::default()).unwrap();
+        assert!(SourceMap::from_component(&bytes).unwrap().is_none());
+
+        let bytes = composition
+            .encode_with_source_map(EncodingOptions::default(), &sources)
+            .unwrap();
+        let map = SourceMap::from_component(&bytes).unwrap().unwrap();
+
+        assert_eq!(
+            map.items()
+                .map(|(sort, index, location)| format!("{sort} {index}: {location}"))
+                .collect::<Vec<_>>(),
+            ["func 0: test.wac:2:8", "func 1: test.wac:3:13"]
+        );
+        assert_eq!(
+            map.lookup(ItemSort::Func, 1).unwrap().span,
+            SourceSpan::new(49.into(), 1.into())
+        );
+        assert!(map.lookup(ItemSort::Instance, 0).is_none());
+    }
+}
@@ -0,0 +1,439 @@
+//! Module for the source files of a composition.
+//!
+//! A composition may span multiple source files when its document includes
+//! other documents with `include` statements.
+//!
+//! Each file is given a distinct range of offsets so that the spans of the
+//! parsed documents identify both the file and the location in the file.
+
+use crate::ast::{Document, Statement};
+use miette::{Diagnostic, MietteError, MietteSpanContents, SourceCode, SourceSpan, SpanContents};
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+/// Represents an error loading the sources of a composition.
+#[derive(thiserror::Error, Diagnostic, Debug)]
+pub enum Error {
+    /// An included document could not be read.
+    #[error("failed to read included document `{path}`")]
+    #[diagnostic(code(wac::load::read_failed))]
+    ReadFailed {
+        /// The path of the included document.
+        path: String,
+        /// The span where the error occurred.
+        #[label(primary, "document included here")]
+        span: SourceSpan,
+        /// The underlying I/O error.
+        #[source]
+        source: std::io::Error,
+    },
+    /// A document includes itself, directly or indirectly.
+    #[error("document `{path}` includes itself")]
+    #[diagnostic(code(wac::load::include_cycle))]
+    IncludeCycle {
+        /// The path of the included document.
+        path: String,
+        /// The span where the error occurred.
+        #[label(primary, "cyclic include here")]
+        span: SourceSpan,
+    },
+}
+
+/// Represents a source file of a composition.
+#[derive(Debug, Clone)]
+pub struct SourceFile {
+    path: PathBuf,
+    canonical: Option<PathBuf>,
+    text: String,
+    offset: usize,
+}
+
+impl SourceFile {
+    /// Gets the path of the source file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Gets the text of the source file.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Gets the offset of the start of the source file.
+    ///
+    /// Spans in the source file are relative to this offset.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Determines if the given offset is in the source file.
+    pub fn contains(&self, offset: usize) -> bool {
+        offset >= self.offset && offset <= self.offset + self.text.len()
+    }
+}
+
+/// Represents the source files of a composition.
+///
+/// The first file is the root document of the composition; the remaining
+/// files are the documents it includes, directly or indirectly.
+///
+/// The sources implement [`SourceCode`] so that diagnostics for any of the
+/// files may be rendered.
+#[derive(Debug, Clone)]
+pub struct Sources {
+    files: Vec<SourceFile>,
+    /// The map of the offset of an include statement's path to the included file.
+    includes: HashMap<usize, usize>,
+    /// The order in which the files are parsed; included files come first.
+    order: Vec<usize>,
+}
+
+impl Sources {
+    /// Creates a new set of sources for the given root document.
+    ///
+    /// The documents included by the root document are not loaded;
+    /// use [`Sources::load_includes`] to load them.
+    pub fn new(path: impl Into<PathBuf>, text: impl Into<String>) -> Self {
+        let path = path.into();
+        Self {
+            files: vec![SourceFile {
+                canonical: fs::canonicalize(&path).ok(),
+                path,
+                text: text.into(),
+                offset: 0,
+            }],
+            includes: Default::default(),
+            order: vec![0],
+        }
+    }
+
+    /// Loads the documents included by the root document from disk.
+    ///
+    /// Included paths are relative to the directory of the including document.
+    /// A document included more than once is only loaded once.
+    ///
+    /// If an error is returned, the documents loaded before the error may
+    /// still be parsed.
+    pub fn load_includes(&mut self) -> Result<(), Error> {
+        self.files.truncate(1);
+        self.includes.clear();
+        self.order.clear();
+        let result = self.load(0, &mut vec![0]);
+
+        // Ensure the root document is parsed even if an include failed to load
+        if self.order.last() != Some(&0) {
+            self.order.push(0);
+        }
+
+        result
+    }
+
+    /// Gets the root source file.
+    pub fn root(&self) -> &SourceFile {
+        &self.files[0]
+    }
+
+    /// Gets the source files, starting with the root.
+    pub fn files(&self) -> impl ExactSizeIterator<Item = &SourceFile> {
+        self.files.iter()
+    }
+
+    /// Gets the source file containing the given offset.
+    pub fn file(&self, offset: usize) -> Option<&SourceFile> {
+        self.files.iter().find(|f| f.contains(offset))
+    }
+
+    /// Parses the root document and the documents it includes.
+    ///
+    /// Parsing stops at the first error.
+    pub fn parse(&self) -> crate::ast::ParseResult<Document<'_>> {
+        let (document, errors) = self.parse_recovering();
+        match errors.into_iter().next() {
+            Some(e) => Err(e),
+            None => Ok(document.expect("document should be present without errors")),
+        }
+    }
+
+    /// Parses the root document and the documents it includes, recovering
+    /// from errors.
+    ///
+    /// The `document` field of each include statement is set to the
+    /// included document.
+    ///
+    /// See [`Document::parse_recovering`] for more information.
+    pub fn parse_recovering(&self) -> (Option<Document<'_>>, Vec<crate::ast::Error>) {
+        let mut documents = HashMap::new();
+        let mut errors = Vec::new();
+        let mut root = None;
+
+        for &index in &self.order {
+            let file = &self.files[index];
+            let (document, e) = Document::parse_recovering_with_offset(&file.text, file.offset);
+            errors.extend(e);
+
+            let mut document = match document {
+                Some(document) => document,
+                None => continue,
+            };
+
+            for statement in &mut document.statements {
+                if let Statement::Include(i) = statement {
+                    i.document = self
+                        .includes
+                        .get(&i.path.span.offset())
+                        .and_then(|index| documents.get(index))
+                        .cloned();
+                }
+            }
+
+            if index == 0 {
+                root = Some(document);
+            } else {
+                documents.insert(index, Arc::new(document));
+            }
+        }
+
+        (root, errors)
+    }
+
+    fn load(&mut self, index: usize, stack: &mut Vec<usize>) -> Result<(), Error> {
+        // Parse the document only to find its include statements; any parse
+        // errors are reported when the sources are parsed
+        let includes: Vec<(String, SourceSpan)> = {
+            let file = &self.files[index];
+            Document::parse_recovering_with_offset(&file.text, file.offset)
+                .0
+                .map(|document| {
+                    document
+                        .statements
+                        .iter()
+                        .filter_map(|s| match s {
+                            Statement::Include(i) => Some((i.path.value.to_owned(), i.path.span)),
+                            _ => None,
+                        })
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+
+        let dir = self.files[index]
+            .path
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_default();
+
+        for (path, span) in includes {
+            let full = dir.join(&path);
+            let canonical = fs::canonicalize(&full).map_err(|e| Error::ReadFailed {
+                path: path.clone(),
+                span,
+                source: e,
+            })?;
+
+            if let Some(existing) = self
+                .files
+                .iter()
+                .position(|f| f.canonical.as_ref() == Some(&canonical))
+            {
+                if stack.contains(&existing) {
+                    return Err(Error::IncludeCycle { path, span });
+                }
+
+                self.includes.insert(span.offset(), existing);
+                continue;
+            }
+
+            log::debug!("loading included document `{path}`", path = full.display());
+            let text = fs::read_to_string(&full).map_err(|e| Error::ReadFailed {
+                path: path.clone(),
+                span,
+                source: e,
+            })?;
+
+            // Leave a gap between files so that the end of one file is
+            // distinct from the start of the next
+            let last = self.files.last().unwrap();
+            let offset = last.offset + last.text.len() + 1;
+            let included = self.files.len();
+            self.files.push(SourceFile {
+                path: full,
+                canonical: Some(canonical),
+                text,
+                offset,
+            });
+            self.includes.insert(span.offset(), included);
+
+            stack.push(included);
+            self.load(included, stack)?;
+            stack.pop();
+        }
+
+        self.order.push(index);
+        Ok(())
+    }
+}
+
+impl SourceCode for Sources {
+    fn read_span<'a>(
+        &'a self,
+        span: &SourceSpan,
+        context_lines_before: usize,
+        context_lines_after: usize,
+    ) -> Result<Box<dyn SpanContents<'a> + 'a>, MietteError> {
+        let file = self.file(span.offset()).ok_or(MietteError::OutOfBounds)?;
+        let local = SourceSpan::new((span.offset() - file.offset).into(), span.len().into());
+        let contents =
+            file.text
+                .as_str()
+                .read_span(&local, context_lines_before, context_lines_after)?;
+
+        // Translate the span of the contents back to the global offsets
+        Ok(Box::new(MietteSpanContents::new_named(
+            file.path.display().to_string(),
+            contents.data(),
+            SourceSpan::new(
+                (contents.span().offset() + file.offset).into(),
+                contents.span().len().into(),
+            ),
+            contents.line(),
+            contents.column(),
+            contents.line_count(),
+        )))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Composition, Error as ResolutionError, Lint, LintLevels};
+    use pretty_assertions::assert_eq;
+    use tempdir::TempDir;
+
+    fn write(dir: &Path, name: &str, contents: &str) -> PathBuf {
+        let path = dir.join(name);
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn it_resolves_included_documents() {
+        let temp = TempDir::new("wac-parser-sources-include").unwrap();
+        let dir = temp.path();
+        write(
+            dir,
+            "types.wac",
+            "package test:types;\n\ninterface i {\n    type t = u32;\n}\n",
+        );
+        write(
+            dir,
+            "common.wac",
+            "package test:common;\n\ninclude \"types.wac\";\n\nimport x: i;\n",
+        );
+        let root = write(
+            dir,
+            "root.wac",
+            "package test:root;\n\ninclude \"common.wac\";\ninclude \"types.wac\";\n\nexport x;\n",
+        );
+
+        let mut sources = Sources::new(&root, fs::read_to_string(&root).unwrap());
+        sources.load_includes().unwrap();
+        assert_eq!(sources.files().len(), 3);
+
+        let document = sources.parse().unwrap();
+        let composition = Composition::from_ast(&document, Default::default()).unwrap();
+        assert_eq!(composition.exports.keys().collect::<Vec<_>>(), vec!["x"],);
+
+        // The span of the import should be in the included file
+        let span = composition.names["x"].1;
+        let file = sources.file(span.offset()).unwrap();
+        assert_eq!(file.path(), dir.join("common.wac"));
+        assert_eq!(
+            &file.text()[span.offset() - file.offset()..][..span.len()],
+            "x"
+        );
+    }
+
+    #[test]
+    fn it_reports_include_errors() {
+        let temp = TempDir::new("wac-parser-sources-errors").unwrap();
+        let dir = temp.path();
+        write(dir, "a.wac", "package test:a;\n\ninclude \"b.wac\";\n");
+        write(dir, "b.wac", "package test:b;\n\ninclude \"a.wac\";\n");
+        let root = dir.join("a.wac");
+
+        let mut sources = Sources::new(&root, fs::read_to_string(&root).unwrap());
+        let e = sources.load_includes().unwrap_err();
+        assert_eq!(e.to_string(), "document `a.wac` includes itself");
+
+        let mut sources = Sources::new(&root, "package test:a;\n\ninclude \"missing.wac\";\n");
+        let e = sources.load_includes().unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "failed to read included document `missing.wac`"
+        );
+
+        let sources = Sources::new(&root, "package test:a;\n\ninclude \"b.wac\";\n");
+        let document = sources.parse().unwrap();
+        let e = Composition::from_ast(&document, Default::default()).unwrap_err();
+        assert!(matches!(e, ResolutionError::IncludeNotLoaded { .. }));
+
+        write(
+            dir,
+            "c.wac",
+            "package test:c;\n\nimport x: func();\nexport x;\n",
+        );
+        let mut sources = Sources::new(&root, "package test:a;\n\ninclude \"c.wac\";\n");
+        sources.load_includes().unwrap();
+        let document = sources.parse().unwrap();
+        let e = Composition::from_ast(&document, Default::default()).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "export statements are not allowed in included documents"
+        );
+    }
+
+    #[test]
+    fn it_applies_lint_directives_per_file() {
+        let temp = TempDir::new("wac-parser-sources-lints").unwrap();
+        let dir = temp.path();
+        write(
+            dir,
+            "common.wac",
+            "package test:common;\n\ninterface i {\n    type t = u32;\n}\n\n// wac: allow(redundant-rename)\ninterface j {\n    use i.{t as t};\n}\n\ninterface k {\n    use i.{t as t};\n}\n",
+        );
+        let root = write(
+            dir,
+            "root.wac",
+            "package test:root;\n\ninclude \"common.wac\";\n",
+        );
+
+        let mut sources = Sources::new(&root, fs::read_to_string(&root).unwrap());
+        sources.load_includes().unwrap();
+        let document = sources.parse().unwrap();
+        let composition = Composition::from_ast(&document, Default::default()).unwrap();
+        assert_eq!(composition.warnings.len(), 2);
+
+        // The directive in the included file is not seen from the root document
+        let levels = LintLevels::default();
+        assert_eq!(
+            levels
+                .apply(sources.root().text(), &composition.warnings)
+                .len(),
+            2
+        );
+
+        let warnings = levels.apply_sources(&sources, &composition.warnings);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].lint(), Lint::RedundantRename);
+
+        let file = sources.file(warnings[0].span().offset()).unwrap();
+        assert_eq!(file.path(), dir.join("common.wac"));
+        assert!(
+            warnings[0].span().offset() - file.offset() > file.text().find("interface k").unwrap()
+        );
+    }
+}
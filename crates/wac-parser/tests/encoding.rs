@@ -11,7 +11,7 @@ use std::{
     sync::atomic::{AtomicUsize, Ordering},
 };
 use support::fmt_err;
-use wac_parser::{ast::Document, Composition, EncodingOptions};
+use wac_parser::{ast::Document, Composition, DecodedDocument, EncodingOptions, Item};
 use wac_resolver::{packages, FileSystemPackageResolver};
 
 mod support;
@@ -72,6 +72,86 @@ fn compare_result(test: &Path, result: &str) -> Result<()> {
     Ok(())
 }
 
+fn validate(bytes: &[u8]) -> Result<()> {
+    wasmparser::Validator::new_with_features(wasmparser::WasmFeatures {
+        component_model: true,
+        ..Default::default()
+    })
+    .validate_all(bytes)?;
+    Ok(())
+}
+
+/// Gets the names of the instantiated packages of a composition, including
+/// any version of the package.
+fn packages_of(composition: &Composition) -> Vec<String> {
+    composition
+        .items
+        .iter()
+        .filter_map(|(_, item)| match item {
+            Item::Instantiation(instantiation) => {
+                let package = &composition.packages[instantiation.package];
+                Some(match &package.version {
+                    Some(version) => format!("{name}@{version}", name = package.name),
+                    None => package.name.clone(),
+                })
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Decodes an encoded composition and checks that the decoded document
+/// parses, resolves, and encodes to a component that decodes to the same
+/// document.
+fn roundtrip(
+    test: &Path,
+    package: &str,
+    composition: &Composition,
+    resolver: &FileSystemPackageResolver,
+    bytes: &[u8],
+) -> Result<()> {
+    let decoded = DecodedDocument::decode(package, bytes).with_context(|| {
+        format!(
+            "failed to decode the encoded composition `{path}`",
+            path = test.display()
+        )
+    })?;
+
+    let source = decoded.source();
+
+    // The decoded composition should instantiate the same versions of its packages
+    for package in packages_of(composition) {
+        if !source.contains(&format!("new {package} ")) {
+            bail!("decoded composition does not instantiate `{package}`:\n{source}");
+        }
+    }
+
+    let document = decoded
+        .document()
+        .map_err(|e| anyhow!(fmt_err(e, test, source)))
+        .context("failed to parse the decoded composition")?;
+
+    let packages = resolver.resolve(&packages(&document)?)?;
+    let bytes = Composition::from_ast(&document, packages)
+        .map_err(|e| anyhow!(fmt_err(e, test, source)))
+        .context("failed to resolve the decoded composition")?
+        .encode(EncodingOptions::default())
+        .context("failed to encode the decoded composition")?;
+
+    validate(&bytes).context("failed to validate the encoded decoded composition")?;
+
+    let redecoded = DecodedDocument::decode(package, &bytes)
+        .context("failed to decode the encoded decoded composition")?;
+    if redecoded.source() != source {
+        bail!(
+            "decoded composition does not roundtrip:\n{}",
+            StrComparison::new(source, redecoded.source()),
+        );
+    }
+
+    Ok(())
+}
+
 fn run_test(test: &Path, ntests: &AtomicUsize) -> Result<()> {
     let source = std::fs::read_to_string(test)?.replace("\r\n", "\n");
 
@@ -85,8 +165,10 @@ fn run_test(test: &Path, ntests: &AtomicUsize) -> Result<()> {
 
     let packages = resolver.resolve(&packages(&document)?)?;
 
-    let bytes = Composition::from_ast(&document, packages)
-        .map_err(|e| anyhow!(fmt_err(e, test, &source)))?
+    let composition = Composition::from_ast(&document, packages)
+        .map_err(|e| anyhow!(fmt_err(e, test, &source)))?;
+
+    let bytes = composition
         .encode(EncodingOptions::default())
         .with_context(|| {
             format!(
@@ -95,18 +177,21 @@ fn run_test(test: &Path, ntests: &AtomicUsize) -> Result<()> {
             )
         })?;
 
-    wasmparser::Validator::new_with_features(wasmparser::WasmFeatures {
-        component_model: true,
-        ..Default::default()
-    })
-    .validate_all(&bytes)
-    .with_context(|| {
+    validate(&bytes).with_context(|| {
         format!(
             "failed to validate the encoded composition `{path}`",
             path = test.display()
         )
     })?;
 
+    roundtrip(
+        test,
+        document.directive.package.name,
+        &composition,
+        &resolver,
+        &bytes,
+    )?;
+
     let result = wasmprinter::print_bytes(bytes).with_context(|| {
         format!(
             "failed to convert binary wasm output to text `{path}`",
@@ -0,0 +1,4 @@
+(component
+  (import "f" (func))
+  (export "g" (func 0))
+)
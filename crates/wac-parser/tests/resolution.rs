@@ -11,7 +11,7 @@ use std::{
     sync::atomic::{AtomicUsize, Ordering},
 };
 use support::fmt_err;
-use wac_parser::{ast::Document, Composition};
+use wac_parser::{ast::Document, Composition, LintLevels};
 use wac_resolver::{packages, FileSystemPackageResolver};
 
 mod support;
@@ -20,6 +20,8 @@ fn find_tests() -> Vec<PathBuf> {
     let mut tests = Vec::new();
     find_tests("tests/resolution", &mut tests);
     find_tests("tests/resolution/fail", &mut tests);
+    find_tests("tests/resolution/fail/recovering", &mut tests);
+    find_tests("tests/resolution/lints", &mut tests);
     tests.sort();
     return tests;
 
@@ -40,9 +42,9 @@ fn find_tests() -> Vec<PathBuf> {
     }
 }
 
-fn normalize(s: &str, should_fail: bool) -> String {
-    if should_fail {
-        // Normalize paths in any error messages
+fn normalize(s: &str, diagnostics: bool) -> String {
+    if diagnostics {
+        // Normalize paths in any diagnostic messages
         return s.replace('\\', "/").replace("\r\n", "\n");
     }
 
@@ -50,10 +52,10 @@ fn normalize(s: &str, should_fail: bool) -> String {
     s.replace("\r\n", "\n")
 }
 
-fn compare_result(test: &Path, result: &str, should_fail: bool) -> Result<()> {
+fn compare_result(test: &Path, result: &str, diagnostics: bool) -> Result<()> {
     let path = test.with_extension("wac.result");
 
-    let result = normalize(result, should_fail);
+    let result = normalize(result, diagnostics);
     if env::var_os("BLESS").is_some() {
         fs::write(&path, &result).with_context(|| {
             format!(
@@ -79,7 +81,12 @@ fn compare_result(test: &Path, result: &str, should_fail: bool) -> Result<()> {
 }
 
 fn run_test(test: &Path, ntests: &AtomicUsize) -> Result<()> {
-    let should_fail = test.parent().map(|p| p.ends_with("fail")).unwrap_or(false);
+    let recovering = test
+        .parent()
+        .map(|p| p.ends_with("recovering"))
+        .unwrap_or(false);
+    let should_fail = recovering || test.parent().map(|p| p.ends_with("fail")).unwrap_or(false);
+    let lints = test.parent().map(|p| p.ends_with("lints")).unwrap_or(false);
     let source = std::fs::read_to_string(test)?.replace("\r\n", "\n");
 
     let document = Document::parse(&source).map_err(|e| anyhow!(fmt_err(e, test, &source)))?;
@@ -95,7 +102,31 @@ fn run_test(test: &Path, ntests: &AtomicUsize) -> Result<()> {
             .resolve(&packages(&document).map_err(|e| fmt_err(e, test, &source))?)
             .map_err(|e| fmt_err(e, test, &source))?;
 
-        Composition::from_ast(&document, packages).map_err(|e| fmt_err(e, test, &source))
+        if !recovering {
+            return Composition::from_ast(&document, packages)
+                .map_err(|e| fmt_err(e, test, &source));
+        }
+
+        // Recovering tests expect every error; resolution that does not
+        // recover should fail with the first of them
+        let first = Composition::from_ast(&document, packages.clone())
+            .err()
+            .map(|e| fmt_err(e, test, &source));
+        let errors = match Composition::from_ast_recovering(&document, packages) {
+            Ok(doc) => return Ok(doc),
+            Err(errors) => errors
+                .into_iter()
+                .map(|e| fmt_err(e, test, &source))
+                .collect::<Vec<_>>(),
+        };
+
+        assert_eq!(
+            first.as_ref(),
+            errors.first(),
+            "the first error of the recovering resolution should be the error of the resolution"
+        );
+
+        Err(errors.join("\n"))
     };
 
     let result = match resolve() {
@@ -104,7 +135,21 @@ fn run_test(test: &Path, ntests: &AtomicUsize) -> Result<()> {
                 bail!("the resolution was successful but it was expected to fail");
             }
 
-            serde_json::to_string_pretty(&doc)?
+            if lints {
+                // Lint tests expect the warnings rather than the composition
+                let warnings = LintLevels::default().apply(&source, &doc.warnings);
+                if warnings.is_empty() {
+                    bail!("the resolution reported no warnings but warnings were expected");
+                }
+
+                warnings
+                    .into_iter()
+                    .map(|w| fmt_err(w, test, &source))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            } else {
+                serde_json::to_string_pretty(&doc)?
+            }
         }
         Err(e) => {
             if !should_fail {
@@ -116,7 +161,7 @@ fn run_test(test: &Path, ntests: &AtomicUsize) -> Result<()> {
         }
     };
 
-    compare_result(test, &result, should_fail)?;
+    compare_result(test, &result, should_fail || lints)?;
 
     ntests.fetch_add(1, Ordering::SeqCst);
     Ok(())
@@ -0,0 +1,5 @@
+(component
+  (instance (import "x")
+    (export "f" (func (param "x" u32)))
+  )
+)
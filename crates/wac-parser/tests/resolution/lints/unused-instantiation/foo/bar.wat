@@ -0,0 +1 @@
+(component)
@@ -0,0 +1,76 @@
+use super::{Error, PackageResolver};
+use async_trait::async_trait;
+use indexmap::IndexMap;
+use miette::SourceSpan;
+use wac_parser::{PackageKey, ResolvedPackage};
+
+/// Used to resolve packages with a sequence of resolvers.
+///
+/// Each resolver is given only the packages that the resolvers before it
+/// did not resolve; packages that no resolver resolves are omitted from
+/// the returned map.
+///
+/// An error from any resolver stops the resolution.
+#[derive(Default)]
+pub struct ChainResolver {
+    resolvers: Vec<Box<dyn PackageResolver>>,
+}
+
+impl ChainResolver {
+    /// Creates a new, empty chain resolver.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends a resolver to the end of the chain.
+    pub fn with(mut self, resolver: impl PackageResolver + 'static) -> Self {
+        self.push(resolver);
+        self
+    }
+
+    /// Appends a resolver to the end of the chain.
+    pub fn push(&mut self, resolver: impl PackageResolver + 'static) {
+        self.resolvers.push(Box::new(resolver));
+    }
+
+    /// Inserts a resolver at the given position in the chain.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is greater than the number of resolvers in the chain.
+    pub fn insert(&mut self, index: usize, resolver: impl PackageResolver + 'static) {
+        self.resolvers.insert(index, Box::new(resolver));
+    }
+
+    /// Gets the number of resolvers in the chain.
+    pub fn len(&self) -> usize {
+        self.resolvers.len()
+    }
+
+    /// Determines if the chain has no resolvers.
+    pub fn is_empty(&self) -> bool {
+        self.resolvers.is_empty()
+    }
+}
+
+#[async_trait]
+impl PackageResolver for ChainResolver {
+    async fn resolve<'a>(
+        &self,
+        keys: &IndexMap<PackageKey<'a>, SourceSpan>,
+    ) -> Result<IndexMap<PackageKey<'a>, ResolvedPackage>, Error> {
+        let mut remaining = keys.clone();
+        let mut packages = IndexMap::new();
+        for resolver in &self.resolvers {
+            if remaining.is_empty() {
+                break;
+            }
+
+            let resolved = resolver.resolve(&remaining).await?;
+            remaining.retain(|key, _| !resolved.contains_key(key));
+            packages.extend(resolved);
+        }
+
+        Ok(packages)
+    }
+}
@@ -1,17 +1,51 @@
-use super::Error;
+use super::{CompositionDiagnostic, Error, PackageResolver};
 use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
 use indexmap::IndexMap;
 use miette::SourceSpan;
-use std::{collections::HashMap, fs, path::PathBuf, sync::Arc};
-use wac_parser::PackageKey;
+use semver::{Version, VersionReq};
+use std::{
+    collections::HashMap,
+    ffi::OsStr,
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+use wac_parser::{sources::Sources, Composition, EncodingOptions, PackageKey, ResolvedPackage};
 
 /// Used to resolve packages from the file system.
+///
+/// A package may be a binary component, a WAT file (with the `wat` feature),
+/// a directory or `.wit` file containing a WIT package (with the `wit`
+/// feature), or a WAC composition; compositions are resolved with the same
+/// resolver and encoded with their packages defined.
+///
+/// The packages of a composition and the foreign dependencies of a WIT
+/// package that are not in its own `deps` directory are resolved with the
+/// same resolver; any that are not found may be resolved with a dependency
+/// resolver, such as a registry resolver.
+///
+/// A package referenced without an exact version may be stored as several
+/// versions in a `<root>/<namespace>/<name>` directory, in which case the
+/// highest version matching any version requirement is resolved and reported
+/// as the version of the package.
+#[derive(Clone)]
 pub struct FileSystemPackageResolver {
     root: PathBuf,
     overrides: HashMap<String, PathBuf>,
     error_on_unknown: bool,
+    dependencies: Option<Arc<dyn PackageResolver>>,
 }
 
+/// The packages resolved for the packages of compositions and the foreign
+/// dependencies of WIT packages that are not in the root directory, keyed by
+/// the string representation of the package key.
+type Dependencies = HashMap<String, ResolvedPackage>;
+
+/// An owned package key: the name, version, and version requirement of a
+/// package.
+pub type OwnedPackageKey = (String, Option<Version>, Option<VersionReq>);
+
 impl FileSystemPackageResolver {
     /// Creates a new file system resolver with the given root directory.
     pub fn new(
@@ -23,17 +57,269 @@ impl FileSystemPackageResolver {
             root: root.into(),
             overrides,
             error_on_unknown,
+            dependencies: None,
+        }
+    }
+
+    /// Uses the given resolver for the packages of compositions and the
+    /// foreign dependencies of WIT packages that are not found in the root
+    /// directory.
+    ///
+    /// The dependency resolver is only used when resolving packages through
+    /// the [`PackageResolver`] trait.
+    pub fn with_dependency_resolver(mut self, resolver: impl PackageResolver + 'static) -> Self {
+        self.dependencies = Some(Arc::new(resolver));
+        self
+    }
+
+    /// Gets the path of the binary package with the given key.
+    ///
+    /// The path is `<root>/<namespace>/<name>.wasm`, or
+    /// `<root>/<namespace>/<name>/<version>.wasm` for a versioned key.
+    pub fn package_path(&self, key: &PackageKey) -> PathBuf {
+        with_extension(&self.base_path(key), "wasm")
+    }
+
+    /// Gets the path that the package with the given key is resolved from.
+    ///
+    /// This is either the local path override of the package or its path
+    /// in the root directory; the path may not exist.
+    ///
+    /// For a package without a version that is stored as several versions,
+    /// this is the directory containing the versions.
+    pub fn path(&self, key: &PackageKey) -> PathBuf {
+        match self.overrides.get(key.name) {
+            Some(path) if key.version.is_none() => path.clone(),
+            None if key.version.is_none() && !self.versions(key.name).is_empty() => {
+                self.base_path(key)
+            }
+            _ => self
+                .find_path(key)
+                .map(|(path, _)| path)
+                .unwrap_or_else(|| self.base_path(key)),
         }
     }
 
+    /// Gets the local paths that the package with the given key is resolved
+    /// from, including the paths it depends on.
+    ///
+    /// In addition to [`FileSystemPackageResolver::path`], these are the
+    /// documents included by a composition and the paths of the packages of
+    /// a composition and of the foreign dependencies of a WIT package that
+    /// are in the root directory, transitively.
+    pub fn paths(&self, key: &PackageKey) -> Vec<PathBuf> {
+        let mut paths = vec![self.path(key)];
+        if let Some(path) = self.find_dependency(key) {
+            let mut visited = Vec::new();
+            self.find_missing_packages(
+                &path,
+                SourceSpan::new(0.into(), 0.into()),
+                false,
+                &mut visited,
+                &mut IndexMap::new(),
+            );
+
+            for path in visited {
+                if !paths.contains(&path) {
+                    paths.push(path);
+                }
+            }
+        }
+
+        paths
+    }
+
+    /// Finds the binary packages used by the packages with the given keys.
+    ///
+    /// A package that is a composition or a WIT package in the root directory
+    /// is replaced by the packages of the composition or the foreign
+    /// dependencies of the WIT package, transitively; every other package is
+    /// included whether or not it is found in the root directory.
+    ///
+    /// Packages with local path overrides are not included.
+    pub fn binary_packages(
+        &self,
+        keys: &IndexMap<PackageKey, SourceSpan>,
+    ) -> IndexMap<OwnedPackageKey, SourceSpan> {
+        let mut visited = Vec::new();
+        let mut packages = IndexMap::new();
+        for (key, span) in keys {
+            self.find_missing_package(key, *span, true, &mut visited, &mut packages);
+        }
+
+        packages
+    }
+
+    /// Gets the versions of the package with the given name in the root
+    /// directory, in ascending order.
+    ///
+    /// The versions are the names of the `<version>.wasm` and
+    /// `<version>.wac` files, `<version>.wat` files (with the `wat` feature),
+    /// and `<version>.wit` files and `<version>` WIT package directories
+    /// (with the `wit` feature) in the `<root>/<namespace>/<name>` directory.
+    pub fn versions(&self, name: &str) -> Vec<Version> {
+        let dir = self.base_path(&PackageKey {
+            name,
+            version: None,
+            requirement: None,
+        });
+
+        let mut versions: Vec<Version> = fs::read_dir(dir)
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| {
+                let path = entry.ok()?.path();
+                let version = if path.is_dir() {
+                    if !cfg!(feature = "wit") {
+                        return None;
+                    }
+
+                    path.file_name()
+                } else {
+                    match path.extension().and_then(OsStr::to_str) {
+                        Some("wasm" | "wac") => path.file_stem(),
+                        #[cfg(feature = "wat")]
+                        Some("wat") => path.file_stem(),
+                        #[cfg(feature = "wit")]
+                        Some("wit") => path.file_stem(),
+                        _ => return None,
+                    }
+                };
+
+                version?.to_str()?.parse().ok()
+            })
+            .collect();
+
+        versions.sort();
+        versions.dedup();
+        versions
+    }
+
+    /// Finds the highest version of the package with the given name in the
+    /// root directory that matches the given requirement.
+    pub fn find_version(&self, name: &str, req: &VersionReq) -> Option<Version> {
+        self.versions(name)
+            .into_iter()
+            .rev()
+            .find(|v| req.matches(v))
+    }
+
+    /// Finds the path of the package with the given key in the root directory.
+    ///
+    /// If the key has no version and the package is stored as several
+    /// versions, the path of the highest version matching the key's version
+    /// requirement is returned along with the version; without a requirement,
+    /// release versions are preferred over pre-release versions.
+    ///
+    /// Returns `None` if the package is stored as several versions but none
+    /// of them match the key's version requirement.
+    fn find_path(&self, key: &PackageKey) -> Option<(PathBuf, Option<Version>)> {
+        if key.version.is_none() {
+            let versions = self.versions(key.name);
+            let version = match key.requirement {
+                Some(req) => versions.iter().rev().find(|v| req.matches(v)),
+                None => versions
+                    .iter()
+                    .rev()
+                    .find(|v| v.pre.is_empty())
+                    .or(versions.last()),
+            };
+
+            if let Some(version) = version {
+                log::debug!(
+                    "discovered version {version} of package `{name}`",
+                    name = key.name
+                );
+
+                let path = self.find_exact_path(&PackageKey {
+                    name: key.name,
+                    version: Some(version),
+                    requirement: None,
+                });
+                return Some((path, Some(version.clone())));
+            }
+
+            if !versions.is_empty() {
+                return None;
+            }
+        }
+
+        Some((self.find_exact_path(key), None))
+    }
+
+    /// Finds the path of the package with exactly the given key in the root
+    /// directory.
+    fn find_exact_path(&self, key: &PackageKey) -> PathBuf {
+        let path = self.base_path(key);
+
+        // If the path is not a directory, use a `.wasm`, `.wat`, `.wit`, or
+        // `.wac` extension
+        if path.is_dir() {
+            return path;
+        }
+
+        #[cfg(feature = "wat")]
+        {
+            let wat = with_extension(&path, "wat");
+            if wat.exists() {
+                return wat;
+            }
+        }
+
+        #[cfg(feature = "wit")]
+        {
+            let wit = with_extension(&path, "wit");
+            if wit.exists() {
+                return wit;
+            }
+        }
+
+        let wac = with_extension(&path, "wac");
+        if wac.exists() {
+            return wac;
+        }
+
+        with_extension(&path, "wasm")
+    }
+
+    fn base_path(&self, key: &PackageKey) -> PathBuf {
+        let mut path = self.root.clone();
+        for segment in key.name.split(':') {
+            path.push(segment);
+        }
+
+        if let Some(version) = key.version {
+            path.push(version.to_string());
+        }
+
+        path
+    }
+
     /// Resolves the provided package keys to packages.
     pub fn resolve<'a>(
         &self,
         keys: &IndexMap<PackageKey<'a>, SourceSpan>,
-    ) -> Result<IndexMap<PackageKey<'a>, Arc<Vec<u8>>>, Error> {
+    ) -> Result<IndexMap<PackageKey<'a>, ResolvedPackage>, Error> {
+        self.resolve_with_stack(keys, &Default::default(), &mut Vec::new())
+    }
+
+    /// Resolves the provided package keys to packages.
+    ///
+    /// The dependencies are the packages already resolved for the packages
+    /// of compositions and the foreign dependencies of WIT packages that are
+    /// not in the root directory.
+    ///
+    /// The stack contains the paths of the compositions and WIT packages
+    /// currently being resolved and is used to detect cyclic dependencies.
+    fn resolve_with_stack<'a>(
+        &self,
+        keys: &IndexMap<PackageKey<'a>, SourceSpan>,
+        dependencies: &Dependencies,
+        stack: &mut Vec<PathBuf>,
+    ) -> Result<IndexMap<PackageKey<'a>, ResolvedPackage>, Error> {
         let mut packages = IndexMap::new();
         for (key, span) in keys.iter() {
-            let path = match self.overrides.get(key.name) {
+            let (path, version) = match self.overrides.get(key.name) {
                 Some(path) if key.version.is_none() => {
                     if !path.is_file() {
                         return Err(Error::PackageResolutionFailure {
@@ -47,72 +333,42 @@ impl FileSystemPackageResolver {
                         });
                     }
 
-                    path.clone()
+                    (path.clone(), None)
                 }
-                _ => {
-                    let mut path = self.root.clone();
-                    for segment in key.name.split(':') {
-                        path.push(segment);
-                    }
-
-                    if let Some(version) = key.version {
-                        path.push(version.to_string());
-                    }
-
-                    // If the path is not a directory, use a `.wasm` or `.wat` extension
-                    if !path.is_dir() {
-                        path.set_extension("wasm");
-
-                        #[cfg(feature = "wat")]
-                        {
-                            path.set_extension("wat");
-                            if !path.exists() {
-                                path.set_extension("wasm");
-                            }
+                _ => match self.find_path(key) {
+                    Some(found) => found,
+                    None => {
+                        log::debug!("no version of package `{key}` exists in the root directory");
+                        if self.error_on_unknown {
+                            return Err(Error::NoMatchingPackageVersion {
+                                name: key.name.to_string(),
+                                requirement: key.requirement.cloned().unwrap_or_default(),
+                                span: *span,
+                            });
                         }
+                        continue;
                     }
+                },
+            };
 
-                    path
-                }
+            // Include any discovered version in diagnostics
+            let name = match &version {
+                Some(version) => format!("{name}@{version}", name = key.name),
+                None => key.name.to_string(),
+            };
+            let resolved = |bytes| ResolvedPackage {
+                version: version.clone(),
+                bytes: Arc::new(bytes),
             };
 
-            // First check to see if a directory exists.
+            // First check to see if a directory or `.wit` file exists.
             // If so, then treat it as a textual WIT package.
             #[cfg(feature = "wit")]
-            if path.is_dir() {
-                log::debug!(
-                    "loading WIT package from directory `{path}`",
-                    path = path.display()
-                );
-
-                let mut resolve = wit_parser::Resolve::new();
-                let (pkg, _) =
-                    resolve
-                        .push_dir(&path)
-                        .map_err(|e| Error::PackageResolutionFailure {
-                            name: key.name.to_string(),
-                            span: *span,
-                            source: e,
-                        })?;
-
+            if is_wit(&path) {
                 packages.insert(
                     *key,
-                    Arc::new(
-                        wit_component::encode(Some(true), &resolve, pkg)
-                            .with_context(|| {
-                                format!(
-                                    "failed to encode WIT package from directory `{path}`",
-                                    path = path.display()
-                                )
-                            })
-                            .map_err(|e| Error::PackageResolutionFailure {
-                                name: key.name.to_string(),
-                                span: *span,
-                                source: e,
-                            })?,
-                    ),
+                    resolved(self.encode_wit(&name, *span, &path, dependencies, stack)?),
                 );
-
                 continue;
             }
 
@@ -123,13 +379,21 @@ impl FileSystemPackageResolver {
                 );
                 if self.error_on_unknown {
                     return Err(Error::UnknownPackage {
-                        name: key.name.to_string(),
+                        name: name.clone(),
                         span: *span,
                     });
                 }
                 continue;
             }
 
+            if path.extension().and_then(OsStr::to_str) == Some("wac") {
+                packages.insert(
+                    *key,
+                    resolved(self.compose(&name, *span, &path, dependencies, stack)?),
+                );
+                continue;
+            }
+
             log::debug!(
                 "loading package `{key}` from `{path}`",
                 path = path.display()
@@ -137,35 +401,480 @@ impl FileSystemPackageResolver {
             let bytes = fs::read(&path)
                 .with_context(|| format!("failed to read package `{path}`", path = path.display()))
                 .map_err(|e| Error::PackageResolutionFailure {
-                    name: key.name.to_string(),
+                    name: name.clone(),
                     span: *span,
                     source: e,
                 })?;
 
             #[cfg(feature = "wat")]
-            if path.extension().and_then(std::ffi::OsStr::to_str) == Some("wat") {
+            if path.extension().and_then(OsStr::to_str) == Some("wat") {
                 let bytes = match wat::parse_bytes(&bytes) {
                     Ok(std::borrow::Cow::Borrowed(_)) => bytes,
                     Ok(std::borrow::Cow::Owned(wat)) => wat,
                     Err(mut e) => {
                         e.set_path(path);
                         return Err(Error::PackageResolutionFailure {
-                            name: key.name.to_string(),
+                            name: name.clone(),
                             span: *span,
                             source: anyhow!(e),
                         });
                     }
                 };
 
-                packages.insert(*key, Arc::new(bytes));
+                packages.insert(*key, resolved(bytes));
                 continue;
             }
 
-            packages.insert(*key, Arc::new(bytes));
+            packages.insert(*key, resolved(bytes));
         }
 
         Ok(packages)
     }
+
+    /// Resolves and encodes the composition at the given path.
+    fn compose(
+        &self,
+        name: &str,
+        span: SourceSpan,
+        path: &Path,
+        dependencies: &Dependencies,
+        stack: &mut Vec<PathBuf>,
+    ) -> Result<Vec<u8>, Error> {
+        let canonical = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        if stack.contains(&canonical) {
+            return Err(Error::CompositionCycle {
+                name: name.to_string(),
+                span,
+            });
+        }
+
+        log::debug!(
+            "composing package `{name}` from `{path}`",
+            path = path.display()
+        );
+
+        let failure = |errors| Error::CompositionFailure {
+            name: name.to_string(),
+            path: path.to_path_buf(),
+            span,
+            errors,
+        };
+
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("failed to read composition `{path}`", path = path.display()))
+            .map_err(|e| Error::PackageResolutionFailure {
+                name: name.to_string(),
+                span,
+                source: e,
+            })?;
+
+        let mut sources = Sources::new(path, contents);
+        if let Err(e) = sources.load_includes() {
+            return Err(failure(vec![CompositionDiagnostic::new(e, &sources)]));
+        }
+
+        let document = sources
+            .parse()
+            .map_err(|e| failure(vec![CompositionDiagnostic::new(e, &sources)]))?;
+
+        let keys = crate::packages(&document)
+            .map_err(|e| failure(vec![CompositionDiagnostic::new(e, &sources)]))?;
+
+        // Packages that are not in the root directory were already resolved
+        // by the dependency resolver
+        let (resolved, local): (IndexMap<_, _>, IndexMap<_, _>) = keys
+            .into_iter()
+            .partition(|(key, _)| dependencies.contains_key(&key.to_string()));
+
+        stack.push(canonical);
+        let packages = self.resolve_with_stack(&local, dependencies, stack);
+        stack.pop();
+
+        let mut packages =
+            packages.map_err(|e| failure(vec![CompositionDiagnostic::new(e, &sources)]))?;
+        packages.extend(
+            resolved
+                .into_keys()
+                .map(|key| (key, dependencies[&key.to_string()].clone())),
+        );
+
+        let composition =
+            Composition::from_ast_recovering(&document, packages).map_err(|errors| {
+                failure(
+                    errors
+                        .into_iter()
+                        .map(|e| CompositionDiagnostic::new(e, &sources))
+                        .collect(),
+                )
+            })?;
+
+        // The packages of the composition are defined so that it is self-contained
+        composition
+            .encode(EncodingOptions {
+                define_packages: true,
+            })
+            .with_context(|| {
+                format!(
+                    "failed to encode composition `{path}`",
+                    path = path.display()
+                )
+            })
+            .map_err(|e| Error::PackageResolutionFailure {
+                name: name.to_string(),
+                span,
+                source: e,
+            })
+    }
+
+    /// Resolves the foreign dependencies of the WIT package at the given path
+    /// and encodes the package.
+    #[cfg(feature = "wit")]
+    fn encode_wit(
+        &self,
+        name: &str,
+        span: SourceSpan,
+        path: &Path,
+        dependencies: &Dependencies,
+        stack: &mut Vec<PathBuf>,
+    ) -> Result<Vec<u8>, Error> {
+        let canonical = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        if stack.contains(&canonical) {
+            return Err(Error::CompositionCycle {
+                name: name.to_string(),
+                span,
+            });
+        }
+
+        log::debug!(
+            "loading WIT package `{name}` from `{path}`",
+            path = path.display()
+        );
+
+        let failure = |e| Error::PackageResolutionFailure {
+            name: name.to_string(),
+            span,
+            source: e,
+        };
+
+        let deps = wit_dependencies(path).map_err(failure)?;
+
+        stack.push(canonical);
+        let resolve = self.wit_resolve(name, span, &deps, dependencies, stack);
+        stack.pop();
+
+        let mut resolve = resolve?;
+        let pkg = if path.is_dir() {
+            resolve.push_dir(path).map(|(pkg, _)| pkg)
+        } else {
+            wit_parser::UnresolvedPackage::parse_file(path).and_then(|pkg| resolve.push(pkg))
+        }
+        .map_err(failure)?;
+
+        wit_component::encode(Some(true), &resolve, pkg)
+            .with_context(|| {
+                format!(
+                    "failed to encode WIT package `{path}`",
+                    path = path.display()
+                )
+            })
+            .map_err(failure)
+    }
+
+    /// Creates a WIT resolve containing the given foreign dependencies of
+    /// the WIT package with the given name.
+    #[cfg(feature = "wit")]
+    fn wit_resolve(
+        &self,
+        package: &str,
+        span: SourceSpan,
+        deps: &[wit_parser::PackageName],
+        dependencies: &Dependencies,
+        stack: &mut Vec<PathBuf>,
+    ) -> Result<wit_parser::Resolve, Error> {
+        let mut resolve = wit_parser::Resolve::new();
+        for dep in deps {
+            let name = dep.to_string();
+            let failure = |e| Error::PackageResolutionFailure {
+                name: package.to_string(),
+                span,
+                source: e,
+            };
+
+            let package_name = package_name(dep);
+            let key = PackageKey {
+                name: &package_name,
+                version: dep.version.as_ref(),
+                requirement: None,
+            };
+
+            let bytes = match self.find_dependency(&key) {
+                Some(_) => {
+                    let keys = [(key, span)].into_iter().collect();
+                    self.resolve_with_stack(&keys, dependencies, stack)?
+                        .into_values()
+                        .next()
+                        .map(|package| package.bytes)
+                }
+                None => dependencies.get(&name).map(|package| package.bytes.clone()),
+            }
+            .ok_or_else(|| anyhow!("WIT dependency `{name}` was not found"))
+            .map_err(failure)?;
+
+            log::debug!("merging WIT dependency `{name}`");
+            let other = match wit_component::decode(&bytes)
+                .with_context(|| format!("failed to decode WIT dependency `{name}`"))
+                .map_err(failure)?
+            {
+                wit_component::DecodedWasm::WitPackage(resolve, _)
+                | wit_component::DecodedWasm::Component(resolve, _) => resolve,
+            };
+
+            resolve
+                .merge(other)
+                .with_context(|| format!("failed to merge WIT dependency `{name}`"))
+                .map_err(failure)?;
+        }
+
+        Ok(resolve)
+    }
+
+    /// Finds the local path of a package of a composition or a foreign
+    /// dependency of a WIT package.
+    ///
+    /// Returns `None` if the package is not in the root directory and has no
+    /// local path override.
+    fn find_dependency(&self, key: &PackageKey) -> Option<PathBuf> {
+        let path = match self.overrides.get(key.name) {
+            Some(path) if key.version.is_none() => path.clone(),
+            _ => self.find_path(key)?.0,
+        };
+
+        path.exists().then_some(path)
+    }
+
+    /// Resolves the packages of the compositions and the foreign dependencies
+    /// of the WIT packages with the given keys that are not found in the root
+    /// directory.
+    ///
+    /// The packages are resolved with the given resolver.
+    async fn resolve_dependencies(
+        &self,
+        resolver: &dyn PackageResolver,
+        keys: &IndexMap<PackageKey<'_>, SourceSpan>,
+    ) -> Result<Dependencies, Error> {
+        let mut missing = IndexMap::new();
+        let mut visited = Vec::new();
+        for (key, span) in keys {
+            if let Some(path) = self.find_dependency(key) {
+                self.find_missing_packages(&path, *span, false, &mut visited, &mut missing);
+            }
+        }
+
+        if missing.is_empty() {
+            return Ok(Default::default());
+        }
+
+        let keys = missing
+            .iter()
+            .map(|((name, version, requirement), span)| {
+                (
+                    PackageKey {
+                        name,
+                        version: version.as_ref(),
+                        requirement: requirement.as_ref(),
+                    },
+                    *span,
+                )
+            })
+            .collect();
+
+        Ok(resolver
+            .resolve(&keys)
+            .await?
+            .into_iter()
+            .map(|(key, package)| (key.to_string(), package))
+            .collect())
+    }
+
+    /// Finds the packages of the composition or the foreign dependencies of
+    /// the WIT package at the given path, and of their packages in the root
+    /// directory, that are not found in the root directory.
+    ///
+    /// If `binary` is set, binary packages in the root directory are also
+    /// added to `missing`.
+    ///
+    /// The local paths of the packages and of the documents included by
+    /// compositions are added to `visited`.
+    ///
+    /// Packages that fail to parse are skipped; the failure is reported
+    /// when the package is resolved.
+    fn find_missing_packages(
+        &self,
+        path: &Path,
+        span: SourceSpan,
+        binary: bool,
+        visited: &mut Vec<PathBuf>,
+        missing: &mut IndexMap<OwnedPackageKey, SourceSpan>,
+    ) {
+        if visited.iter().any(|p| p == path) {
+            return;
+        }
+
+        visited.push(path.to_path_buf());
+
+        #[cfg(feature = "wit")]
+        if is_wit(path) {
+            for dep in wit_dependencies(path).unwrap_or_default() {
+                let name = package_name(&dep);
+                self.find_missing_package(
+                    &PackageKey {
+                        name: &name,
+                        version: dep.version.as_ref(),
+                        requirement: None,
+                    },
+                    span,
+                    binary,
+                    visited,
+                    missing,
+                );
+            }
+
+            return;
+        }
+
+        if path.extension().and_then(OsStr::to_str) != Some("wac") {
+            return;
+        }
+
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(_) => return,
+        };
+
+        let mut sources = Sources::new(path, contents);
+        let included = sources.load_includes();
+        visited.extend(sources.files().skip(1).map(|f| f.path().to_path_buf()));
+        if included.is_err() {
+            return;
+        }
+
+        let document = match sources.parse() {
+            Ok(document) => document,
+            Err(_) => return,
+        };
+
+        for key in crate::packages(&document).unwrap_or_default().keys() {
+            self.find_missing_package(key, span, binary, visited, missing);
+        }
+    }
+
+    /// Finds the packages used by the package with the given key that are
+    /// not found in the root directory, or the package itself if it is not
+    /// found.
+    ///
+    /// If `binary` is set, the package itself is also added to `missing` if
+    /// it is a binary package in the root directory.
+    fn find_missing_package(
+        &self,
+        key: &PackageKey,
+        span: SourceSpan,
+        binary: bool,
+        visited: &mut Vec<PathBuf>,
+        missing: &mut IndexMap<OwnedPackageKey, SourceSpan>,
+    ) {
+        let path = self.find_dependency(key);
+        let overridden = key.version.is_none() && self.overrides.contains_key(key.name);
+        let add = match &path {
+            Some(path) => binary && !overridden && !is_source(path),
+            None => !(binary && overridden),
+        };
+
+        if add {
+            missing
+                .entry((
+                    key.name.to_string(),
+                    key.version.cloned(),
+                    key.requirement.cloned(),
+                ))
+                .or_insert(span);
+        }
+
+        if let Some(path) = path {
+            self.find_missing_packages(&path, span, binary, visited, missing);
+        }
+    }
+}
+
+/// Determines if the given path is a composition or a WIT package.
+fn is_source(path: &Path) -> bool {
+    #[cfg(feature = "wit")]
+    if is_wit(path) {
+        return true;
+    }
+
+    path.extension().and_then(OsStr::to_str) == Some("wac")
+}
+
+/// Determines if the given path is a WIT package directory or file.
+#[cfg(feature = "wit")]
+fn is_wit(path: &Path) -> bool {
+    path.is_dir() || path.extension().and_then(OsStr::to_str) == Some("wit")
+}
+
+/// Gets the name of a WIT package without its version.
+#[cfg(feature = "wit")]
+fn package_name(name: &wit_parser::PackageName) -> String {
+    format!("{ns}:{name}", ns = name.namespace, name = name.name)
+}
+
+/// Gets the foreign dependencies of the WIT package at the given path that
+/// are not in its own `deps` directory.
+#[cfg(feature = "wit")]
+fn wit_dependencies(path: &Path) -> Result<Vec<wit_parser::PackageName>> {
+    use wit_parser::UnresolvedPackage;
+
+    let mut packages = Vec::new();
+    if path.is_dir() {
+        packages.push(UnresolvedPackage::parse_dir(path)?);
+
+        let deps = path.join("deps");
+        if deps.is_dir() {
+            for entry in fs::read_dir(&deps).with_context(|| {
+                format!("failed to read directory `{deps}`", deps = deps.display())
+            })? {
+                let path = entry?.path();
+                if path.is_dir() {
+                    packages.push(UnresolvedPackage::parse_dir(&path)?);
+                } else if path.extension().and_then(OsStr::to_str) == Some("wit") {
+                    packages.push(UnresolvedPackage::parse_file(&path)?);
+                }
+            }
+        }
+    } else {
+        packages.push(UnresolvedPackage::parse_file(path)?);
+    }
+
+    let mut deps = Vec::new();
+    for package in &packages {
+        for dep in package.foreign_deps.keys() {
+            if !packages.iter().any(|p| p.name == *dep) && !deps.contains(dep) {
+                deps.push(dep.clone());
+            }
+        }
+    }
+
+    Ok(deps)
+}
+
+/// Appends an extension to the given path.
+///
+/// Unlike `Path::with_extension`, this does not replace any part of a
+/// file name containing a `.` (e.g. a version).
+fn with_extension(path: &Path, extension: &str) -> PathBuf {
+    let mut path = path.as_os_str().to_owned();
+    path.push(".");
+    path.push(extension);
+    path.into()
 }
 
 impl Default for FileSystemPackageResolver {
@@ -173,3 +882,18 @@ impl Default for FileSystemPackageResolver {
         Self::new("deps", Default::default(), true)
     }
 }
+
+#[async_trait]
+impl PackageResolver for FileSystemPackageResolver {
+    async fn resolve<'a>(
+        &self,
+        keys: &IndexMap<PackageKey<'a>, SourceSpan>,
+    ) -> Result<IndexMap<PackageKey<'a>, ResolvedPackage>, Error> {
+        if let Some(resolver) = &self.dependencies {
+            let dependencies = self.resolve_dependencies(resolver.as_ref(), keys).await?;
+            return self.resolve_with_stack(keys, &dependencies, &mut Vec::new());
+        }
+
+        FileSystemPackageResolver::resolve(self, keys)
+    }
+}
@@ -1,25 +1,33 @@
 //! Modules for package resolvers.
 
+use async_trait::async_trait;
 use indexmap::IndexMap;
-use miette::{Diagnostic, SourceSpan};
-use wac_parser::{ast::Document, PackageKey};
+use miette::{Diagnostic, LabeledSpan, Severity, SourceCode, SourceSpan};
+use std::{fmt, sync::Arc};
+use wac_parser::{ast::Document, sources::Sources, PackageKey, ResolvedPackage};
 
+mod chain;
 mod fs;
 #[cfg(feature = "registry")]
+mod lock;
+#[cfg(feature = "registry")]
 mod registry;
 mod visitor;
 
+pub use chain::*;
 pub use fs::*;
 #[cfg(feature = "registry")]
+pub use lock::*;
+#[cfg(feature = "registry")]
 pub use registry::*;
 pub use visitor::*;
 
 /// Represents a package resolution error.
 #[derive(thiserror::Error, Diagnostic, Debug)]
-#[diagnostic(code("failed to resolve document"))]
 pub enum Error {
     /// An unknown package was encountered.
     #[error("unknown package `{name}`")]
+    #[diagnostic(code(wac::package::unknown_package))]
     UnknownPackage {
         /// The name of the package.
         name: String,
@@ -30,6 +38,7 @@ pub enum Error {
     /// An unknown package version was encountered.
     #[cfg(feature = "registry")]
     #[error("version {version} of package `{name}` does not exist")]
+    #[diagnostic(code(wac::package::unknown_package_version))]
     UnknownPackageVersion {
         /// The name of the package.
         name: String,
@@ -39,8 +48,21 @@ pub enum Error {
         #[label(primary, "unknown package version `{version}`")]
         span: SourceSpan,
     },
+    /// No version of a package matches a version requirement.
+    #[error("no version of package `{name}` matches `{requirement}`")]
+    #[diagnostic(code(wac::package::no_matching_package_version))]
+    NoMatchingPackageVersion {
+        /// The name of the package.
+        name: String,
+        /// The version requirement of the package.
+        requirement: semver::VersionReq,
+        /// The span where the error occurred.
+        #[label(primary, "no version matches `{requirement}`")]
+        span: SourceSpan,
+    },
     /// Cannot instantiate the package being defined.
     #[error("cannot instantiate the package being defined")]
+    #[diagnostic(code(wac::package::cannot_instantiate_self))]
     CannotInstantiateSelf {
         /// The span where the error occurred.
         #[label(primary, "cannot instantiate self")]
@@ -48,6 +70,7 @@ pub enum Error {
     },
     /// Cannot instantiate the package being defined.
     #[error("package `{name}` does not exist in the registry")]
+    #[diagnostic(code(wac::package::package_does_not_exist))]
     PackageDoesNotExist {
         /// The name of the package that does not exist.
         name: String,
@@ -58,6 +81,7 @@ pub enum Error {
     /// The requested package version has been yanked.
     #[cfg(feature = "registry")]
     #[error("version {version} of package `{name}` has been yanked")]
+    #[diagnostic(code(wac::package::package_version_yanked))]
     PackageVersionYanked {
         /// The name of the package.
         name: String,
@@ -70,6 +94,7 @@ pub enum Error {
     /// A package log was empty.
     #[cfg(feature = "registry")]
     #[error("a release for package `{name}` has not yet been published")]
+    #[diagnostic(code(wac::package::package_log_empty))]
     PackageLogEmpty {
         /// The name of the package.
         name: String,
@@ -80,14 +105,25 @@ pub enum Error {
     /// A failure occurred while updating logs from the registry.
     #[cfg(feature = "registry")]
     #[error("failed to update registry logs")]
+    #[diagnostic(code(wac::package::registry_update_failure))]
     RegistryUpdateFailure {
         /// The underlying error.
         #[source]
         source: anyhow::Error,
     },
+    /// A failure occurred while creating a registry resolver.
+    #[cfg(feature = "registry")]
+    #[error("failed to create a resolver for the registry")]
+    #[diagnostic(code(wac::package::registry_client_failure))]
+    RegistryClientFailure {
+        /// The underlying error.
+        #[source]
+        source: anyhow::Error,
+    },
     /// A failure occurred while downloading content from the registry.
     #[cfg(feature = "registry")]
     #[error("failed to download content from the registry")]
+    #[diagnostic(code(wac::package::registry_download_failure))]
     RegistryDownloadFailure {
         /// The underlying error.
         #[source]
@@ -96,6 +132,7 @@ pub enum Error {
     /// A failure occurred while reading content from registry storage.
     #[cfg(feature = "registry")]
     #[error("failed to read content path `{path}`", path = .path.display())]
+    #[diagnostic(code(wac::package::registry_content_failure))]
     RegistryContentFailure {
         /// The path to the content.
         path: std::path::PathBuf,
@@ -103,8 +140,79 @@ pub enum Error {
         #[source]
         source: anyhow::Error,
     },
+    /// A package is not present in the lock file.
+    #[cfg(feature = "registry")]
+    #[error("package `{name}` is not present in lock file `{path}`", path = .path.display())]
+    #[diagnostic(
+        code(wac::package::package_not_locked),
+        help("run without `--locked` to update the lock file")
+    )]
+    PackageNotLocked {
+        /// The name of the package.
+        name: String,
+        /// The path to the lock file.
+        path: std::path::PathBuf,
+        /// The span where the error occurred.
+        #[label(primary, "package `{name}` is not locked")]
+        span: SourceSpan,
+    },
+    /// A package is not available in local storage while offline.
+    #[cfg(feature = "registry")]
+    #[error("package `{name}` is not available offline")]
+    #[diagnostic(
+        code(wac::package::package_not_available_offline),
+        help("run without `--offline` to fetch it from the registry")
+    )]
+    PackageNotAvailableOffline {
+        /// The name of the package, including any requested version.
+        name: String,
+        /// The span where the error occurred.
+        #[label(primary, "package `{name}` has not been downloaded")]
+        span: SourceSpan,
+    },
+    /// The content of a package is not available in local storage while offline.
+    #[cfg(feature = "registry")]
+    #[error("content of version {version} of package `{name}` is not available offline")]
+    #[diagnostic(
+        code(wac::package::package_content_not_available_offline),
+        help("run without `--offline` to download it from the registry")
+    )]
+    PackageContentNotAvailableOffline {
+        /// The name of the package.
+        name: String,
+        /// The version of the package.
+        version: semver::Version,
+        /// The span where the error occurred.
+        #[label(primary, "content of version {version} has not been downloaded")]
+        span: SourceSpan,
+    },
+    /// The content of a locked package does not match the lock file.
+    #[cfg(feature = "registry")]
+    #[error("content of version {version} of package `{name}` does not match the lock file")]
+    #[diagnostic(code(wac::package::locked_content_mismatch))]
+    LockedContentMismatch {
+        /// The name of the package.
+        name: String,
+        /// The locked version of the package.
+        version: semver::Version,
+        /// The span where the error occurred.
+        #[label(primary, "content hash mismatch for version {version}")]
+        span: SourceSpan,
+    },
+    /// A failure occurred while writing the lock file.
+    #[cfg(feature = "registry")]
+    #[error("failed to write lock file `{path}`", path = .path.display())]
+    #[diagnostic(code(wac::package::lock_file_write_failure))]
+    LockFileWriteFailure {
+        /// The path to the lock file.
+        path: std::path::PathBuf,
+        /// The underlying error.
+        #[source]
+        source: anyhow::Error,
+    },
     /// A package failed to resolve.
     #[error("failed to resolve package `{name}`")]
+    #[diagnostic(code(wac::package::package_resolution_failure))]
     PackageResolutionFailure {
         /// The name of the package.
         name: String,
@@ -115,6 +223,135 @@ pub enum Error {
         #[source]
         source: anyhow::Error,
     },
+    /// A composition resolved as a package failed to compose.
+    #[error("failed to compose package `{name}` from `{path}`", path = .path.display())]
+    #[diagnostic(code(wac::package::composition_failure))]
+    CompositionFailure {
+        /// The name of the package.
+        name: String,
+        /// The path to the composition.
+        path: std::path::PathBuf,
+        /// The span where the error occurred.
+        #[label(primary, "package `{name}` failed to compose")]
+        span: SourceSpan,
+        /// The errors in the composition.
+        #[related]
+        errors: Vec<CompositionDiagnostic>,
+    },
+    /// A composition resolved as a package depends on itself.
+    #[error("package `{name}` depends on itself")]
+    #[diagnostic(code(wac::package::composition_cycle))]
+    CompositionCycle {
+        /// The name of the package.
+        name: String,
+        /// The span where the error occurred.
+        #[label(primary, "cyclic dependency on package `{name}`")]
+        span: SourceSpan,
+    },
+}
+
+/// Represents a diagnostic of a composition resolved as a package.
+///
+/// The diagnostic is rendered with the sources of the composition rather
+/// than those of the document referencing the package.
+#[derive(Debug)]
+pub struct CompositionDiagnostic {
+    diagnostic: Box<dyn Diagnostic + Send + Sync>,
+    sources: Sources,
+}
+
+impl CompositionDiagnostic {
+    fn new(diagnostic: impl Diagnostic + Send + Sync + 'static, sources: &Sources) -> Self {
+        Self {
+            diagnostic: Box::new(diagnostic),
+            sources: sources.clone(),
+        }
+    }
+}
+
+impl fmt::Display for CompositionDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.diagnostic, f)
+    }
+}
+
+impl std::error::Error for CompositionDiagnostic {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.diagnostic.source()
+    }
+}
+
+impl Diagnostic for CompositionDiagnostic {
+    fn code<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        self.diagnostic.code()
+    }
+
+    fn severity(&self) -> Option<Severity> {
+        self.diagnostic.severity()
+    }
+
+    fn help<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        self.diagnostic.help()
+    }
+
+    fn source_code(&self) -> Option<&dyn SourceCode> {
+        Some(&self.sources)
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = LabeledSpan> + '_>> {
+        self.diagnostic.labels()
+    }
+
+    fn related<'a>(&'a self) -> Option<Box<dyn Iterator<Item = &'a dyn Diagnostic> + 'a>> {
+        self.diagnostic.related()
+    }
+
+    fn diagnostic_source(&self) -> Option<&dyn Diagnostic> {
+        self.diagnostic.diagnostic_source()
+    }
+}
+
+/// Implemented by package resolvers.
+///
+/// A resolver is given the packages referenced in a document, keyed by the
+/// span of the first reference, and returns the packages it resolved.
+///
+/// A resolver that chooses the version of a package requested without an
+/// exact version should report the chosen version in the returned package.
+///
+/// Resolvers may be combined with a [`ChainResolver`], in which case a
+/// resolver should omit the packages it cannot find from the returned map
+/// rather than return an error, so that a later resolver may resolve them.
+///
+/// Resolvers are `Send` and `Sync` so that resolution may be spawned on a
+/// multi-threaded runtime.
+#[async_trait]
+pub trait PackageResolver: Send + Sync {
+    /// Resolves the provided package keys to packages.
+    async fn resolve<'a>(
+        &self,
+        keys: &IndexMap<PackageKey<'a>, SourceSpan>,
+    ) -> Result<IndexMap<PackageKey<'a>, ResolvedPackage>, Error>;
+}
+
+#[async_trait]
+impl<T: PackageResolver + ?Sized> PackageResolver for Arc<T> {
+    async fn resolve<'a>(
+        &self,
+        keys: &IndexMap<PackageKey<'a>, SourceSpan>,
+    ) -> Result<IndexMap<PackageKey<'a>, ResolvedPackage>, Error> {
+        T::resolve(self, keys).await
+    }
+}
+
+#[async_trait]
+impl<T: PackageResolver + ?Sized> PackageResolver for Box<T> {
+    async fn resolve<'a>(
+        &self,
+        keys: &IndexMap<PackageKey<'a>, SourceSpan>,
+    ) -> Result<IndexMap<PackageKey<'a>, ResolvedPackage>, Error> {
+        T::resolve(self, keys).await
+    }
 }
 
 /// Builds a map of packages referenced in a document to the first span
@@ -123,17 +360,13 @@ pub fn packages<'a>(
     document: &'a Document<'a>,
 ) -> Result<IndexMap<PackageKey<'a>, SourceSpan>, Error> {
     let mut keys = IndexMap::new();
-    let mut visitor = PackageVisitor::new(|name, version, span| {
-        if name == document.directive.package.name {
+    let mut visitor = PackageVisitor::new(|key: PackageKey<'a>, span| {
+        if key.name == document.directive.package.name {
             return true;
         }
 
-        if keys.insert(PackageKey { name, version }, span).is_none() {
-            if let Some(version) = version {
-                log::debug!("discovered reference to package `{name}` ({version})");
-            } else {
-                log::debug!("discovered reference to package `{name}`");
-            }
+        if keys.insert(key, span).is_none() {
+            log::debug!("discovered reference to package `{key}`");
         }
 
         true
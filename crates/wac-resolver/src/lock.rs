@@ -0,0 +1,168 @@
+use anyhow::{bail, Context, Result};
+use semver::{Comparator, Op, Version, VersionReq};
+use serde::{Deserialize, Serialize};
+use std::{fs, path::Path};
+use wac_parser::PackageKey;
+
+/// The current version of the lock file format.
+const LOCK_FILE_VERSION: u32 = 1;
+
+/// The header written at the start of a lock file.
+const LOCK_FILE_HEADER: &str =
+    "# This file is automatically generated by wac.\n# It is not intended for manual editing.\n";
+
+/// Represents a package that has been locked to a specific version and content.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct LockedPackage {
+    /// The name of the package.
+    pub name: String,
+    /// The version requirement of the package requested by the document.
+    ///
+    /// An exact version requested by the document is recorded as an `=`
+    /// requirement; this is `None` if the document did not request a version.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub requirement: Option<VersionReq>,
+    /// The version the package was resolved to.
+    pub version: Version,
+    /// The URL of the registry the package was resolved from.
+    ///
+    /// For a package resolved from the default registry, this is the default
+    /// registry URL of the client configuration; it is `None` only if the
+    /// configuration has no default URL.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub registry: Option<String>,
+    /// The content hash of the resolved package.
+    pub hash: String,
+}
+
+impl LockedPackage {
+    /// Gets the version requirement recorded in a lock file for the given
+    /// package key.
+    ///
+    /// An exact version is recorded as an `=` requirement.
+    pub fn requirement_for(key: &PackageKey) -> Option<VersionReq> {
+        key.version.map(exact).or_else(|| key.requirement.cloned())
+    }
+}
+
+/// Represents a `wac.lock` file.
+///
+/// A lock file records the version and content hash that each package
+/// was resolved to so that later resolutions are reproducible.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LockFile {
+    /// The version of the lock file format.
+    version: u32,
+    /// The locked packages.
+    #[serde(default, rename = "package")]
+    packages: Vec<LockedPackage>,
+}
+
+impl Default for LockFile {
+    fn default() -> Self {
+        Self {
+            version: LOCK_FILE_VERSION,
+            packages: Vec::new(),
+        }
+    }
+}
+
+impl LockFile {
+    /// The default file name of a lock file.
+    pub const FILE_NAME: &'static str = "wac.lock";
+
+    /// Opens the lock file at the given path.
+    ///
+    /// Returns an empty lock file if the path does not exist.
+    pub fn open(path: &Path) -> Result<Self> {
+        if !path.is_file() {
+            return Ok(Self::default());
+        }
+
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("failed to read lock file `{path}`", path = path.display()))?;
+
+        let lock: Self = toml::from_str(&contents).with_context(|| {
+            format!("failed to parse lock file `{path}`", path = path.display())
+        })?;
+
+        if lock.version != LOCK_FILE_VERSION {
+            bail!(
+                "lock file `{path}` has unsupported version {version}",
+                path = path.display(),
+                version = lock.version
+            );
+        }
+
+        Ok(lock)
+    }
+
+    /// Writes the lock file to the given path.
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let mut lock = self.clone();
+        lock.packages.sort_by(|a, b| {
+            a.name.cmp(&b.name).then_with(|| {
+                let a = a.requirement.as_ref().map(ToString::to_string);
+                let b = b.requirement.as_ref().map(ToString::to_string);
+                a.cmp(&b)
+            })
+        });
+
+        let contents = format!(
+            "{LOCK_FILE_HEADER}{contents}",
+            contents = toml::to_string_pretty(&lock)?
+        );
+
+        fs::write(path, contents)
+            .with_context(|| format!("failed to write lock file `{path}`", path = path.display()))
+    }
+
+    /// Gets the locked packages.
+    pub fn packages(&self) -> &[LockedPackage] {
+        &self.packages
+    }
+
+    /// Finds a locked package by name, requested version requirement, and registry.
+    pub fn find(
+        &self,
+        name: &str,
+        requirement: Option<&VersionReq>,
+        registry: Option<&str>,
+    ) -> Option<&LockedPackage> {
+        self.packages.iter().find(|p| {
+            p.name == name
+                && p.requirement.as_ref() == requirement
+                && p.registry.as_deref() == registry
+        })
+    }
+
+    /// Inserts a locked package.
+    ///
+    /// Any existing package with the same name and requested version
+    /// requirement is replaced.
+    pub fn insert(&mut self, package: LockedPackage) {
+        self.packages
+            .retain(|p| p.name != package.name || p.requirement != package.requirement);
+        self.packages.push(package);
+    }
+
+    /// Retains only the locked packages for which the given predicate
+    /// returns `true`.
+    pub fn retain(&mut self, f: impl FnMut(&LockedPackage) -> bool) {
+        self.packages.retain(f);
+    }
+}
+
+/// Creates a version requirement matching exactly the given version.
+pub(crate) fn exact(version: &Version) -> VersionReq {
+    VersionReq {
+        comparators: vec![Comparator {
+            op: Op::Exact,
+            major: version.major,
+            minor: Some(version.minor),
+            patch: Some(version.patch),
+            pre: version.pre.clone(),
+        }],
+    }
+}
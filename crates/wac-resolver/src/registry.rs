@@ -1,11 +1,16 @@
-use super::Error;
+use super::{lock::exact, Error, LockFile, LockedPackage, PackageResolver};
 use anyhow::Result;
+use async_trait::async_trait;
 use futures::{stream::FuturesUnordered, StreamExt};
 use indexmap::{IndexMap, IndexSet};
 use miette::SourceSpan;
-use semver::{Comparator, Op, Version, VersionReq};
-use std::{fs, path::Path, sync::Arc};
-use wac_parser::PackageKey;
+use semver::{Version, VersionReq};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+use wac_parser::{PackageKey, ResolvedPackage};
 use warg_client::{
     storage::{ContentStorage, RegistryStorage},
     Client, ClientError, Config, FileSystemClient,
@@ -15,7 +20,7 @@ use warg_crypto::hash::AnyHash;
 /// Implemented by progress bars.
 ///
 /// This is used to abstract a UI for the registry resolver.
-pub trait ProgressBar {
+pub trait ProgressBar: Send + Sync {
     /// Initializes the progress bar with the given count.
     fn init(&self, count: usize);
 
@@ -35,7 +40,17 @@ pub trait ProgressBar {
 /// the resolver.
 pub struct RegistryPackageResolver {
     client: Arc<FileSystemClient>,
+    registry: Option<String>,
     bar: Option<Box<dyn ProgressBar>>,
+    lock: Option<Lock>,
+    offline: bool,
+}
+
+/// Represents the lock file used by a registry resolver.
+struct Lock {
+    path: PathBuf,
+    locked: bool,
+    file: Mutex<LockFile>,
 }
 
 impl RegistryPackageResolver {
@@ -45,10 +60,7 @@ impl RegistryPackageResolver {
     /// If `url` is `None`, the default URL will be used.
     pub fn new(url: Option<&str>, bar: Option<Box<dyn ProgressBar>>) -> Result<Self> {
         let config = Config::from_default_file()?.unwrap_or_default();
-        Ok(Self {
-            client: Arc::new(Client::new_with_config(url, &config)?),
-            bar,
-        })
+        Self::new_with_config(url, &config, bar)
     }
 
     /// Creates a new registry package resolver with the given configuration.
@@ -61,24 +73,65 @@ impl RegistryPackageResolver {
     ) -> Result<Self> {
         Ok(Self {
             client: Arc::new(Client::new_with_config(url, config)?),
+            registry: url
+                .map(ToOwned::to_owned)
+                .or_else(|| config.default_url.clone()),
             bar,
+            lock: None,
+            offline: false,
         })
     }
 
+    /// Uses the lock file at the given path when resolving packages.
+    ///
+    /// Packages present in the lock file resolve to their locked versions;
+    /// any other resolved packages are added to the lock file, which is
+    /// written back to the given path.
+    ///
+    /// If `locked` is `true`, resolution fails instead of changing the lock file.
+    pub fn with_lock_file(mut self, path: impl Into<PathBuf>, locked: bool) -> Result<Self> {
+        let path = path.into();
+        let file = LockFile::open(&path)?;
+        self.lock = Some(Lock {
+            path,
+            locked,
+            file: Mutex::new(file),
+        });
+        Ok(self)
+    }
+
+    /// Sets whether the resolver is offline.
+    ///
+    /// An offline resolver does not contact the registry; packages are
+    /// resolved only from the logs and content already in client storage,
+    /// and resolution fails for any package that is not present.
+    pub fn with_offline(mut self, offline: bool) -> Self {
+        self.offline = offline;
+        self
+    }
+
     /// Resolves the provided package keys to packages.
     ///
     /// If the package isn't found, an error is returned.
     pub async fn resolve<'a>(
         &self,
         keys: &IndexMap<PackageKey<'a>, SourceSpan>,
-    ) -> Result<IndexMap<PackageKey<'a>, Arc<Vec<u8>>>, Error> {
+    ) -> Result<IndexMap<PackageKey<'a>, ResolvedPackage>, Error> {
+        let mut lock = self
+            .lock
+            .as_ref()
+            .map(|l| l.file.lock().unwrap().clone())
+            .unwrap_or_default();
+
         // Start by fetching any required package logs
-        self.fetch(keys).await?;
+        self.fetch(keys, &lock).await?;
 
         // All the logs have been updated, now we need to see what content
         // is missing from local storage.
         let mut packages = IndexMap::new();
-        let missing = self.find_missing_content(keys, &mut packages).await?;
+        let missing = self
+            .find_missing_content(keys, &mut lock, &mut packages)
+            .await?;
 
         if !missing.is_empty() {
             if let Some(bar) = self.bar.as_ref() {
@@ -118,7 +171,13 @@ impl RegistryPackageResolver {
 
                 let contents = Self::read_contents(&path)?;
                 for key in set {
-                    packages.insert(*key, contents.clone());
+                    packages.insert(
+                        *key,
+                        ResolvedPackage {
+                            version: Some(version.clone()),
+                            bytes: contents.clone(),
+                        },
+                    );
                 }
             }
 
@@ -129,10 +188,34 @@ impl RegistryPackageResolver {
             }
         }
 
+        // Update the lock file if any new packages were locked
+        if let Some(state) = &self.lock {
+            let mut file = state.file.lock().unwrap();
+            if *file != lock {
+                log::debug!("writing lock file `{path}`", path = state.path.display());
+
+                lock.write(&state.path)
+                    .map_err(|e| Error::LockFileWriteFailure {
+                        path: state.path.clone(),
+                        source: e,
+                    })?;
+                *file = lock;
+            }
+        }
+
         Ok(packages)
     }
 
-    async fn fetch(&self, keys: &IndexMap<PackageKey<'_>, SourceSpan>) -> Result<(), Error> {
+    async fn fetch(
+        &self,
+        keys: &IndexMap<PackageKey<'_>, SourceSpan>,
+        lock: &LockFile,
+    ) -> Result<(), Error> {
+        if self.offline {
+            log::debug!("skipping update of package logs while offline");
+            return Ok(());
+        }
+
         // First check if we already have the packages in client storage.
         // If not, we'll fetch the logs from the registry.
         let mut fetch = IndexMap::new();
@@ -159,19 +242,17 @@ impl RegistryPackageResolver {
                     source: e,
                 })?
             {
-                if let Some(version) = key.version {
-                    let req = VersionReq {
-                        comparators: vec![Comparator {
-                            op: Op::Exact,
-                            major: version.major,
-                            minor: Some(version.minor),
-                            patch: Some(version.patch),
-                            pre: version.pre.clone(),
-                        }],
-                    };
+                let locked = lock
+                    .find(
+                        key.name,
+                        LockedPackage::requirement_for(key).as_ref(),
+                        self.registry.as_deref(),
+                    )
+                    .map(|p| &p.version);
 
+                if let Some(version) = locked.or(key.version) {
                     // Version already present, no need to fetch the log
-                    if info.state.find_latest_release(&req).is_some() {
+                    if info.state.find_latest_release(&exact(version)).is_some() {
                         log::debug!(
                             "package log for `{name}` has a release version {version}",
                             name = key.name
@@ -222,7 +303,8 @@ impl RegistryPackageResolver {
     async fn find_missing_content<'a>(
         &self,
         keys: &IndexMap<PackageKey<'a>, SourceSpan>,
-        packages: &mut IndexMap<PackageKey<'a>, Arc<Vec<u8>>>,
+        lock: &mut LockFile,
+        packages: &mut IndexMap<PackageKey<'a>, ResolvedPackage>,
     ) -> Result<IndexMap<AnyHash, (Version, IndexSet<PackageKey<'a>>)>, Error> {
         let mut downloads: IndexMap<AnyHash, (Version, IndexSet<PackageKey<'a>>)> = IndexMap::new();
         for (key, span) in keys {
@@ -235,7 +317,7 @@ impl RegistryPackageResolver {
                         source: e,
                     })?;
 
-            let info = self
+            let info = match self
                 .client
                 .registry()
                 .load_package(&id)
@@ -244,20 +326,35 @@ impl RegistryPackageResolver {
                     name: key.name.to_string(),
                     span: *span,
                     source: e,
-                })?
-                .expect("package log should be present after fetching");
-
-            let req = match key.version {
-                Some(v) => VersionReq {
-                    comparators: vec![Comparator {
-                        op: Op::Exact,
-                        major: v.major,
-                        minor: Some(v.minor),
-                        patch: Some(v.patch),
-                        pre: v.pre.clone(),
-                    }],
-                },
-                None => VersionReq::STAR,
+                })? {
+                Some(info) => info,
+                None if self.offline => {
+                    return Err(Error::PackageNotAvailableOffline {
+                        name: key.to_string(),
+                        span: *span,
+                    });
+                }
+                None => panic!("package log should be present after fetching"),
+            };
+
+            let requirement = LockedPackage::requirement_for(key);
+            let locked = lock
+                .find(key.name, requirement.as_ref(), self.registry.as_deref())
+                .cloned();
+
+            if locked.is_none() {
+                if let Some(state) = self.lock.as_ref().filter(|l| l.locked) {
+                    return Err(Error::PackageNotLocked {
+                        name: key.name.to_string(),
+                        path: state.path.clone(),
+                        span: *span,
+                    });
+                }
+            }
+
+            let req = match &locked {
+                Some(locked) => exact(&locked.version),
+                None => requirement.clone().unwrap_or(VersionReq::STAR),
             };
 
             let release = match info.state.find_latest_release(&req) {
@@ -269,6 +366,12 @@ impl RegistryPackageResolver {
                         span: *span,
                     });
                 }
+                None if self.offline => {
+                    return Err(Error::PackageNotAvailableOffline {
+                        name: key.to_string(),
+                        span: *span,
+                    });
+                }
                 None => {
                     if let Some(version) = key.version {
                         return Err(Error::UnknownPackageVersion {
@@ -276,6 +379,12 @@ impl RegistryPackageResolver {
                             version: version.clone(),
                             span: *span,
                         });
+                    } else if let Some(requirement) = key.requirement {
+                        return Err(Error::NoMatchingPackageVersion {
+                            name: key.name.to_string(),
+                            requirement: requirement.clone(),
+                            span: *span,
+                        });
                     } else {
                         return Err(Error::PackageLogEmpty {
                             name: key.name.to_string(),
@@ -286,8 +395,47 @@ impl RegistryPackageResolver {
             };
 
             let hash = release.content().unwrap();
+            match &locked {
+                Some(locked) => {
+                    if locked.hash != hash.to_string() {
+                        return Err(Error::LockedContentMismatch {
+                            name: key.name.to_string(),
+                            version: release.version.clone(),
+                            span: *span,
+                        });
+                    }
+                }
+                None => {
+                    log::debug!(
+                        "locking package `{name}` to version {version}",
+                        name = key.name,
+                        version = release.version
+                    );
+
+                    lock.insert(LockedPackage {
+                        name: key.name.to_string(),
+                        requirement,
+                        version: release.version.clone(),
+                        registry: self.registry.clone(),
+                        hash: hash.to_string(),
+                    });
+                }
+            }
+
             if let Some(path) = self.client.content().content_location(hash) {
-                packages.insert(*key, Self::read_contents(&path)?);
+                packages.insert(
+                    *key,
+                    ResolvedPackage {
+                        version: Some(release.version.clone()),
+                        bytes: Self::read_contents(&path)?,
+                    },
+                );
+            } else if self.offline {
+                return Err(Error::PackageContentNotAvailableOffline {
+                    name: key.name.to_string(),
+                    version: release.version.clone(),
+                    span: *span,
+                });
             } else {
                 log::debug!(
                     "downloading content for version {version} of package `{name}`",
@@ -315,3 +463,13 @@ impl RegistryPackageResolver {
         })?))
     }
 }
+
+#[async_trait]
+impl PackageResolver for RegistryPackageResolver {
+    async fn resolve<'a>(
+        &self,
+        keys: &IndexMap<PackageKey<'a>, SourceSpan>,
+    ) -> Result<IndexMap<PackageKey<'a>, ResolvedPackage>, Error> {
+        RegistryPackageResolver::resolve(self, keys).await
+    }
+}
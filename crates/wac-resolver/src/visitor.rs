@@ -1,8 +1,10 @@
 use miette::SourceSpan;
-use semver::Version;
-use wac_parser::ast::{
-    Document, Expr, ExternType, ImportStatement, ImportType, InstantiationArgument, InterfaceItem,
-    PrimaryExpr, Statement, TypeStatement, UsePath, WorldItem, WorldItemPath, WorldRef,
+use wac_parser::{
+    ast::{
+        visit::{walk_new_expr, walk_statement},
+        Document, IncludeStatement, NewExpr, PackageDirective, PackagePath, Statement, Visitor,
+    },
+    PackageKey,
 };
 
 use crate::Error;
@@ -11,180 +13,114 @@ use crate::Error;
 ///
 /// This can be used to collect all of the packages referenced
 /// in a document so that they may all be resolved at the same time.
-pub struct PackageVisitor<T>(T);
+pub struct PackageVisitor<T> {
+    cb: T,
+    /// The name of the package of the document being visited.
+    this: String,
+    /// Whether or not the callback has requested to stop visiting.
+    stopped: bool,
+    /// The error encountered while visiting.
+    error: Option<Error>,
+}
 
 impl<'a, T> PackageVisitor<T>
 where
-    T: FnMut(&'a str, Option<&'a Version>, SourceSpan) -> bool,
+    T: FnMut(PackageKey<'a>, SourceSpan) -> bool,
 {
     /// Creates a new package visitor with the given callback.
     ///
-    /// The callback receives the package name, optional version, and
-    /// the span of the package name.
+    /// The callback receives the key of the package (its name and optional
+    /// version or version requirement) and the span of the package name.
     pub fn new(cb: T) -> Self {
-        Self(cb)
+        Self {
+            cb,
+            this: String::new(),
+            stopped: false,
+            error: None,
+        }
     }
 
-    /// Visits any package names referenced in the document.
+    /// Visits any package names referenced in the document and the
+    /// documents it includes.
     ///
     /// The package names are visited in-order and will not deduplicate
     /// names/versions that are referenced multiple times.
-    pub fn visit(&mut self, doc: &'a Document) -> Result<(), Error> {
-        if let Some(targets) = &doc.directive.targets {
-            if !(self.0)(
-                targets.name,
-                targets.version.as_ref(),
-                targets.package_name_span(),
-            ) {
-                return Ok(());
-            }
+    pub fn visit(&mut self, doc: &'a Document<'a>) -> Result<(), Error> {
+        self.this = doc.directive.package.name.to_owned();
+        self.stopped = false;
+        self.visit_document(doc);
+
+        match self.error.take() {
+            Some(e) => Err(e),
+            None => Ok(()),
         }
+    }
 
-        for stmt in &doc.statements {
-            match stmt {
-                Statement::Import(i) => {
-                    if !self.import_statement(i) {
-                        break;
-                    }
-                }
-                Statement::Type(t) => {
-                    if !self.type_statement(t) {
-                        break;
-                    }
-                }
-                Statement::Let(l) => {
-                    if !self.expr(doc.directive.package.name, &l.expr)? {
-                        break;
-                    }
-                }
-                Statement::Export(e) => {
-                    if !self.expr(doc.directive.package.name, &e.expr)? {
-                        break;
-                    }
-                }
-            }
+    fn package(&mut self, key: PackageKey<'a>, span: SourceSpan) {
+        if !self.stopped && !(self.cb)(key, span) {
+            self.stopped = true;
         }
-
-        Ok(())
     }
+}
 
-    fn import_statement(&mut self, stmt: &'a ImportStatement) -> bool {
-        match &stmt.ty {
-            ImportType::Package(p) => (self.0)(p.name, p.version.as_ref(), p.package_name_span()),
-            ImportType::Interface(i) => {
-                for item in &i.items {
-                    if !self.interface_item(item) {
-                        return false;
-                    }
-                }
-
-                true
-            }
-            ImportType::Func(_) | ImportType::Ident(_) => true,
+impl<'a, T> Visitor<'a> for PackageVisitor<T>
+where
+    T: FnMut(PackageKey<'a>, SourceSpan) -> bool,
+{
+    fn visit_package_directive(&mut self, directive: &'a PackageDirective<'a>) {
+        // The package being defined is not a reference to a package
+        if let Some(targets) = &directive.targets {
+            self.visit_package_path(targets);
         }
     }
 
-    fn type_statement(&mut self, stmt: &'a TypeStatement) -> bool {
-        match stmt {
-            TypeStatement::Interface(i) => {
-                for item in &i.items {
-                    if !self.interface_item(item) {
-                        return false;
-                    }
-                }
-
-                true
-            }
-            TypeStatement::World(w) => {
-                for item in &w.items {
-                    if !self.world_item(item) {
-                        return false;
-                    }
-                }
-
-                true
-            }
-            TypeStatement::Type(_) => true,
+    fn visit_statement(&mut self, statement: &'a Statement<'a>) {
+        if !self.stopped {
+            walk_statement(self, statement);
         }
     }
 
-    fn interface_item(&mut self, item: &'a InterfaceItem) -> bool {
-        match item {
-            InterfaceItem::Use(u) => match &u.path {
-                UsePath::Package(p) => (self.0)(p.name, p.version.as_ref(), p.package_name_span()),
-                UsePath::Ident(_) => true,
-            },
-            InterfaceItem::Type(_) | InterfaceItem::Export(_) => true,
+    fn visit_include_statement(&mut self, statement: &'a IncludeStatement<'a>) {
+        // Included documents are visited in place of the include statement
+        if let Some(doc) = &statement.document {
+            for statement in &doc.statements {
+                self.visit_statement(statement);
+            }
         }
     }
 
-    fn world_item(&mut self, item: &'a WorldItem) -> bool {
-        match item {
-            WorldItem::Use(u) => match &u.path {
-                UsePath::Package(p) => (self.0)(p.name, p.version.as_ref(), p.package_name_span()),
-                UsePath::Ident(_) => true,
-            },
-            WorldItem::Type(_) => true,
-            WorldItem::Import(i) => self.world_item_path(&i.path),
-            WorldItem::Export(e) => self.world_item_path(&e.path),
-            WorldItem::Include(i) => match &i.world {
-                WorldRef::Package(p) => (self.0)(p.name, p.version.as_ref(), p.package_name_span()),
-                WorldRef::Ident(_) => true,
-            },
+    fn visit_new_expr(&mut self, expr: &'a NewExpr<'a>) {
+        if self.stopped {
+            return;
         }
-    }
 
-    fn world_item_path(&mut self, path: &'a WorldItemPath) -> bool {
-        match path {
-            WorldItemPath::Named(n) => match &n.ty {
-                ExternType::Interface(i) => {
-                    for item in &i.items {
-                        if !self.interface_item(item) {
-                            return false;
-                        }
-                    }
+        if expr.package.name == self.this {
+            self.error = Some(Error::CannotInstantiateSelf {
+                span: expr.package.span,
+            });
+            self.stopped = true;
+            return;
+        }
 
-                    true
-                }
-                ExternType::Ident(_) | ExternType::Func(_) => true,
+        self.package(
+            PackageKey {
+                name: expr.package.name,
+                version: expr.package.version.as_ref(),
+                requirement: expr.package.requirement.as_ref(),
             },
-            WorldItemPath::Package(p) => {
-                (self.0)(p.name, p.version.as_ref(), p.package_name_span())
-            }
-            WorldItemPath::Ident(_) => true,
-        }
+            expr.package.span,
+        );
+        walk_new_expr(self, expr);
     }
 
-    fn expr(&mut self, this: &str, expr: &'a Expr) -> Result<bool, Error> {
-        match &expr.primary {
-            PrimaryExpr::New(e) => {
-                if e.package.name == this {
-                    return Err(Error::CannotInstantiateSelf {
-                        span: e.package.span,
-                    });
-                }
-
-                if !(self.0)(e.package.name, e.package.version.as_ref(), e.package.span) {
-                    return Ok(false);
-                }
-
-                for arg in &e.arguments {
-                    match arg {
-                        InstantiationArgument::Inferred(_)
-                        | InstantiationArgument::Spread(_)
-                        | InstantiationArgument::Fill(_) => continue,
-                        InstantiationArgument::Named(a) => {
-                            if !self.expr(this, &a.expr)? {
-                                return Ok(false);
-                            }
-                        }
-                    }
-                }
-
-                Ok(true)
-            }
-            PrimaryExpr::Nested(e) => self.expr(this, &e.inner),
-            PrimaryExpr::Ident(_) => Ok(true),
-        }
+    fn visit_package_path(&mut self, path: &'a PackagePath<'a>) {
+        self.package(
+            PackageKey {
+                name: path.name,
+                version: path.version.as_ref(),
+                requirement: path.requirement.as_ref(),
+            },
+            path.package_name_span(),
+        );
     }
 }
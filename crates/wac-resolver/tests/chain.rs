@@ -0,0 +1,87 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use indexmap::IndexMap;
+use miette::SourceSpan;
+use pretty_assertions::assert_eq;
+use std::{collections::HashMap, fs, sync::Arc};
+use tempdir::TempDir;
+use wac_parser::{PackageKey, ResolvedPackage};
+use wac_resolver::{ChainResolver, Error, FileSystemPackageResolver, PackageResolver};
+
+/// A resolver of packages held in memory.
+struct MemoryResolver(HashMap<&'static str, Arc<Vec<u8>>>);
+
+#[async_trait]
+impl PackageResolver for MemoryResolver {
+    async fn resolve<'a>(
+        &self,
+        keys: &IndexMap<PackageKey<'a>, SourceSpan>,
+    ) -> Result<IndexMap<PackageKey<'a>, ResolvedPackage>, Error> {
+        Ok(keys
+            .keys()
+            .filter_map(|key| Some((*key, self.0.get(key.name)?.clone().into())))
+            .collect())
+    }
+}
+
+#[tokio::test]
+async fn it_resolves_packages_in_order() -> Result<()> {
+    let root = TempDir::new("test")?;
+    fs::create_dir_all(root.path().join("test"))?;
+    fs::write(root.path().join("test/b.wasm"), b"fs")?;
+    fs::write(root.path().join("test/c.wasm"), b"fs")?;
+
+    let memory = |names: &[&'static str], contents: &[u8]| {
+        MemoryResolver(
+            names
+                .iter()
+                .map(|name| (*name, Arc::new(contents.to_vec())))
+                .collect(),
+        )
+    };
+
+    let mut chain = ChainResolver::new()
+        .with(FileSystemPackageResolver::new(
+            root.path(),
+            Default::default(),
+            false,
+        ))
+        .with(memory(&["test:a", "test:b"], b"last"));
+    chain.insert(0, memory(&["test:c"], b"first"));
+    assert_eq!(chain.len(), 3);
+
+    let span = SourceSpan::new(0.into(), 0.into());
+    let keys = ["test:a", "test:b", "test:c", "test:d"]
+        .into_iter()
+        .map(|name| {
+            (
+                PackageKey {
+                    name,
+                    version: None,
+                    requirement: None,
+                },
+                span,
+            )
+        })
+        .collect();
+
+    let packages = chain.resolve(&keys).await?;
+    assert_eq!(
+        packages
+            .iter()
+            .map(|(key, package)| {
+                (
+                    key.name,
+                    String::from_utf8_lossy(&package.bytes).into_owned(),
+                )
+            })
+            .collect::<Vec<_>>(),
+        [
+            ("test:c", "first".to_string()),
+            ("test:b", "fs".to_string()),
+            ("test:a", "last".to_string()),
+        ]
+    );
+
+    Ok(())
+}
@@ -0,0 +1,392 @@
+use anyhow::Result;
+use indexmap::IndexMap;
+use miette::{Diagnostic, SourceSpan};
+use pretty_assertions::assert_eq;
+use semver::{Version, VersionReq};
+use std::fs;
+use tempdir::TempDir;
+use wac_parser::PackageKey;
+use wac_resolver::{Error, FileSystemPackageResolver, PackageResolver};
+
+/// The bytes of an empty component.
+const EMPTY_COMPONENT: &[u8] = b"\0asm\x0d\0\x01\0";
+
+/// Creates a map of the given unversioned package to an empty span.
+fn key(name: &str) -> IndexMap<PackageKey<'_>, SourceSpan> {
+    [(
+        PackageKey {
+            name,
+            version: None,
+            requirement: None,
+        },
+        SourceSpan::new(0.into(), 0.into()),
+    )]
+    .into_iter()
+    .collect()
+}
+
+#[test]
+fn it_discovers_package_versions() -> Result<()> {
+    let root = TempDir::new("test")?;
+    let dir = root.path().join("test/pkg");
+    fs::create_dir_all(&dir)?;
+    for version in ["1.0.0", "1.2.0", "1.10.0", "2.0.0-rc.1"] {
+        fs::write(dir.join(format!("{version}.wasm")), version)?;
+    }
+    fs::write(dir.join("README.md"), "not a version")?;
+
+    let resolver = FileSystemPackageResolver::new(root.path(), Default::default(), true);
+    assert_eq!(
+        resolver
+            .versions("test:pkg")
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>(),
+        ["1.0.0", "1.2.0", "1.10.0", "2.0.0-rc.1"]
+    );
+    assert_eq!(
+        resolver.find_version("test:pkg", &VersionReq::parse("~1.2")?),
+        Some(Version::parse("1.2.0")?)
+    );
+    assert_eq!(
+        resolver.find_version("test:pkg", &VersionReq::parse(">=2.0.0-rc.1")?),
+        Some(Version::parse("2.0.0-rc.1")?)
+    );
+    assert_eq!(
+        resolver.find_version("test:pkg", &VersionReq::parse("^3")?),
+        None
+    );
+
+    // The highest release version is resolved for an unversioned package
+    // and the highest matching version for a version requirement
+    let span = SourceSpan::new(0.into(), 0.into());
+    let exact = Version::parse("1.0.0")?;
+    let req = VersionReq::parse("~1.2")?;
+    let keys: IndexMap<_, _> = [
+        PackageKey {
+            name: "test:pkg",
+            version: None,
+            requirement: None,
+        },
+        PackageKey {
+            name: "test:pkg",
+            version: Some(&exact),
+            requirement: None,
+        },
+        PackageKey {
+            name: "test:pkg",
+            version: None,
+            requirement: Some(&req),
+        },
+    ]
+    .into_iter()
+    .map(|key| (key, span))
+    .collect();
+
+    let packages = resolver.resolve(&keys)?;
+    assert_eq!(
+        packages
+            .iter()
+            .map(|(key, package)| (
+                key.to_string(),
+                package.version.as_ref().map(ToString::to_string),
+                String::from_utf8_lossy(&package.bytes).into_owned()
+            ))
+            .collect::<Vec<_>>(),
+        [
+            (
+                "test:pkg".to_string(),
+                Some("1.10.0".to_string()),
+                "1.10.0".to_string()
+            ),
+            ("test:pkg@1.0.0".to_string(), None, "1.0.0".to_string()),
+            (
+                "test:pkg@~1.2".to_string(),
+                Some("1.2.0".to_string()),
+                "1.2.0".to_string()
+            ),
+        ]
+    );
+
+    // The versions directory is watched for an unversioned package
+    assert_eq!(resolver.path(keys.get_index(0).unwrap().0), dir);
+
+    // A requirement that no version matches is an error
+    let req = VersionReq::parse("^3")?;
+    let keys = [(
+        PackageKey {
+            name: "test:pkg",
+            version: None,
+            requirement: Some(&req),
+        },
+        span,
+    )]
+    .into_iter()
+    .collect();
+    match resolver.resolve(&keys) {
+        Err(Error::NoMatchingPackageVersion {
+            name, requirement, ..
+        }) => {
+            assert_eq!(name, "test:pkg");
+            assert_eq!(requirement, req);
+        }
+        Err(e) => panic!("unexpected error: {e}"),
+        Ok(_) => panic!("expected resolution to fail"),
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "wit")]
+#[tokio::test]
+async fn it_resolves_wit_dependencies() -> Result<()> {
+    let root = TempDir::new("test")?;
+    fs::create_dir_all(root.path().join("deps/test/io"))?;
+    fs::create_dir_all(root.path().join("deps/test/http"))?;
+    fs::create_dir_all(root.path().join("registry/test"))?;
+
+    // A single file WIT package stored as a version
+    fs::write(
+        root.path().join("deps/test/io/0.1.0.wit"),
+        r#"package test:io@0.1.0;
+
+interface streams {
+    type stream = u32;
+}
+"#,
+    )?;
+
+    // A WIT package directory that uses the package above
+    fs::write(
+        root.path().join("deps/test/http/types.wit"),
+        r#"package test:http;
+
+interface types {
+    use test:io/streams@0.1.0.{stream};
+
+    send: func(s: stream);
+}
+"#,
+    )?;
+
+    // A WIT package that uses a package only available from another resolver
+    fs::write(
+        root.path().join("deps/test/cli.wit"),
+        r#"package test:cli;
+
+interface run {
+    use test:clocks/time.{instant};
+
+    run: func(at: instant);
+}
+"#,
+    )?;
+    fs::write(
+        root.path().join("registry/test/clocks.wit"),
+        r#"package test:clocks;
+
+interface time {
+    type instant = u64;
+}
+"#,
+    )?;
+
+    let names = |bytes: &[u8]| -> Result<Vec<String>> {
+        let mut names = match wit_component::decode(bytes)? {
+            wit_component::DecodedWasm::WitPackage(resolve, _) => resolve
+                .package_names
+                .keys()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            _ => panic!("expected a WIT package"),
+        };
+        names.sort();
+        Ok(names)
+    };
+
+    let resolver =
+        FileSystemPackageResolver::new(root.path().join("deps"), Default::default(), true);
+    let packages = resolver.resolve(&key("test:http"))?;
+    assert_eq!(names(&packages[0].bytes)?, ["test:http", "test:io@0.1.0"]);
+
+    // Dependencies that are not found locally are an error
+    match resolver.resolve(&key("test:cli")) {
+        Err(Error::PackageResolutionFailure { name, source, .. }) => {
+            assert_eq!(name, "test:cli");
+            assert_eq!(
+                source.to_string(),
+                "WIT dependency `test:clocks` was not found"
+            );
+        }
+        Err(e) => panic!("unexpected error: {e}"),
+        Ok(_) => panic!("expected resolution to fail"),
+    }
+
+    // ...unless they are resolved by the dependency resolver
+    let resolver = resolver.with_dependency_resolver(FileSystemPackageResolver::new(
+        root.path().join("registry"),
+        Default::default(),
+        true,
+    ));
+    let packages = PackageResolver::resolve(&resolver, &key("test:cli")).await?;
+    assert_eq!(names(&packages[0].bytes)?, ["test:cli", "test:clocks"]);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn it_resolves_compositions() -> Result<()> {
+    let root = TempDir::new("test")?;
+    fs::create_dir_all(root.path().join("deps/test"))?;
+    fs::create_dir_all(root.path().join("registry/test"))?;
+
+    // A composition of a package in the root directory
+    fs::write(root.path().join("deps/test/leaf.wasm"), EMPTY_COMPONENT)?;
+    fs::write(
+        root.path().join("deps/test/inner.wac"),
+        "package test:inner;\n\nlet leaf = new test:leaf { ... };\n",
+    )?;
+
+    // A composition of the composition above
+    fs::write(
+        root.path().join("deps/test/outer.wac"),
+        "package test:outer;\n\nlet inner = new test:inner { ... };\n",
+    )?;
+
+    // A composition of a package only available from another resolver
+    fs::write(
+        root.path().join("registry/test/remote.wasm"),
+        EMPTY_COMPONENT,
+    )?;
+    fs::write(
+        root.path().join("deps/test/uses-remote.wac"),
+        "package test:uses-remote;\n\nlet remote = new test:remote { ... };\n",
+    )?;
+
+    let resolver =
+        FileSystemPackageResolver::new(root.path().join("deps"), Default::default(), true);
+    for name in ["test:inner", "test:outer"] {
+        let packages = resolver.resolve(&key(name))?;
+        let text = wasmprinter::print_bytes(&packages[0].bytes)?;
+        assert!(text.starts_with("(component"), "unexpected package: {text}");
+    }
+
+    // The paths of a composition include those of its packages
+    let deps = root.path().join("deps/test");
+    assert_eq!(
+        resolver.paths(key("test:outer").keys().next().unwrap()),
+        [
+            deps.join("outer.wac"),
+            deps.join("inner.wac"),
+            deps.join("leaf.wasm")
+        ]
+    );
+
+    // Packages that are not found locally are an error
+    match resolver.resolve(&key("test:uses-remote")) {
+        Err(Error::CompositionFailure { name, errors, .. }) => {
+            assert_eq!(name, "test:uses-remote");
+            assert_eq!(errors.len(), 1);
+            assert_eq!(errors[0].to_string(), "unknown package `test:remote`");
+        }
+        Err(e) => panic!("unexpected error: {e}"),
+        Ok(_) => panic!("expected resolution to fail"),
+    }
+
+    // ...unless they are resolved by the dependency resolver
+    let resolver = resolver.with_dependency_resolver(FileSystemPackageResolver::new(
+        root.path().join("registry"),
+        Default::default(),
+        true,
+    ));
+    let packages = PackageResolver::resolve(&resolver, &key("test:uses-remote")).await?;
+    let text = wasmprinter::print_bytes(&packages[0].bytes)?;
+    assert!(text.starts_with("(component"), "unexpected package: {text}");
+
+    Ok(())
+}
+
+#[test]
+fn it_finds_binary_packages() -> Result<()> {
+    let root = TempDir::new("test")?;
+    fs::create_dir_all(root.path().join("test"))?;
+    fs::write(root.path().join("test/leaf.wasm"), EMPTY_COMPONENT)?;
+    fs::write(
+        root.path().join("test/inner.wac"),
+        "package test:inner;\n\nlet leaf = new test:leaf { ... };\nlet remote = new test:remote { ... };\n",
+    )?;
+    fs::write(
+        root.path().join("test/outer.wac"),
+        "package test:outer;\n\nlet inner = new test:inner { ... };\n",
+    )?;
+
+    // Compositions are replaced by their packages, transitively
+    let resolver = FileSystemPackageResolver::new(root.path(), Default::default(), true);
+    assert_eq!(
+        resolver
+            .binary_packages(&key("test:outer"))
+            .into_keys()
+            .map(|(name, ..)| name)
+            .collect::<Vec<_>>(),
+        ["test:leaf", "test:remote"]
+    );
+
+    // WIT packages are replaced by their foreign dependencies
+    #[cfg(feature = "wit")]
+    {
+        fs::write(
+            root.path().join("test/http.wit"),
+            "package test:http;\n\ninterface types {\n    use test:io/streams.{stream};\n}\n",
+        )?;
+        assert_eq!(
+            resolver
+                .binary_packages(&key("test:http"))
+                .into_keys()
+                .map(|(name, ..)| name)
+                .collect::<Vec<_>>(),
+            ["test:io"]
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn it_detects_composition_cycles() -> Result<()> {
+    let root = TempDir::new("test")?;
+    fs::create_dir_all(root.path().join("test"))?;
+    fs::write(
+        root.path().join("test/a.wac"),
+        "package test:a;\n\nlet b = new test:b { ... };\n",
+    )?;
+    fs::write(
+        root.path().join("test/b.wac"),
+        "package test:b;\n\nlet a = new test:a { ... };\n",
+    )?;
+
+    let resolver = FileSystemPackageResolver::new(root.path(), Default::default(), true);
+    match resolver.resolve(&key("test:a")) {
+        Err(Error::CompositionFailure { name, errors, .. }) => {
+            assert_eq!(name, "test:a");
+            assert_eq!(errors.len(), 1);
+            assert!(errors[0]
+                .to_string()
+                .starts_with("failed to compose package `test:b`"));
+
+            // The cycle is reported by the composition that closes it
+            assert_eq!(
+                errors[0]
+                    .related()
+                    .into_iter()
+                    .flatten()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>(),
+                ["package `test:a` depends on itself"]
+            );
+        }
+        Err(e) => panic!("unexpected error: {e}"),
+        Ok(_) => panic!("expected resolution to fail"),
+    }
+
+    Ok(())
+}
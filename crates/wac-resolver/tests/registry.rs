@@ -1,9 +1,12 @@
 use crate::support::{publish_component, publish_wit, spawn_server};
 use anyhow::Result;
 use pretty_assertions::assert_eq;
+use std::fs;
 use tempdir::TempDir;
 use wac_parser::{ast::Document, Composition, EncodingOptions};
-use wac_resolver::{packages, RegistryPackageResolver};
+use wac_resolver::{
+    packages, Error, FileSystemPackageResolver, LockFile, PackageResolver, RegistryPackageResolver,
+};
 
 mod support;
 
@@ -95,7 +98,7 @@ export i2.foo as "bar";
       (export (;1;) "test:wit/foo" (instance (type 0)))
     )
   )
-  (import "unlocked-dep=<test:comp>" (component (;0;) (type 2)))
+  (import "unlocked-dep=<test:comp@{>=0.1.0}>" (component (;0;) (type 2)))
   (instance (;2;) (instantiate 0
       (with "test:wit/foo" (instance 0))
     )
@@ -116,3 +119,210 @@ export i2.foo as "bar";
 
     Ok(())
 }
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn it_locks_registry_packages() -> Result<()> {
+    let root = TempDir::new("test")?;
+    let (_server, config) = spawn_server(root.path()).await?;
+    config.write_to_file(&root.path().join("warg-config.json"))?;
+
+    let lock_path = root.path().join(LockFile::FILE_NAME);
+
+    publish_component(&config, "test:comp", "0.1.0", "(component)", true).await?;
+
+    let document = Document::parse(
+        r#"
+package test:composition;
+
+let i = new test:comp {};
+"#,
+    )?;
+
+    // The first resolution should write the lock file
+    let resolver = RegistryPackageResolver::new_with_config(None, &config, None)?
+        .with_lock_file(&lock_path, false)?;
+    let first = resolver.resolve(&packages(&document)?).await?;
+
+    let lock = LockFile::open(&lock_path)?;
+    assert_eq!(lock.packages().len(), 1);
+    assert_eq!(lock.packages()[0].name, "test:comp");
+    assert_eq!(lock.packages()[0].requirement, None);
+    assert_eq!(lock.packages()[0].version.to_string(), "0.1.0");
+
+    publish_component(
+        &config,
+        "test:comp",
+        "0.2.0",
+        r#"(component (import "f" (func)))"#,
+        false,
+    )
+    .await?;
+
+    // Later resolutions should honor the lock file
+    let resolver = RegistryPackageResolver::new_with_config(None, &config, None)?
+        .with_lock_file(&lock_path, true)?;
+    let second = resolver.resolve(&packages(&document)?).await?;
+    assert_eq!(first, second);
+    assert_eq!(LockFile::open(&lock_path)?, lock);
+
+    // A locked resolution should fail if the lock file would change
+    let document = Document::parse(
+        r#"
+package test:composition;
+
+let i = new test:comp@0.2.0 {};
+"#,
+    )?;
+
+    match resolver.resolve(&packages(&document)?).await {
+        Err(Error::PackageNotLocked { name, .. }) => assert_eq!(name, "test:comp"),
+        Err(e) => panic!("unexpected error: {e}"),
+        Ok(_) => panic!("expected resolution to fail"),
+    }
+
+    assert_eq!(LockFile::open(&lock_path)?, lock);
+
+    // An unlocked resolution should add the newly requested package to the lock file
+    let resolver = RegistryPackageResolver::new_with_config(None, &config, None)?
+        .with_lock_file(&lock_path, false)?;
+    resolver.resolve(&packages(&document)?).await?;
+
+    let lock = LockFile::open(&lock_path)?;
+    assert_eq!(
+        lock.packages()
+            .iter()
+            .map(|p| (
+                p.name.as_str(),
+                p.requirement.as_ref().map(ToString::to_string),
+                p.version.to_string()
+            ))
+            .collect::<Vec<_>>(),
+        [
+            ("test:comp", None, "0.1.0".to_string()),
+            ("test:comp", Some("=0.2.0".to_string()), "0.2.0".to_string())
+        ]
+    );
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn it_locks_packages_of_nested_compositions() -> Result<()> {
+    let root = TempDir::new("test")?;
+    let (_server, config) = spawn_server(root.path()).await?;
+    config.write_to_file(&root.path().join("warg-config.json"))?;
+
+    let lock_path = root.path().join(LockFile::FILE_NAME);
+
+    publish_component(&config, "test:comp", "0.1.0", "(component)", true).await?;
+    publish_component(&config, "test:comp", "0.2.0", "(component)", false).await?;
+
+    // The nested composition requests a different version of the package
+    // than the document does
+    let deps = root.path().join("deps");
+    fs::create_dir_all(deps.join("test"))?;
+    fs::write(
+        deps.join("test/nested.wac"),
+        "package test:nested;\n\nlet i = new test:comp { ... };\n",
+    )?;
+
+    let nested = Document::parse("package test:composition;\n\nlet n = new test:nested {};\n")?;
+    let document =
+        Document::parse("package test:composition;\n\nlet i = new test:comp@0.1.0 {};\n")?;
+
+    // The nested composition and the document are resolved in separate
+    // calls, each with a registry resolver using the same lock file
+    for locked in [false, true] {
+        {
+            let resolver = FileSystemPackageResolver::new(&deps, Default::default(), true)
+                .with_dependency_resolver(
+                    RegistryPackageResolver::new_with_config(None, &config, None)?
+                        .with_lock_file(&lock_path, locked)?,
+                );
+            PackageResolver::resolve(&resolver, &packages(&nested)?).await?;
+        }
+
+        let resolver = RegistryPackageResolver::new_with_config(None, &config, None)?
+            .with_lock_file(&lock_path, locked)?;
+        resolver.resolve(&packages(&document)?).await?;
+
+        let lock = LockFile::open(&lock_path)?;
+        assert_eq!(
+            lock.packages()
+                .iter()
+                .map(|p| (
+                    p.name.as_str(),
+                    p.requirement.as_ref().map(ToString::to_string),
+                    p.version.to_string()
+                ))
+                .collect::<Vec<_>>(),
+            [
+                ("test:comp", None, "0.2.0".to_string()),
+                ("test:comp", Some("=0.1.0".to_string()), "0.1.0".to_string())
+            ]
+        );
+
+        // A newer version should not be used once the packages are locked
+        if !locked {
+            publish_component(&config, "test:comp", "0.3.0", "(component)", false).await?;
+        }
+    }
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn it_resolves_registry_packages_offline() -> Result<()> {
+    let root = TempDir::new("test")?;
+    let (_server, config) = spawn_server(root.path()).await?;
+    config.write_to_file(&root.path().join("warg-config.json"))?;
+
+    publish_component(&config, "test:comp", "0.1.0", "(component)", true).await?;
+
+    let document = Document::parse(
+        r#"
+package test:composition;
+
+let i = new test:comp {};
+"#,
+    )?;
+
+    // Download the package so that it is present in client storage
+    let resolver = RegistryPackageResolver::new_with_config(None, &config, None)?;
+    let online = resolver.resolve(&packages(&document)?).await?;
+
+    publish_component(
+        &config,
+        "test:comp",
+        "0.2.0",
+        r#"(component (import "f" (func)))"#,
+        false,
+    )
+    .await?;
+
+    // An offline resolution should only use the downloaded package log
+    let resolver =
+        RegistryPackageResolver::new_with_config(None, &config, None)?.with_offline(true);
+    let offline = resolver.resolve(&packages(&document)?).await?;
+    assert_eq!(online, offline);
+    assert_eq!(
+        offline[0].version.as_ref().map(ToString::to_string),
+        Some("0.1.0".to_string())
+    );
+
+    // Versions and packages that were not downloaded should fail to resolve
+    for (source, expected) in [
+        ("let i = new test:comp@0.2.0 {};", "test:comp@0.2.0"),
+        ("let i = new test:other {};", "test:other"),
+        ("let i = new test:other@1.0.0 {};", "test:other@1.0.0"),
+    ] {
+        let document = Document::parse(&format!("package test:composition;\n{source}\n"))?;
+        match resolver.resolve(&packages(&document)?).await {
+            Err(Error::PackageNotAvailableOffline { name, .. }) => assert_eq!(name, expected),
+            Err(e) => panic!("unexpected error: {e}"),
+            Ok(_) => panic!("expected resolution to fail"),
+        }
+    }
+
+    Ok(())
+}
@@ -1,7 +1,12 @@
 use anyhow::Result;
 use clap::Parser;
 use owo_colors::{OwoColorize, Stream, Style};
-use wac::commands::{EncodeCommand, ParseCommand, ResolveCommand};
+#[cfg(feature = "registry")]
+use wac::commands::DepsCommand;
+use wac::commands::{
+    CheckCommand, DecodeCommand, EncodeCommand, FmtCommand, GraphCommand, LspCommand, ParseCommand,
+    ResolveCommand, SymbolizeCommand,
+};
 
 fn version() -> &'static str {
     option_env!("CARGO_VERSION_INFO").unwrap_or(env!("CARGO_PKG_VERSION"))
@@ -20,6 +25,15 @@ enum Wac {
     Parse(ParseCommand),
     Resolve(ResolveCommand),
     Encode(EncodeCommand),
+    Decode(DecodeCommand),
+    Symbolize(SymbolizeCommand),
+    Check(CheckCommand),
+    Fmt(FmtCommand),
+    Graph(GraphCommand),
+    Lsp(LspCommand),
+    #[cfg(feature = "registry")]
+    #[clap(subcommand)]
+    Deps(DepsCommand),
 }
 
 #[tokio::main]
@@ -30,6 +44,14 @@ async fn main() -> Result<()> {
         Wac::Parse(cmd) => cmd.exec().await,
         Wac::Resolve(cmd) => cmd.exec().await,
         Wac::Encode(cmd) => cmd.exec().await,
+        Wac::Decode(cmd) => cmd.exec().await,
+        Wac::Symbolize(cmd) => cmd.exec().await,
+        Wac::Check(cmd) => cmd.exec().await,
+        Wac::Fmt(cmd) => cmd.exec().await,
+        Wac::Graph(cmd) => cmd.exec().await,
+        Wac::Lsp(cmd) => cmd.exec().await,
+        #[cfg(feature = "registry")]
+        Wac::Deps(cmd) => cmd.exec().await,
     } {
         eprintln!(
             "{error}: {e:?}",
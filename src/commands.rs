@@ -1,9 +1,27 @@
 //! Module for CLI commands.
 
+mod check;
+mod decode;
+#[cfg(feature = "registry")]
+mod deps;
 mod encode;
+mod fmt;
+mod graph;
+mod lsp;
+mod options;
 mod parse;
 mod resolve;
+mod symbolize;
 
+pub use self::check::*;
+pub use self::decode::*;
+#[cfg(feature = "registry")]
+pub use self::deps::*;
 pub use self::encode::*;
+pub use self::fmt::*;
+pub use self::graph::*;
+pub use self::lsp::*;
+pub use self::options::*;
 pub use self::parse::*;
 pub use self::resolve::*;
+pub use self::symbolize::*;
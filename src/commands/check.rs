@@ -0,0 +1,400 @@
+use super::PackageOptions;
+use crate::{fmt_err, lint_levels, DocumentResolver, Manifest};
+use anyhow::{bail, Context, Result};
+use clap::{Args, ValueEnum};
+use miette::{Report, Severity, SourceSpan};
+use serde::Serialize;
+use serde_json::json;
+use std::{fs, path::PathBuf};
+use wac_parser::{sources::Sources, Composition, LintLevels};
+
+/// The format of diagnostics reported by the check command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum DiagnosticFormat {
+    /// Graphical diagnostics written to stderr.
+    Human,
+    /// A JSON array of diagnostics written to stdout.
+    Json,
+    /// A SARIF 2.1.0 log written to stdout.
+    Sarif,
+}
+
+/// Checks compositions for errors without encoding them.
+#[derive(Args)]
+#[clap(disable_version_flag = true)]
+pub struct CheckCommand {
+    /// The options used to resolve packages.
+    #[clap(flatten)]
+    pub packages: PackageOptions,
+
+    /// The format of the reported diagnostics.
+    #[clap(long, value_enum, default_value_t = DiagnosticFormat::Human)]
+    pub format: DiagnosticFormat,
+
+    /// Allows a lint, suppressing its warnings.
+    ///
+    /// Use `warnings` to allow every lint.
+    #[clap(long, value_name = "LINT")]
+    pub allow: Vec<String>,
+
+    /// Denies a lint, reporting its warnings as errors.
+    ///
+    /// Use `warnings` to deny every lint.
+    #[clap(long, value_name = "LINT")]
+    pub deny: Vec<String>,
+
+    /// The paths to the composition files to check.
+    #[clap(value_name = "PATH", required = true)]
+    pub paths: Vec<PathBuf>,
+}
+
+impl CheckCommand {
+    /// Executes the command.
+    pub async fn exec(self) -> Result<()> {
+        log::debug!("executing check command");
+
+        let levels = lint_levels(&self.allow, &self.deny)?;
+        let mut errors = 0;
+        let mut diagnostics = Vec::new();
+        for path in &self.paths {
+            log::debug!("checking file `{path}`", path = path.display());
+
+            let manifest = Manifest::find(path)?;
+            let resolver = self.packages.resolver(manifest.as_ref(), path)?;
+
+            let contents = fs::read_to_string(path)
+                .with_context(|| format!("failed to read file `{path}`", path = path.display()))?;
+
+            // Errors loading included documents are reported as diagnostics
+            let mut sources = Sources::new(path, contents);
+            let reports = match sources.load_includes() {
+                Ok(()) => check(&resolver, &levels, &sources).await,
+                Err(e) => vec![e.into()],
+            };
+
+            for report in reports {
+                if report.severity().unwrap_or(Severity::Error) == Severity::Error {
+                    errors += 1;
+                }
+
+                match self.format {
+                    DiagnosticFormat::Human => {
+                        eprintln!("{e:?}", e = fmt_err(report, &sources))
+                    }
+                    DiagnosticFormat::Json | DiagnosticFormat::Sarif => {
+                        diagnostics.push(Diagnostic::new(&sources, &*report))
+                    }
+                }
+            }
+        }
+
+        match self.format {
+            DiagnosticFormat::Human => {}
+            DiagnosticFormat::Json => {
+                serde_json::to_writer_pretty(std::io::stdout(), &diagnostics)?;
+                println!();
+            }
+            DiagnosticFormat::Sarif => {
+                serde_json::to_writer_pretty(std::io::stdout(), &sarif(&diagnostics))?;
+                println!();
+            }
+        }
+
+        if errors > 0 {
+            bail!(
+                "{errors} error{s} found in the checked compositions",
+                s = if errors == 1 { "" } else { "s" }
+            );
+        }
+
+        Ok(())
+    }
+}
+
+/// Parses and resolves a composition, returning its diagnostics.
+///
+/// Warnings are reported according to the given lint levels.
+async fn check(resolver: &DocumentResolver, levels: &LintLevels, sources: &Sources) -> Vec<Report> {
+    // Report every parse error rather than stopping at the first
+    let document = match sources.parse_recovering() {
+        (Some(document), errors) if errors.is_empty() => document,
+        (_, errors) => return errors.into_iter().map(Into::into).collect(),
+    };
+
+    let packages = match resolver.resolve(&document).await {
+        Ok(packages) => packages,
+        Err(e) => return vec![e.into()],
+    };
+
+    match Composition::from_ast_recovering(&document, packages) {
+        Ok(composition) => levels
+            .apply_sources(sources, &composition.warnings)
+            .into_iter()
+            .map(Into::into)
+            .collect(),
+        Err(errors) => errors.into_iter().map(Into::into).collect(),
+    }
+}
+
+/// Represents a location in a source file.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Location {
+    /// The byte offset of the location.
+    offset: usize,
+    /// The 1-based line number of the location.
+    line: usize,
+    /// The 1-based column (in characters) of the location.
+    column: usize,
+}
+
+impl Location {
+    fn new(source: &str, offset: usize) -> Self {
+        let offset = offset.min(source.len());
+        let before = &source[..offset];
+        let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+        Self {
+            offset,
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+        }
+    }
+}
+
+/// Represents a labeled span of a diagnostic.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Label {
+    /// The path of the file containing the labeled span.
+    path: String,
+    /// The label's message.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    message: Option<String>,
+    /// Whether or not the label is the primary label of the diagnostic.
+    primary: bool,
+    /// The start of the labeled span.
+    start: Location,
+    /// The end of the labeled span.
+    end: Location,
+}
+
+impl Label {
+    fn new(sources: &Sources, message: Option<String>, primary: bool, span: SourceSpan) -> Self {
+        // The span may be in a document included by the composition
+        let file = sources.file(span.offset()).unwrap_or(sources.root());
+        let offset = span.offset().saturating_sub(file.offset());
+        Self {
+            path: file.path().display().to_string(),
+            message,
+            primary,
+            start: Location::new(file.text(), offset),
+            end: Location::new(file.text(), offset + span.len()),
+        }
+    }
+}
+
+/// Represents a machine-readable diagnostic.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Diagnostic {
+    /// The path of the file the diagnostic is for.
+    ///
+    /// This is the file of the primary label, if there is one.
+    path: String,
+    /// The severity of the diagnostic.
+    severity: &'static str,
+    /// The code of the diagnostic.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    code: Option<String>,
+    /// The message of the diagnostic.
+    message: String,
+    /// The help text of the diagnostic.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    help: Option<String>,
+    /// The labeled spans of the diagnostic.
+    ///
+    /// The primary label, if any, is always first.
+    labels: Vec<Label>,
+}
+
+impl Diagnostic {
+    fn new(sources: &Sources, diagnostic: &dyn miette::Diagnostic) -> Self {
+        let mut labels: Vec<_> = diagnostic
+            .labels()
+            .into_iter()
+            .flatten()
+            .map(|l| {
+                Label::new(
+                    sources,
+                    l.label().map(ToString::to_string),
+                    l.primary(),
+                    *l.inner(),
+                )
+            })
+            .collect();
+
+        // Treat the first label as primary if the diagnostic did not specify one
+        match labels.iter().position(|l| l.primary) {
+            Some(index) => labels[..=index].rotate_right(1),
+            None => {
+                if let Some(label) = labels.first_mut() {
+                    label.primary = true;
+                }
+            }
+        }
+
+        Self {
+            path: labels
+                .first()
+                .map(|l| l.path.clone())
+                .unwrap_or_else(|| sources.root().path().display().to_string()),
+            severity: match diagnostic.severity().unwrap_or(Severity::Error) {
+                Severity::Advice => "advice",
+                Severity::Warning => "warning",
+                Severity::Error => "error",
+            },
+            code: diagnostic.code().map(|c| c.to_string()),
+            message: diagnostic.to_string(),
+            help: diagnostic.help().map(|h| h.to_string()),
+            labels,
+        }
+    }
+}
+
+/// Creates a SARIF 2.1.0 log from the given diagnostics.
+fn sarif(diagnostics: &[Diagnostic]) -> serde_json::Value {
+    fn region(label: &Label) -> serde_json::Value {
+        json!({
+            "startLine": label.start.line,
+            "startColumn": label.start.column,
+            "endLine": label.end.line,
+            "endColumn": label.end.column,
+            "charOffset": label.start.offset,
+            "charLength": label.end.offset - label.start.offset,
+        })
+    }
+
+    let results: Vec<_> = diagnostics
+        .iter()
+        .map(|d| {
+            let location = |label: &Label| {
+                json!({
+                    "physicalLocation": {
+                        "artifactLocation": { "uri": label.path.replace('\\', "/") },
+                        "region": region(label),
+                    },
+                    "message": { "text": label.message.as_deref().unwrap_or_default() },
+                })
+            };
+
+            let mut text = d.message.clone();
+            if let Some(help) = &d.help {
+                text.push_str("\n\nhelp: ");
+                text.push_str(help);
+            }
+
+            let level = match d.severity {
+                "advice" => "note",
+                level => level,
+            };
+
+            let (primary, related): (Vec<_>, Vec<_>) = d.labels.iter().partition(|l| l.primary);
+            let locations: Vec<_> = primary.into_iter().map(location).collect();
+            let related: Vec<_> = related.into_iter().map(location).collect();
+
+            let mut result = json!({
+                "level": level,
+                "message": { "text": text },
+                "locations": locations,
+                "relatedLocations": related,
+            });
+
+            if let Some(code) = &d.code {
+                result["ruleId"] = json!(code);
+            }
+
+            result
+        })
+        .collect();
+
+    json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "wac",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "informationUri": "https://github.com/bytecodealliance/wac",
+                },
+            },
+            "columnKind": "unicodeCodePoints",
+            "results": results,
+        }],
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::path::Path;
+
+    async fn diagnostics(path: &Path) -> Result<Vec<Diagnostic>> {
+        let mut sources = Sources::new(path, fs::read_to_string(path)?);
+        sources.load_includes()?;
+
+        let resolver = DocumentResolver::new(
+            "tests/check",
+            Default::default(),
+            #[cfg(feature = "registry")]
+            None,
+        )?;
+
+        Ok(check(&resolver, &LintLevels::default(), &sources)
+            .await
+            .iter()
+            .map(|report| Diagnostic::new(&sources, &**report))
+            .collect())
+    }
+
+    fn compare(path: &Path, extension: &str, result: serde_json::Value) -> Result<()> {
+        let path = path.with_extension(extension);
+        let expected: serde_json::Value = serde_json::from_str(
+            &fs::read_to_string(&path)
+                .with_context(|| format!("failed to read `{path}`", path = path.display()))?,
+        )?;
+
+        assert_eq!(expected, result);
+        Ok(())
+    }
+
+    #[test]
+    fn it_locates_offsets() {
+        let source = "ab\n\u{e9}c\n";
+        for (offset, line, column) in [(0, 1, 1), (2, 1, 3), (3, 2, 1), (5, 2, 2), (7, 3, 1)] {
+            let location = Location::new(source, offset);
+            assert_eq!((location.line, location.column), (line, column));
+        }
+
+        // Offsets past the end of the source are clamped
+        assert_eq!(Location::new(source, 100).offset, source.len());
+    }
+
+    #[tokio::test]
+    async fn it_reports_json_diagnostics() -> Result<()> {
+        let path = Path::new("tests/check/include.wac");
+        let diagnostics = diagnostics(path).await?;
+        compare(path, "json.result", serde_json::to_value(&diagnostics)?)
+    }
+
+    #[tokio::test]
+    async fn it_reports_sarif_diagnostics() -> Result<()> {
+        let path = Path::new("tests/check/include.wac");
+        let mut log = sarif(&diagnostics(path).await?);
+
+        // The fixture does not depend on the version of the tool
+        log["runs"][0]["tool"]["driver"]["version"] = json!("0.0.0");
+        compare(path, "sarif.result", log)
+    }
+}
@@ -0,0 +1,76 @@
+use crate::fmt_err;
+use anyhow::{Context, Result};
+use clap::Args;
+use std::{fs, io::Write, path::PathBuf};
+use wac_parser::{ast::DocumentPrinter, sources::Sources, DecodedDocument};
+
+/// Decodes a WebAssembly component into a composition.
+///
+/// Packages defined in the component are given placeholder names.
+#[derive(Args)]
+#[clap(disable_version_flag = true)]
+pub struct DecodeCommand {
+    /// The name of the package for the decoded composition.
+    #[clap(long, value_name = "NAME", default_value = "decoded:component")]
+    pub package: String,
+
+    /// The path to write the output to.
+    ///
+    /// If not specified, the output will be written to stdout.
+    #[clap(long, short = 'o')]
+    pub output: Option<PathBuf>,
+
+    /// The path to the WebAssembly component to decode.
+    #[clap(value_name = "PATH")]
+    pub path: PathBuf,
+}
+
+impl DecodeCommand {
+    /// Executes the command.
+    pub async fn exec(self) -> Result<()> {
+        log::debug!("executing decode command");
+
+        let bytes = fs::read(&self.path)
+            .with_context(|| format!("failed to read file `{path}`", path = self.path.display()))?;
+        let bytes = wat::parse_bytes(&bytes).with_context(|| {
+            format!(
+                "failed to parse file `{path}` as a WebAssembly component",
+                path = self.path.display()
+            )
+        })?;
+
+        let decoded = DecodedDocument::decode(&self.package, &bytes).with_context(|| {
+            format!(
+                "failed to decode component `{path}`",
+                path = self.path.display()
+            )
+        })?;
+
+        let document = decoded
+            .document()
+            .map_err(|e| fmt_err(e, &Sources::new(&self.path, decoded.source())))?;
+
+        let mut source = String::new();
+        DocumentPrinter::new(&mut source, decoded.source(), None)
+            .document(&document)
+            .context("failed to print decoded composition")?;
+
+        match self.output {
+            Some(path) => {
+                fs::write(&path, source).with_context(|| {
+                    format!(
+                        "failed to write output file `{path}`",
+                        path = path.display()
+                    )
+                })?;
+            }
+            None => {
+                std::io::stdout()
+                    .write_all(source.as_bytes())
+                    .context("failed to write to stdout")?;
+            }
+        }
+
+        Ok(())
+    }
+}
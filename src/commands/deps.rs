@@ -0,0 +1,271 @@
+use super::PackageOptions;
+use crate::{fmt_err, load_sources, DocumentResolver, Manifest};
+use anyhow::{bail, Context, Result};
+use clap::{Args, Subcommand};
+use std::{fs, path::PathBuf};
+use wac_parser::{PackageKey, ResolvedPackage};
+
+/// Manages the package dependencies of compositions.
+#[derive(Subcommand)]
+pub enum DepsCommand {
+    /// Vendors registry packages into the dependencies directory.
+    Vendor(VendorCommand),
+}
+
+impl DepsCommand {
+    /// Executes the command.
+    pub async fn exec(self) -> Result<()> {
+        match self {
+            Self::Vendor(cmd) => cmd.exec().await,
+        }
+    }
+}
+
+/// Vendors registry packages into the dependencies directory.
+///
+/// Each package referenced by the compositions is resolved from the registry
+/// and written to the dependencies directory so that later builds do not
+/// require access to the registry.
+///
+/// The packages used by compositions and WIT packages in the dependencies
+/// directory are vendored in their place.
+#[derive(Args)]
+#[clap(disable_version_flag = true)]
+pub struct VendorCommand {
+    /// The options used to resolve packages.
+    #[clap(flatten)]
+    pub packages: PackageOptions,
+
+    /// Check that the vendored packages match the registry without modifying them.
+    #[clap(long)]
+    pub check: bool,
+
+    /// The paths to the composition files to vendor dependencies for.
+    #[clap(value_name = "PATH", required = true)]
+    pub paths: Vec<PathBuf>,
+}
+
+impl VendorCommand {
+    /// Executes the command.
+    pub async fn exec(self) -> Result<()> {
+        log::debug!("executing deps vendor command");
+
+        let mut outdated = 0;
+        for path in &self.paths {
+            let sources = load_sources(path)?;
+            let document = sources.parse().map_err(|e| fmt_err(e, &sources))?;
+
+            let manifest = Manifest::find(path)?;
+            let resolver = self.packages.resolver(manifest.as_ref(), path)?;
+
+            let keys = resolver
+                .registry_packages(&document)
+                .map_err(|e| fmt_err(e, &sources))?;
+            let packages = resolver
+                .resolve_from_registries(&keys)
+                .await
+                .map_err(|e| fmt_err(e, &sources))?;
+
+            for (key, package) in packages {
+                if !vendor(&resolver, &key, &package, self.check)? && self.check {
+                    outdated += 1;
+                }
+            }
+        }
+
+        if outdated > 0 {
+            bail!(
+                "{outdated} vendored package{s} {are} out of date; run `wac deps vendor` to update",
+                s = if outdated == 1 { "" } else { "s" },
+                are = if outdated == 1 { "is" } else { "are" }
+            );
+        }
+
+        Ok(())
+    }
+}
+
+/// Vendors a package resolved from a registry into the dependencies directory.
+///
+/// The package is written with the version it resolved to, so that a package
+/// referenced without a version or with a version requirement is found by
+/// later resolutions.
+///
+/// If `check` is set, the vendored package is compared with the given package
+/// and is not modified.
+///
+/// Returns whether the vendored package was already up to date.
+fn vendor(
+    resolver: &DocumentResolver,
+    key: &PackageKey,
+    package: &ResolvedPackage,
+    check: bool,
+) -> Result<bool> {
+    let local = resolver.local_path(&PackageKey {
+        name: key.name,
+        version: package.version.as_ref().or(key.version),
+        requirement: None,
+    });
+
+    let existing = fs::read(&local).ok();
+    if existing.as_deref() == Some(package.bytes.as_slice()) {
+        log::debug!(
+            "package `{key}` is up to date at `{path}`",
+            path = local.display()
+        );
+        return Ok(true);
+    }
+
+    if check {
+        match existing {
+            Some(_) => eprintln!(
+                "package `{key}` at `{path}` does not match the registry content",
+                path = local.display()
+            ),
+            None => eprintln!(
+                "package `{key}` has not been vendored to `{path}`",
+                path = local.display()
+            ),
+        }
+
+        return Ok(false);
+    }
+
+    if let Some(parent) = local.parent() {
+        fs::create_dir_all(parent).with_context(|| {
+            format!(
+                "failed to create directory `{path}`",
+                path = parent.display()
+            )
+        })?;
+    }
+
+    fs::write(&local, package.bytes.as_slice()).with_context(|| {
+        format!(
+            "failed to write package `{key}` to `{path}`",
+            path = local.display()
+        )
+    })?;
+
+    eprintln!(
+        "vendored package `{key}` to `{path}`",
+        path = local.display()
+    );
+    Ok(false)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use semver::{Version, VersionReq};
+    use std::sync::Arc;
+    use wac_parser::ast::Document;
+
+    fn package(version: &str, bytes: &[u8]) -> Result<ResolvedPackage> {
+        Ok(ResolvedPackage {
+            version: Some(Version::parse(version)?),
+            bytes: Arc::new(bytes.to_vec()),
+        })
+    }
+
+    #[test]
+    fn it_vendors_packages_with_their_versions() -> Result<()> {
+        let dir = tempdir::TempDir::new("test")?;
+        let resolver = DocumentResolver::new(dir.path(), Default::default(), None)?;
+
+        let key = PackageKey {
+            name: "test:pkg",
+            version: None,
+            requirement: None,
+        };
+        assert!(!vendor(&resolver, &key, &package("1.0.0", b"v1")?, false)?);
+        assert_eq!(fs::read(dir.path().join("test/pkg/1.0.0.wasm"))?, b"v1");
+
+        // Vendoring again leaves the package as is
+        assert!(vendor(&resolver, &key, &package("1.0.0", b"v1")?, false)?);
+
+        let req = VersionReq::parse("^1")?;
+        let key = PackageKey {
+            name: "test:pkg",
+            version: None,
+            requirement: Some(&req),
+        };
+        assert!(!vendor(&resolver, &key, &package("1.2.0", b"v2")?, false)?);
+        assert_eq!(fs::read(dir.path().join("test/pkg/1.2.0.wasm"))?, b"v2");
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_checks_vendored_packages() -> Result<()> {
+        let dir = tempdir::TempDir::new("test")?;
+        let resolver = DocumentResolver::new(dir.path(), Default::default(), None)?;
+        let path = dir.path().join("test/pkg/1.0.0.wasm");
+
+        let key = PackageKey {
+            name: "test:pkg",
+            version: None,
+            requirement: None,
+        };
+        let package = package("1.0.0", b"v1")?;
+
+        // A package that was not vendored is out of date and is not written
+        assert!(!vendor(&resolver, &key, &package, true)?);
+        assert!(!path.exists());
+
+        // A package that does not match is out of date and is not modified
+        fs::create_dir_all(path.parent().unwrap())?;
+        fs::write(&path, b"modified")?;
+        assert!(!vendor(&resolver, &key, &package, true)?);
+        assert_eq!(fs::read(&path)?, b"modified");
+
+        fs::write(&path, b"v1")?;
+        assert!(vendor(&resolver, &key, &package, true)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_vendors_packages_of_compositions() -> Result<()> {
+        let dir = tempdir::TempDir::new("test")?;
+        fs::create_dir_all(dir.path().join("test"))?;
+        fs::write(dir.path().join("test/leaf.wasm"), b"leaf")?;
+        fs::write(
+            dir.path().join("test/nested.wac"),
+            "package test:nested;\n\nlet leaf = new test:leaf { ... };\nlet remote = new test:remote@^1.0.0 { ... };\nlet local = new test:local { ... };\n",
+        )?;
+
+        let resolver = DocumentResolver::new(
+            dir.path(),
+            [("test:local".to_string(), dir.path().join("local.wasm"))]
+                .into_iter()
+                .collect(),
+            None,
+        )?;
+
+        let document = Document::parse(
+            "package test:comp;\n\nlet nested = new test:nested { ... };\nlet other = new test:other { ... };\n",
+        )?;
+
+        // The composition is replaced by its packages; packages with local
+        // path overrides are not vendored
+        assert_eq!(
+            resolver
+                .registry_packages(&document)?
+                .into_keys()
+                .collect::<Vec<_>>(),
+            [
+                ("test:leaf".to_string(), None, None),
+                (
+                    "test:remote".to_string(),
+                    None,
+                    Some(VersionReq::parse("^1.0.0")?)
+                ),
+                ("test:other".to_string(), None, None),
+            ]
+        );
+
+        Ok(())
+    }
+}
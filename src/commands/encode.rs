@@ -1,43 +1,69 @@
-use crate::{fmt_err, PackageResolver};
+use super::{parse, PackageOptions};
+use crate::{absolute, fmt_err, lint_levels, load_sources, DocumentResolver, Manifest};
 use anyhow::{bail, Context, Result};
 use clap::Args;
+use notify::{EventKind, RecursiveMode, Watcher};
+use owo_colors::{OwoColorize, Stream, Style};
 use std::{
+    collections::HashSet,
     fs,
     io::{IsTerminal, Write},
     path::PathBuf,
+    str::FromStr,
+    sync::mpsc,
+    time::Duration,
 };
-use wac_parser::{ast::Document, Composition, EncodingOptions};
+use wac_parser::{Composition, LintLevel};
 use wasmparser::{Validator, WasmFeatures};
 use wasmprinter::print_bytes;
 
-fn parse<T, U>(s: &str) -> Result<(T, U)>
-where
-    T: std::str::FromStr,
-    T::Err: Into<anyhow::Error>,
-    U: std::str::FromStr,
-    U::Err: Into<anyhow::Error>,
-{
-    let (k, v) = s.split_once('=').context("value does not contain `=`")?;
-
-    Ok((
-        k.trim().parse().map_err(Into::into)?,
-        v.trim().parse().map_err(Into::into)?,
-    ))
+/// The kind of an artifact emitted by the encode command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmitKind {
+    /// The binary WebAssembly component.
+    Wasm,
+    /// The WebAssembly text format of the component.
+    Wat,
+    /// The resolved composition as JSON.
+    Json,
+    /// The instantiation graph in the Graphviz DOT format.
+    Dot,
+    /// The instantiation graph as a Mermaid flowchart.
+    Mermaid,
+    /// The WIT world of the component.
+    Wit,
+}
+
+impl FromStr for EmitKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "wasm" => Ok(Self::Wasm),
+            "wat" => Ok(Self::Wat),
+            "json" => Ok(Self::Json),
+            "dot" => Ok(Self::Dot),
+            "mermaid" => Ok(Self::Mermaid),
+            "wit" => Ok(Self::Wit),
+            _ => bail!(
+                "unknown artifact kind `{s}`; expected `wasm`, `wat`, `json`, `dot`, `mermaid`, or `wit`"
+            ),
+        }
+    }
 }
 
 /// Encodes a composition into a WebAssembly component.
 #[derive(Args)]
 #[clap(disable_version_flag = true)]
 pub struct EncodeCommand {
-    /// The directory to search for package dependencies.
-    #[clap(long, value_name = "PATH", default_value = "deps")]
-    pub deps_dir: PathBuf,
-
-    /// The directory to search for package dependencies.
-    #[clap(long = "dep", short, value_name = "PKG=PATH", value_parser = parse::<String, PathBuf>)]
-    pub deps: Vec<(String, PathBuf)>,
+    /// The options used to resolve packages.
+    #[clap(flatten)]
+    pub packages: PackageOptions,
 
     /// Whether to skip validation of the encoded WebAssembly component.
+    ///
+    /// Validation may also be disabled with `validate = false` in the
+    /// `[encode]` section of `wac.toml`.
     #[clap(long)]
     pub no_validate: bool,
 
@@ -47,20 +73,59 @@ pub struct EncodeCommand {
 
     /// Whether to not to define referenced packages.
     ///
-    /// If not specified, all referenced packages will be imported.
+    /// If not specified, all referenced packages will be imported unless
+    /// `define = true` is set in the `[encode]` section of `wac.toml`.
     #[clap(long)]
     pub define: bool,
 
+    /// Whether to emit a source map in the encoded component.
+    ///
+    /// The source map is a custom section that maps the component's items
+    /// to their locations in the composition; use `wac symbolize` to look
+    /// up the location of an item.
+    #[clap(long)]
+    pub source_map: bool,
+
     /// The path to write the output to.
     ///
     /// If not specified, the output will be written to stdout.
     #[clap(long, short = 'o')]
     pub output: Option<PathBuf>,
 
-    /// The URL of the registry to use.
-    #[cfg(feature = "registry")]
-    #[clap(long, value_name = "URL")]
-    pub registry: Option<String>,
+    /// The artifacts to write, as a comma-separated list of `KIND=PATH`.
+    ///
+    /// The supported kinds are `wasm`, `wat`, `json` (the resolved
+    /// composition), `dot` and `mermaid` (the instantiation graph), and
+    /// `wit` (the world of the component).
+    ///
+    /// All artifacts are produced from a single resolution of the composition.
+    #[clap(
+        long,
+        value_name = "KIND=PATH",
+        value_delimiter = ',',
+        value_parser = parse::<EmitKind, PathBuf>,
+        conflicts_with_all = ["output", "wat"]
+    )]
+    pub emit: Vec<(EmitKind, PathBuf)>,
+
+    /// Allows a lint, suppressing its warnings.
+    ///
+    /// Use `warnings` to allow every lint.
+    #[clap(long, value_name = "LINT")]
+    pub allow: Vec<String>,
+
+    /// Denies a lint, reporting its warnings as errors.
+    ///
+    /// Use `warnings` to deny every lint.
+    #[clap(long, value_name = "LINT")]
+    pub deny: Vec<String>,
+
+    /// Whether to watch for changes and encode the composition again.
+    ///
+    /// The composition is encoded again when it or any of its local
+    /// package dependencies change.
+    #[clap(long)]
+    pub watch: bool,
 
     /// The path to the composition file.
     #[clap(value_name = "PATH")]
@@ -72,34 +137,105 @@ impl EncodeCommand {
     pub async fn exec(self) -> Result<()> {
         log::debug!("executing encode command");
 
-        let contents = fs::read_to_string(&self.path)
-            .with_context(|| format!("failed to read file `{path}`", path = self.path.display()))?;
+        let manifest = Manifest::find(&self.path)?;
+        let resolver = self.packages.resolver(manifest.as_ref(), &self.path)?;
 
-        let document = Document::parse(&contents).map_err(|e| fmt_err(e, &self.path, &contents))?;
+        if !self.wat
+            && self.output.is_none()
+            && self.emit.is_empty()
+            && std::io::stdout().is_terminal()
+        {
+            bail!("cannot print binary wasm output to a terminal; pass the `-t` flag to print the text format instead");
+        }
 
-        let resolver = PackageResolver::new(
-            self.deps_dir,
-            self.deps.into_iter().collect(),
-            #[cfg(feature = "registry")]
-            self.registry.as_deref(),
-        )?;
+        if !self.watch {
+            return self
+                .encode(&resolver, manifest.as_ref(), &mut Vec::new())
+                .await;
+        }
+
+        // Unchanged packages are kept in memory between encodings
+        let resolver = resolver.with_cache();
+        loop {
+            let mut paths = vec![absolute(&self.path)];
+            match self.encode(&resolver, manifest.as_ref(), &mut paths).await {
+                Ok(()) => eprintln!(
+                    "encoded composition `{path}`; watching for changes",
+                    path = self.path.display()
+                ),
+                Err(e) => eprintln!(
+                    "{error}: {e:?}",
+                    error = "error".if_supports_color(Stream::Stderr, |text| {
+                        text.style(Style::new().red().bold())
+                    })
+                ),
+            }
+
+            // Watching blocks, so wait for changes off of the async runtime
+            let changed = tokio::task::spawn_blocking(move || wait(&paths))
+                .await
+                .context("file watcher panicked")??;
+            log::debug!("changed paths: {changed:?}");
+            resolver.invalidate(&changed);
+        }
+    }
+
+    /// Encodes the composition.
+    ///
+    /// The local paths of the composition's packages are added to `paths`.
+    async fn encode(
+        &self,
+        resolver: &DocumentResolver,
+        manifest: Option<&Manifest>,
+        paths: &mut Vec<PathBuf>,
+    ) -> Result<()> {
+        let sources = load_sources(&self.path)?;
+        paths.extend(sources.files().skip(1).map(|f| absolute(f.path())));
+
+        let document = sources.parse().map_err(|e| fmt_err(e, &sources))?;
+
+        paths.extend(
+            resolver
+                .resolved_paths(&document)
+                .map_err(|e| fmt_err(e, &sources))?,
+        );
 
         let packages = resolver
             .resolve(&document)
             .await
-            .map_err(|e| fmt_err(e, &self.path, &contents))?;
+            .map_err(|e| fmt_err(e, &sources))?;
 
-        let resolved = Composition::from_ast(&document, packages)
-            .map_err(|e| fmt_err(e, &self.path, &contents))?;
+        let resolved =
+            Composition::from_ast(&document, packages).map_err(|e| fmt_err(e, &sources))?;
 
-        if !self.wat && self.output.is_none() && std::io::stdout().is_terminal() {
-            bail!("cannot print binary wasm output to a terminal; pass the `-t` flag to print the text format instead");
+        let warnings =
+            lint_levels(&self.allow, &self.deny)?.apply_sources(&sources, &resolved.warnings);
+        let denied = warnings
+            .iter()
+            .filter(|w| w.level() == LintLevel::Deny)
+            .count();
+        for warning in warnings {
+            eprintln!("{e:?}", e = fmt_err(warning, &sources));
         }
 
-        let mut bytes = resolved.encode(EncodingOptions {
-            define_packages: self.define,
-        })?;
-        if !self.no_validate {
+        if denied > 0 {
+            bail!(
+                "{denied} denied lint{s} reported for the composition",
+                s = if denied == 1 { "" } else { "s" }
+            );
+        }
+
+        let mut options = manifest.map(Manifest::encoding_options).unwrap_or_default();
+        options.define_packages |= self.define;
+
+        let validate = !self.no_validate && manifest.map(Manifest::validate).unwrap_or(true);
+
+        let mut bytes = if self.source_map {
+            resolved.encode_with_source_map(options, &sources)?
+        } else {
+            resolved.encode(options)?
+        };
+        if validate {
             Validator::new_with_features(WasmFeatures {
                 component_model: true,
                 ..Default::default()
@@ -108,15 +244,46 @@ impl EncodeCommand {
             .context("failed to validate the encoded composition")?;
         }
 
+        if !self.emit.is_empty() {
+            for (kind, path) in &self.emit {
+                let contents = match kind {
+                    EmitKind::Wasm => bytes.clone(),
+                    EmitKind::Wat => print_bytes(&bytes)
+                        .context("failed to convert binary wasm output to text")?
+                        .into_bytes(),
+                    EmitKind::Json => serde_json::to_vec_pretty(&resolved)?,
+                    EmitKind::Dot => resolved.to_dot().into_bytes(),
+                    EmitKind::Mermaid => resolved.to_mermaid().into_bytes(),
+                    EmitKind::Wit => {
+                        let decoded = wit_component::decode(&bytes)
+                            .context("failed to decode the world of the encoded composition")?;
+                        wit_component::WitPrinter::default()
+                            .print(decoded.resolve(), decoded.package())
+                            .context("failed to print the world of the encoded composition")?
+                            .into_bytes()
+                    }
+                };
+
+                fs::write(path, contents).with_context(|| {
+                    format!(
+                        "failed to write output file `{path}`",
+                        path = path.display()
+                    )
+                })?;
+            }
+
+            return Ok(());
+        }
+
         if self.wat {
             bytes = print_bytes(&bytes)
                 .context("failed to convert binary wasm output to text")?
                 .into_bytes();
         }
 
-        match self.output {
+        match &self.output {
             Some(path) => {
-                fs::write(&path, bytes).context(format!(
+                fs::write(path, bytes).context(format!(
                     "failed to write output file `{path}`",
                     path = path.display()
                 ))?;
@@ -135,3 +302,167 @@ impl EncodeCommand {
         Ok(())
     }
 }
+
+/// Waits for any of the given paths to change, returning the changed paths.
+///
+/// Directories are watched recursively.
+fn wait(paths: &[PathBuf]) -> Result<Vec<PathBuf>> {
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx).context("failed to create file watcher")?;
+
+    // Files are watched through their parent directories so that files
+    // replaced by editors on save continue to be observed
+    let mut watched = HashSet::new();
+    for path in paths {
+        if path.is_dir() && watched.insert(path.as_path()) {
+            watcher
+                .watch(path, RecursiveMode::Recursive)
+                .with_context(|| format!("failed to watch `{path}`", path = path.display()))?;
+        }
+
+        if let Some(parent) = path.parent() {
+            if parent.is_dir() && watched.insert(parent) {
+                watcher
+                    .watch(parent, RecursiveMode::NonRecursive)
+                    .with_context(|| {
+                        format!("failed to watch `{path}`", path = parent.display())
+                    })?;
+            }
+        }
+    }
+
+    loop {
+        let event = rx
+            .recv()
+            .context("file watcher stopped unexpectedly")?
+            .context("failed to watch for changes")?;
+
+        if !matches!(
+            event.kind,
+            EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)
+        ) || !event
+            .paths
+            .iter()
+            .any(|p| paths.iter().any(|path| p.starts_with(path)))
+        {
+            continue;
+        }
+
+        // Wait briefly for related events (e.g. from an editor saving) before returning
+        let mut changed = event.paths;
+        while let Ok(event) = rx.recv_timeout(Duration::from_millis(100)) {
+            if let Ok(event) = event {
+                changed.extend(event.paths);
+            }
+        }
+
+        return Ok(changed);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use clap::{error::ErrorKind, Parser};
+    use pretty_assertions::assert_eq;
+    use wac_parser::ast::Document;
+
+    #[derive(Parser)]
+    struct Cli {
+        #[clap(flatten)]
+        command: EncodeCommand,
+    }
+
+    fn parse_command(args: &[&str]) -> Result<EncodeCommand, clap::Error> {
+        Cli::try_parse_from(std::iter::once("wac").chain(args.iter().copied()))
+            .map(|cli| cli.command)
+    }
+
+    #[tokio::test]
+    async fn it_emits_artifacts() -> Result<()> {
+        let dir = tempdir::TempDir::new("test")?;
+        let path = dir.path().join("test.wac");
+        fs::write(
+            &path,
+            "package test:comp;\n\nimport f: func();\nexport f;\n",
+        )?;
+
+        let emit = ["wasm", "wat", "json", "dot", "mermaid", "wit"]
+            .iter()
+            .map(|kind| {
+                format!(
+                    "{kind}={path}",
+                    path = dir.path().join(format!("out.{kind}")).display()
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(",");
+
+        parse_command(&["--emit", &emit, path.to_str().unwrap()])?
+            .exec()
+            .await?;
+
+        let read = |kind: &str| fs::read_to_string(dir.path().join(format!("out.{kind}")));
+        assert!(fs::read(dir.path().join("out.wasm"))?.starts_with(b"\0asm"));
+        assert!(read("wat")?.starts_with("(component"));
+        assert!(serde_json::from_str::<serde_json::Value>(&read("json")?)?.is_object());
+        assert!(read("dot")?.starts_with("digraph {"));
+        assert!(read("mermaid")?.starts_with("flowchart LR"));
+        assert!(read("wit")?.contains("world "));
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_rejects_emit_with_other_outputs() {
+        for args in [
+            ["--emit", "wat=out.wat", "-o", "out.wasm", "test.wac"].as_slice(),
+            ["--emit", "wat=out.wat", "-t", "test.wac"].as_slice(),
+        ] {
+            match parse_command(args) {
+                Err(e) => assert_eq!(e.kind(), ErrorKind::ArgumentConflict),
+                Ok(_) => panic!("expected `{args}` to be rejected", args = args.join(" ")),
+            }
+        }
+
+        assert!(parse_command(&["--emit", "png=out.png", "test.wac"]).is_err());
+    }
+
+    async fn bytes(resolver: &DocumentResolver, document: &Document<'_>) -> Result<Vec<u8>> {
+        let packages = resolver.resolve(document).await?;
+        Ok(packages[0].bytes.to_vec())
+    }
+
+    #[tokio::test]
+    async fn it_invalidates_changed_packages() -> Result<()> {
+        let dir = tempdir::TempDir::new("test")?;
+        let package = dir.path().join("test/pkg.wasm");
+        fs::create_dir_all(package.parent().unwrap())?;
+        fs::write(&package, b"v1")?;
+
+        let resolver = DocumentResolver::new(
+            dir.path(),
+            Default::default(),
+            #[cfg(feature = "registry")]
+            None,
+        )?
+        .with_cache();
+
+        let document = Document::parse("package test:comp;\n\nlet i = new test:pkg { ... };\n")?;
+        assert_eq!(resolver.resolved_paths(&document)?, [package.clone()]);
+
+        assert_eq!(bytes(&resolver, &document).await?, b"v1");
+
+        // The cached package is used until its path is invalidated
+        fs::write(&package, b"v2")?;
+        assert_eq!(bytes(&resolver, &document).await?, b"v1");
+
+        resolver.invalidate(&[dir.path().join("unrelated.wasm")]);
+        assert_eq!(bytes(&resolver, &document).await?, b"v1");
+
+        resolver.invalidate(&[package]);
+        assert_eq!(bytes(&resolver, &document).await?, b"v2");
+
+        Ok(())
+    }
+}
@@ -0,0 +1,111 @@
+use crate::fmt_err;
+use anyhow::{bail, Context, Result};
+use clap::Args;
+use std::{
+    fs,
+    io::{Read, Write},
+    path::{Path, PathBuf},
+};
+use wac_parser::{ast::DocumentPrinter, sources::Sources};
+
+/// Formats a composition in place.
+///
+/// If no paths are given, the composition is read from stdin and the
+/// formatted composition is written to stdout.
+#[derive(Args)]
+#[clap(disable_version_flag = true)]
+pub struct FmtCommand {
+    /// Check that the compositions are formatted without modifying them.
+    ///
+    /// A diff is printed for each composition that is not formatted.
+    #[clap(long)]
+    pub check: bool,
+
+    /// The paths to the composition files to format.
+    #[clap(value_name = "PATH")]
+    pub paths: Vec<PathBuf>,
+}
+
+impl FmtCommand {
+    /// Executes the command.
+    pub async fn exec(self) -> Result<()> {
+        log::debug!("executing fmt command");
+
+        if self.paths.is_empty() {
+            let mut contents = String::new();
+            std::io::stdin()
+                .read_to_string(&mut contents)
+                .context("failed to read composition from stdin")?;
+
+            let path = Path::new("<stdin>");
+            let formatted = format(path, &contents)?;
+            if self.check {
+                if formatted != contents {
+                    print_diff(path, &contents, &formatted)?;
+                    bail!("composition from stdin is not formatted");
+                }
+
+                return Ok(());
+            }
+
+            std::io::stdout().write_all(formatted.as_bytes())?;
+            return Ok(());
+        }
+
+        let mut unformatted = 0;
+        for path in &self.paths {
+            let contents = fs::read_to_string(path)
+                .with_context(|| format!("failed to read file `{path}`", path = path.display()))?;
+
+            let formatted = format(path, &contents)?;
+            if formatted == contents {
+                continue;
+            }
+
+            if self.check {
+                print_diff(path, &contents, &formatted)?;
+                unformatted += 1;
+                continue;
+            }
+
+            log::debug!("formatting file `{path}`", path = path.display());
+            fs::write(path, formatted)
+                .with_context(|| format!("failed to write file `{path}`", path = path.display()))?;
+        }
+
+        if unformatted > 0 {
+            bail!(
+                "{unformatted} composition file{s} {are} not formatted",
+                s = if unformatted == 1 { "" } else { "s" },
+                are = if unformatted == 1 { "is" } else { "are" }
+            );
+        }
+
+        Ok(())
+    }
+}
+
+fn format(path: &Path, contents: &str) -> Result<String> {
+    // Included documents are formatted separately, so they are not loaded
+    let sources = Sources::new(path, contents);
+    let document = sources.parse().map_err(|e| fmt_err(e, &sources))?;
+
+    let mut formatted = String::new();
+    DocumentPrinter::new(&mut formatted, contents, None)
+        .with_comments()
+        .document(&document)
+        .with_context(|| format!("failed to format file `{path}`", path = path.display()))?;
+
+    Ok(formatted)
+}
+
+fn print_diff(path: &Path, contents: &str, formatted: &str) -> Result<()> {
+    let path = path.display().to_string();
+    let diff = similar::TextDiff::from_lines(contents, formatted);
+    write!(
+        std::io::stdout(),
+        "{diff}",
+        diff = diff.unified_diff().header(&path, &path)
+    )?;
+    Ok(())
+}
@@ -0,0 +1,80 @@
+use super::PackageOptions;
+use crate::{fmt_err, load_sources, Manifest};
+use anyhow::{Context, Result};
+use clap::{Args, ValueEnum};
+use std::{fs, io::Write, path::PathBuf};
+use wac_parser::Composition;
+
+/// The format of an instantiation graph.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum GraphFormat {
+    /// The Graphviz DOT format.
+    Dot,
+    /// A Mermaid flowchart.
+    Mermaid,
+}
+
+/// Prints the instantiation graph of a composition.
+#[derive(Args)]
+#[clap(disable_version_flag = true)]
+pub struct GraphCommand {
+    /// The options used to resolve packages.
+    #[clap(flatten)]
+    pub packages: PackageOptions,
+
+    /// The format of the graph.
+    #[clap(long, short, value_enum, default_value_t = GraphFormat::Dot)]
+    pub format: GraphFormat,
+
+    /// The path to write the output to.
+    ///
+    /// If not specified, the output will be written to stdout.
+    #[clap(long, short = 'o')]
+    pub output: Option<PathBuf>,
+
+    /// The path to the composition file.
+    #[clap(value_name = "PATH")]
+    pub path: PathBuf,
+}
+
+impl GraphCommand {
+    /// Executes the command.
+    pub async fn exec(self) -> Result<()> {
+        log::debug!("executing graph command");
+
+        let sources = load_sources(&self.path)?;
+        let document = sources.parse().map_err(|e| fmt_err(e, &sources))?;
+
+        let manifest = Manifest::find(&self.path)?;
+        let resolver = self.packages.resolver(manifest.as_ref(), &self.path)?;
+
+        let packages = resolver
+            .resolve(&document)
+            .await
+            .map_err(|e| fmt_err(e, &sources))?;
+
+        let resolved =
+            Composition::from_ast(&document, packages).map_err(|e| fmt_err(e, &sources))?;
+
+        let graph = match self.format {
+            GraphFormat::Dot => resolved.to_dot(),
+            GraphFormat::Mermaid => resolved.to_mermaid(),
+        };
+
+        match self.output {
+            Some(path) => {
+                fs::write(&path, graph).context(format!(
+                    "failed to write output file `{path}`",
+                    path = path.display()
+                ))?;
+            }
+            None => {
+                std::io::stdout()
+                    .write_all(graph.as_bytes())
+                    .context("failed to write to stdout")?;
+            }
+        }
+
+        Ok(())
+    }
+}
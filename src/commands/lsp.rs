@@ -0,0 +1,834 @@
+use super::PackageOptions;
+use crate::{lint_levels, DocumentResolver, Manifest};
+use anyhow::{Context, Result};
+use clap::Args;
+use lsp_server::{Connection, Message, Notification, Request, RequestId, Response};
+use lsp_types::{
+    notification::{
+        DidChangeTextDocument, DidChangeWatchedFiles, DidCloseTextDocument, DidOpenTextDocument,
+        Notification as _, PublishDiagnostics,
+    },
+    request::{GotoDefinition, HoverRequest, Request as _},
+    Diagnostic, DiagnosticRelatedInformation, DiagnosticSeverity, DidChangeTextDocumentParams,
+    DidChangeWatchedFilesParams, DidCloseTextDocumentParams, DidOpenTextDocumentParams,
+    GotoDefinitionParams, GotoDefinitionResponse, Hover, HoverContents, HoverParams,
+    HoverProviderCapability, Location, MarkupContent, MarkupKind, NumberOrString, OneOf, Position,
+    PublishDiagnosticsParams, Range, ServerCapabilities, TextDocumentSyncCapability,
+    TextDocumentSyncKind, Url,
+};
+use miette::{Severity, SourceSpan};
+use serde::de::DeserializeOwned;
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    fmt::Write,
+    path::{Path, PathBuf},
+};
+use wac_parser::{
+    ast::{Document, Statement},
+    sources::Sources,
+    Composition, ItemKind,
+};
+
+/// Runs a language server for compositions over stdio.
+///
+/// A relative dependencies directory is relative to the directory of each
+/// composition.
+#[derive(Args)]
+#[clap(disable_version_flag = true)]
+pub struct LspCommand {
+    /// The options used to resolve packages.
+    #[clap(flatten)]
+    pub packages: PackageOptions,
+
+    /// Allows a lint, suppressing its warnings.
+    ///
+    /// Use `warnings` to allow every lint.
+    #[clap(long, value_name = "LINT")]
+    pub allow: Vec<String>,
+
+    /// Denies a lint, reporting its warnings as errors.
+    ///
+    /// Use `warnings` to deny every lint.
+    #[clap(long, value_name = "LINT")]
+    pub deny: Vec<String>,
+}
+
+impl LspCommand {
+    /// Executes the command.
+    pub async fn exec(self) -> Result<()> {
+        log::debug!("executing lsp command");
+
+        let (connection, io_threads) = Connection::stdio();
+        let capabilities = serde_json::to_value(ServerCapabilities {
+            text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+            definition_provider: Some(OneOf::Left(true)),
+            hover_provider: Some(HoverProviderCapability::Simple(true)),
+            ..Default::default()
+        })?;
+
+        connection
+            .initialize(capabilities)
+            .context("failed to initialize the language server")?;
+
+        let mut server = Server {
+            command: self,
+            connection: &connection,
+            documents: Default::default(),
+            resolvers: Default::default(),
+        };
+
+        server.run().await?;
+        drop(connection);
+
+        io_threads
+            .join()
+            .context("failed to join the language server threads")?;
+        Ok(())
+    }
+
+    /// Creates the package resolver for the composition at the given path.
+    ///
+    /// The path is `None` for a composition that is not a file.
+    fn resolver(&self, path: Option<&Path>) -> Result<DocumentResolver> {
+        let manifest = match path {
+            Some(path) => Manifest::find(path)?,
+            None => None,
+        };
+
+        // The dependencies directory is relative to the composition
+        let dir = path.and_then(|p| p.parent());
+        let deps_dir = match (&self.packages.deps_dir, dir) {
+            (Some(deps_dir), Some(dir)) => Some(dir.join(deps_dir)),
+            (Some(deps_dir), None) => Some(deps_dir.clone()),
+            (None, Some(dir)) if manifest.is_none() => Some(dir.join("deps")),
+            (None, _) => None,
+        };
+
+        self.packages.resolver_with_deps_dir(
+            manifest.as_ref(),
+            path.unwrap_or_else(|| Path::new("")),
+            deps_dir,
+        )
+    }
+}
+
+/// Represents the analysis of an open composition.
+struct Analysis {
+    /// The sources of the composition, including any included documents.
+    sources: Sources,
+    /// The resolved references in the composition.
+    ///
+    /// Each entry is the span of the reference and the span of the referenced name.
+    references: Vec<(SourceSpan, SourceSpan)>,
+    /// The hover information for names defined by `let` statements.
+    hovers: Vec<(SourceSpan, String)>,
+}
+
+impl Analysis {
+    /// Gets the span of the name defined at or referenced by the given offset.
+    fn definition(&self, offset: usize) -> Option<SourceSpan> {
+        self.hovers
+            .iter()
+            .map(|(span, _)| (*span, *span))
+            .chain(self.references.iter().copied())
+            .find(|(span, _)| contains(*span, offset))
+            .map(|(_, definition)| definition)
+    }
+}
+
+struct Server<'a> {
+    command: LspCommand,
+    connection: &'a Connection,
+    documents: HashMap<Url, Analysis>,
+    /// The package resolvers, keyed by the directory of the compositions
+    /// they resolve packages for.
+    resolvers: HashMap<Option<PathBuf>, DocumentResolver>,
+}
+
+impl Server<'_> {
+    async fn run(&mut self) -> Result<()> {
+        loop {
+            // Receiving blocks, so wait for messages off of the async runtime
+            let receiver = self.connection.receiver.clone();
+            let message = match tokio::task::spawn_blocking(move || receiver.recv())
+                .await
+                .context("language server receiver panicked")?
+            {
+                Ok(message) => message,
+                Err(_) => break,
+            };
+
+            match message {
+                Message::Request(request) => {
+                    if self.connection.handle_shutdown(&request)? {
+                        return Ok(());
+                    }
+
+                    self.request(request)?;
+                }
+                Message::Notification(notification) => self.notification(notification).await?,
+                Message::Response(_) => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    fn request(&self, request: Request) -> Result<()> {
+        log::debug!("received request `{method}`", method = request.method);
+
+        let Request { id, method, params } = request;
+        let response = match method.as_str() {
+            GotoDefinition::METHOD => match serde_json::from_value(params) {
+                Ok(params) => Response::new_ok(id, self.definition(params)),
+                Err(e) => invalid_params(id, &method, e),
+            },
+            HoverRequest::METHOD => match serde_json::from_value(params) {
+                Ok(params) => Response::new_ok(id, self.hover(params)),
+                Err(e) => invalid_params(id, &method, e),
+            },
+            _ => Response::new_err(
+                id,
+                lsp_server::ErrorCode::MethodNotFound as i32,
+                format!("unsupported request `{method}`"),
+            ),
+        };
+
+        self.connection.sender.send(Message::Response(response))?;
+        Ok(())
+    }
+
+    fn definition(&self, params: GotoDefinitionParams) -> Option<GotoDefinitionResponse> {
+        let uri = params.text_document_position_params.text_document.uri;
+        let position = params.text_document_position_params.position;
+        let analysis = self.documents.get(&uri)?;
+        let offset = offset(analysis.sources.root().text(), position);
+        let definition = analysis.definition(offset)?;
+        Some(GotoDefinitionResponse::Scalar(location(
+            &uri,
+            &analysis.sources,
+            definition,
+        )?))
+    }
+
+    fn hover(&self, params: HoverParams) -> Option<Hover> {
+        let uri = params.text_document_position_params.text_document.uri;
+        let position = params.text_document_position_params.position;
+        let analysis = self.documents.get(&uri)?;
+        let offset = offset(analysis.sources.root().text(), position);
+        let definition = analysis.definition(offset)?;
+        let (_, contents) = analysis
+            .hovers
+            .iter()
+            .find(|(span, _)| *span == definition)?;
+
+        Some(Hover {
+            contents: HoverContents::Markup(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value: contents.clone(),
+            }),
+            range: None,
+        })
+    }
+
+    async fn notification(&mut self, notification: Notification) -> Result<()> {
+        log::debug!(
+            "received notification `{method}`",
+            method = notification.method
+        );
+
+        let Notification { method, params } = notification;
+        match method.as_str() {
+            DidOpenTextDocument::METHOD => {
+                let params: DidOpenTextDocumentParams = match notification_params(&method, params) {
+                    Some(params) => params,
+                    None => return Ok(()),
+                };
+
+                self.analyze(
+                    params.text_document.uri,
+                    params.text_document.text,
+                    Some(params.text_document.version),
+                )
+                .await
+            }
+            DidChangeTextDocument::METHOD => {
+                let mut params: DidChangeTextDocumentParams =
+                    match notification_params(&method, params) {
+                        Some(params) => params,
+                        None => return Ok(()),
+                    };
+
+                // Only full document synchronization is supported
+                match params.content_changes.pop() {
+                    Some(change) => {
+                        self.analyze(
+                            params.text_document.uri,
+                            change.text,
+                            Some(params.text_document.version),
+                        )
+                        .await
+                    }
+                    None => Ok(()),
+                }
+            }
+            DidCloseTextDocument::METHOD => {
+                let params: DidCloseTextDocumentParams = match notification_params(&method, params)
+                {
+                    Some(params) => params,
+                    None => return Ok(()),
+                };
+
+                self.documents.remove(&params.text_document.uri);
+                self.publish(params.text_document.uri, Vec::new(), None)
+            }
+            DidChangeWatchedFiles::METHOD => {
+                let params: DidChangeWatchedFilesParams = match notification_params(&method, params)
+                {
+                    Some(params) => params,
+                    None => return Ok(()),
+                };
+
+                // Packages resolved from changed files are resolved again
+                let changed: Vec<_> = params
+                    .changes
+                    .iter()
+                    .filter_map(|c| c.uri.to_file_path().ok())
+                    .collect();
+                for resolver in self.resolvers.values() {
+                    resolver.invalidate(&changed);
+                }
+
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    async fn analyze(&mut self, uri: Url, source: String, version: Option<i32>) -> Result<()> {
+        log::debug!("analyzing document `{uri}`");
+
+        // Documents included by the composition are read from disk
+        let path = uri
+            .to_file_path()
+            .unwrap_or_else(|_| PathBuf::from(uri.path()));
+        let mut sources = Sources::new(path, source);
+        let mut diagnostics: Vec<miette::Report> = Vec::new();
+        if let Err(e) = sources.load_includes() {
+            diagnostics.push(e.into());
+        }
+
+        let mut references = Vec::new();
+        let mut hover = Vec::new();
+        let mut resolved = false;
+        match sources.parse_recovering() {
+            (Some(document), errors) if errors.is_empty() => {
+                match self.resolve(&uri, &document).await {
+                    Ok(composition) => {
+                        resolved = true;
+                        references = composition.references.clone();
+                        hover = hovers(&document, &composition);
+                        match lint_levels(&self.command.allow, &self.command.deny) {
+                            Ok(levels) => diagnostics.extend(
+                                levels
+                                    .apply_sources(&sources, &composition.warnings)
+                                    .into_iter()
+                                    .map(Into::into),
+                            ),
+                            Err(e) => diagnostics.push(miette::miette!("{e:#}")),
+                        }
+                    }
+                    Err(errors) => diagnostics.extend(errors),
+                }
+            }
+            (_, errors) => diagnostics.extend(errors.into_iter().map(Into::into)),
+        }
+
+        let diagnostics = diagnostics
+            .iter()
+            .map(|d| diagnostic(&uri, &sources, &**d))
+            .collect();
+
+        // Keep the last successful analysis while the composition has errors
+        // so that hover and definition requests work while it is being edited
+        if resolved || !self.documents.contains_key(&uri) {
+            self.documents.insert(
+                uri.clone(),
+                Analysis {
+                    sources,
+                    references,
+                    hovers: hover,
+                },
+            );
+        }
+
+        self.publish(uri, diagnostics, version)
+    }
+
+    /// Resolves the given document.
+    ///
+    /// A package resolver is created the first time a composition in a
+    /// directory is resolved and is reused for the compositions in the same
+    /// directory; resolved packages are cached so that they are not resolved
+    /// again on every change to a composition.
+    async fn resolve<'a>(
+        &mut self,
+        uri: &Url,
+        document: &'a Document<'a>,
+    ) -> Result<Composition, Vec<miette::Report>> {
+        let path = uri.to_file_path().ok();
+        let dir = path
+            .as_deref()
+            .and_then(Path::parent)
+            .map(Path::to_path_buf);
+        let resolver = match self.resolvers.entry(dir) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(
+                self.command
+                    .resolver(path.as_deref())
+                    .map_err(|e| vec![miette::miette!("{e:#}")])?
+                    .with_cache(),
+            ),
+        };
+
+        let packages = resolver
+            .resolve(document)
+            .await
+            .map_err(|e| vec![e.into()])?;
+
+        Composition::from_ast_recovering(document, packages)
+            .map_err(|errors| errors.into_iter().map(Into::into).collect())
+    }
+
+    fn publish(&self, uri: Url, diagnostics: Vec<Diagnostic>, version: Option<i32>) -> Result<()> {
+        self.connection
+            .sender
+            .send(Message::Notification(Notification::new(
+                PublishDiagnostics::METHOD.to_string(),
+                PublishDiagnosticsParams {
+                    uri,
+                    diagnostics,
+                    version,
+                },
+            )))?;
+        Ok(())
+    }
+}
+
+/// Creates an error response for a request with invalid parameters.
+fn invalid_params(id: RequestId, method: &str, e: serde_json::Error) -> Response {
+    Response::new_err(
+        id,
+        lsp_server::ErrorCode::InvalidParams as i32,
+        format!("invalid parameters for request `{method}`: {e}"),
+    )
+}
+
+/// Deserializes the parameters of a notification.
+///
+/// Invalid parameters are logged and the notification is ignored as
+/// notifications cannot be responded to.
+fn notification_params<T: DeserializeOwned>(method: &str, params: serde_json::Value) -> Option<T> {
+    match serde_json::from_value(params) {
+        Ok(params) => Some(params),
+        Err(e) => {
+            log::error!("invalid parameters for notification `{method}`: {e}");
+            None
+        }
+    }
+}
+
+/// Converts a miette diagnostic into a LSP diagnostic.
+///
+/// Labels in documents included by the composition are reported as related
+/// information as the diagnostic's range must be in the composition itself.
+fn diagnostic(uri: &Url, sources: &Sources, diagnostic: &dyn miette::Diagnostic) -> Diagnostic {
+    let mut labels: Vec<_> = diagnostic.labels().into_iter().flatten().collect();
+    let primary = labels.iter().position(|l| l.primary()).unwrap_or_default();
+    let primary = (!labels.is_empty())
+        .then(|| labels.remove(primary))
+        .and_then(|l| {
+            if l.inner().offset() <= sources.root().text().len() {
+                Some(l)
+            } else {
+                labels.insert(0, l);
+                None
+            }
+        });
+
+    let mut message = diagnostic.to_string();
+    if let Some(help) = diagnostic.help() {
+        write!(message, "\n\nhelp: {help}").unwrap();
+    }
+
+    Diagnostic {
+        range: primary
+            .map(|l| range(sources.root().text(), *l.inner()))
+            .unwrap_or_default(),
+        severity: Some(match diagnostic.severity().unwrap_or(Severity::Error) {
+            Severity::Advice => DiagnosticSeverity::HINT,
+            Severity::Warning => DiagnosticSeverity::WARNING,
+            Severity::Error => DiagnosticSeverity::ERROR,
+        }),
+        code: diagnostic
+            .code()
+            .map(|c| NumberOrString::String(c.to_string())),
+        source: Some("wac".to_string()),
+        message,
+        related_information: (!labels.is_empty()).then(|| {
+            labels
+                .into_iter()
+                .filter_map(|l| {
+                    Some(DiagnosticRelatedInformation {
+                        location: location(uri, sources, *l.inner())?,
+                        message: l.label().unwrap_or_default().to_string(),
+                    })
+                })
+                .collect()
+        }),
+        ..Default::default()
+    }
+}
+
+/// Gets the hover information for the names defined by `let` statements.
+fn hovers(document: &Document, composition: &Composition) -> Vec<(SourceSpan, String)> {
+    document
+        .statements
+        .iter()
+        .filter_map(|stmt| match stmt {
+            Statement::Let(stmt) => Some(vec![&stmt.id]),
+            Statement::Destructure(stmt) => Some(stmt.ids().collect()),
+            _ => None,
+        })
+        .flatten()
+        .filter_map(|id| {
+            let (item, _) = composition.names.get(id.string)?;
+            Some((
+                id.span,
+                describe(composition, id.string, composition.items[*item].kind()),
+            ))
+        })
+        .collect()
+}
+
+/// Describes the given item kind as markdown.
+fn describe(composition: &Composition, name: &str, kind: ItemKind) -> String {
+    let definitions = &composition.definitions;
+    let mut s = format!(
+        "```wac\nlet {name}: {kind}\n```",
+        kind = kind.as_str(definitions)
+    );
+
+    let exports = match kind {
+        ItemKind::Instance(id) => Some(&definitions.interfaces[id].exports),
+        ItemKind::Instantiation(id) => {
+            Some(&definitions.worlds[composition.packages[id].world].exports)
+        }
+        ItemKind::Component(id) => Some(&definitions.worlds[id].exports),
+        _ => None,
+    };
+
+    if let Some(exports) = exports.filter(|e| !e.is_empty()) {
+        s.push_str("\n\nExports:\n");
+        for (name, kind) in exports {
+            writeln!(s, "- `{name}`: {kind}", kind = kind.as_str(definitions)).unwrap();
+        }
+    }
+
+    if let ItemKind::Instantiation(id) = kind {
+        let package = &composition.packages[id];
+        write!(
+            s,
+            "\n\nInstantiation of package `{name}`",
+            name = package.name
+        )
+        .unwrap();
+        if let Some(version) = &package.version {
+            write!(s, " (version `{version}`)").unwrap();
+        }
+    }
+
+    s
+}
+
+fn contains(span: SourceSpan, offset: usize) -> bool {
+    offset >= span.offset() && offset <= span.offset() + span.len()
+}
+
+/// Converts a LSP position into a byte offset in the given source.
+fn offset(source: &str, position: Position) -> usize {
+    let mut offset = 0;
+    for (i, line) in source.split_inclusive('\n').enumerate() {
+        if i == position.line as usize {
+            let mut units = 0;
+            for (index, c) in line.char_indices() {
+                if units >= position.character as usize {
+                    return offset + index;
+                }
+
+                units += c.len_utf16();
+            }
+
+            return offset + line.trim_end_matches('\n').len();
+        }
+
+        offset += line.len();
+    }
+
+    offset
+}
+
+/// Converts a byte offset in the given source into a LSP position.
+fn position(source: &str, offset: usize) -> Position {
+    let offset = offset.min(source.len());
+    let before = &source[..offset];
+    let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+    Position {
+        line: before.matches('\n').count() as u32,
+        character: before[line_start..].encode_utf16().count() as u32,
+    }
+}
+
+/// Gets the LSP location of a span in the sources of the composition with the given URI.
+fn location(uri: &Url, sources: &Sources, span: SourceSpan) -> Option<Location> {
+    let file = sources.file(span.offset())?;
+    let uri = if file.offset() == 0 {
+        uri.clone()
+    } else {
+        Url::from_file_path(file.path()).ok()?
+    };
+
+    Some(Location {
+        uri,
+        range: range(
+            file.text(),
+            SourceSpan::new((span.offset() - file.offset()).into(), span.len().into()),
+        ),
+    })
+}
+
+/// Converts a span in the given source into a LSP range.
+fn range(source: &str, span: SourceSpan) -> Range {
+    Range {
+        start: position(source, span.offset()),
+        end: position(source, span.offset() + span.len()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // `é` is two bytes and one UTF-16 code unit; `𝄞` is four bytes and
+    // two UTF-16 code units
+    const SOURCE: &str = "a = \"é𝄞\";\nb\n";
+
+    fn pos(line: u32, character: u32) -> Position {
+        Position { line, character }
+    }
+
+    #[test]
+    fn it_converts_positions_to_offsets() {
+        assert_eq!(offset(SOURCE, pos(0, 0)), 0);
+        assert_eq!(offset(SOURCE, pos(0, 5)), 5);
+        assert_eq!(offset(SOURCE, pos(0, 6)), 7);
+        assert_eq!(offset(SOURCE, pos(0, 8)), 11);
+        assert_eq!(offset(SOURCE, pos(1, 0)), 14);
+
+        // A position within a surrogate pair is rounded up to the next character
+        assert_eq!(offset(SOURCE, pos(0, 7)), 11);
+
+        // Positions past the end of a line or the source are clamped
+        assert_eq!(offset(SOURCE, pos(0, 100)), 13);
+        assert_eq!(offset(SOURCE, pos(5, 0)), SOURCE.len());
+    }
+
+    #[test]
+    fn it_converts_offsets_to_positions() {
+        assert_eq!(position(SOURCE, 0), pos(0, 0));
+        assert_eq!(position(SOURCE, 5), pos(0, 5));
+        assert_eq!(position(SOURCE, 7), pos(0, 6));
+        assert_eq!(position(SOURCE, 11), pos(0, 8));
+        assert_eq!(position(SOURCE, 13), pos(0, 10));
+        assert_eq!(position(SOURCE, 14), pos(1, 0));
+        assert_eq!(position(SOURCE, 100), pos(2, 0));
+
+        // Positions round trip through offsets
+        for offset in [0, 5, 7, 11, 14, 16] {
+            assert_eq!(super::offset(SOURCE, position(SOURCE, offset)), offset);
+        }
+    }
+
+    #[test]
+    fn it_converts_spans_to_ranges() {
+        assert_eq!(
+            range(SOURCE, SourceSpan::new(5.into(), 6.into())),
+            Range {
+                start: pos(0, 5),
+                end: pos(0, 8),
+            }
+        );
+        assert_eq!(
+            range(SOURCE, SourceSpan::new(11.into(), 4.into())),
+            Range {
+                start: pos(0, 8),
+                end: pos(1, 1),
+            }
+        );
+    }
+
+    fn command() -> LspCommand {
+        LspCommand {
+            packages: PackageOptions {
+                deps_dir: None,
+                deps: Vec::new(),
+                #[cfg(feature = "registry")]
+                registry: None,
+                #[cfg(feature = "registry")]
+                locked: false,
+                #[cfg(feature = "registry")]
+                offline: false,
+            },
+            allow: Vec::new(),
+            deny: Vec::new(),
+        }
+    }
+
+    /// Receives the diagnostics published to the client.
+    fn diagnostics(client: &Connection) -> Result<Vec<Diagnostic>> {
+        match client.receiver.recv()? {
+            Message::Notification(n) if n.method == PublishDiagnostics::METHOD => {
+                Ok(serde_json::from_value::<PublishDiagnosticsParams>(n.params)?.diagnostics)
+            }
+            message => anyhow::bail!("unexpected message: {message:?}"),
+        }
+    }
+
+    /// Sends a request to the server and receives its response.
+    fn request<R: lsp_types::request::Request>(
+        server: &Server,
+        client: &Connection,
+        params: R::Params,
+    ) -> Result<R::Result> {
+        server.request(Request::new(1.into(), R::METHOD.to_string(), params))?;
+        match client.receiver.recv()? {
+            Message::Response(response) => {
+                Ok(serde_json::from_value(response.result.unwrap_or_default())?)
+            }
+            message => anyhow::bail!("unexpected message: {message:?}"),
+        }
+    }
+
+    fn position_params(uri: &Url, position: Position) -> lsp_types::TextDocumentPositionParams {
+        lsp_types::TextDocumentPositionParams {
+            text_document: lsp_types::TextDocumentIdentifier { uri: uri.clone() },
+            position,
+        }
+    }
+
+    #[tokio::test]
+    async fn it_responds_to_requests() -> Result<()> {
+        let dir = tempdir::TempDir::new("test")?;
+        let uri = Url::from_file_path(dir.path().join("test.wac")).unwrap();
+        let (connection, client) = Connection::memory();
+        let mut server = Server {
+            command: command(),
+            connection: &connection,
+            documents: Default::default(),
+            resolvers: Default::default(),
+        };
+
+        server
+            .notification(Notification::new(
+                DidOpenTextDocument::METHOD.to_string(),
+                DidOpenTextDocumentParams {
+                    text_document: lsp_types::TextDocumentItem {
+                        uri: uri.clone(),
+                        language_id: "wac".to_string(),
+                        version: 1,
+                        text: "package test:comp;\n\nimport f: func();\nlet x = f;\nexport x;\n"
+                            .to_string(),
+                    },
+                },
+            ))
+            .await?;
+        assert_eq!(diagnostics(&client)?, Vec::new());
+
+        let hover = |server: &Server| {
+            request::<HoverRequest>(
+                server,
+                &client,
+                HoverParams {
+                    text_document_position_params: position_params(&uri, pos(3, 4)),
+                    work_done_progress_params: Default::default(),
+                },
+            )
+        };
+        let expected = Some(Hover {
+            contents: HoverContents::Markup(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value: "```wac\nlet x: function\n```".to_string(),
+            }),
+            range: None,
+        });
+        assert_eq!(hover(&server)?, expected);
+
+        assert_eq!(
+            request::<GotoDefinition>(
+                &server,
+                &client,
+                GotoDefinitionParams {
+                    text_document_position_params: position_params(&uri, pos(4, 7)),
+                    work_done_progress_params: Default::default(),
+                    partial_result_params: Default::default(),
+                },
+            )?,
+            Some(GotoDefinitionResponse::Scalar(Location {
+                uri: uri.clone(),
+                range: Range {
+                    start: pos(3, 4),
+                    end: pos(3, 5),
+                },
+            }))
+        );
+
+        // Errors are published and the last successful analysis is kept
+        server
+            .notification(Notification::new(
+                DidChangeTextDocument::METHOD.to_string(),
+                DidChangeTextDocumentParams {
+                    text_document: lsp_types::VersionedTextDocumentIdentifier {
+                        uri: uri.clone(),
+                        version: 2,
+                    },
+                    content_changes: vec![lsp_types::TextDocumentContentChangeEvent {
+                        range: None,
+                        range_length: None,
+                        text: "package test:comp;\n\nimport f: func();\nlet x = f;\nexport y;\n"
+                            .to_string(),
+                    }],
+                },
+            ))
+            .await?;
+
+        let diagnostics = diagnostics(&client)?;
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].code,
+            Some(NumberOrString::String(
+                "wac::resolve::undefined_name".to_string()
+            ))
+        );
+        assert_eq!(diagnostics[0].message, "undefined name `y`");
+        assert_eq!(
+            diagnostics[0].range,
+            Range {
+                start: pos(4, 7),
+                end: pos(4, 8),
+            }
+        );
+        assert_eq!(hover(&server)?, expected);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,92 @@
+#[cfg(feature = "registry")]
+use crate::lock_file_path;
+use crate::{DocumentResolver, Manifest};
+use anyhow::{Context, Result};
+use clap::Args;
+use std::path::{Path, PathBuf};
+
+/// Parses a `KEY=VALUE` command line argument.
+pub(crate) fn parse<T, U>(s: &str) -> Result<(T, U)>
+where
+    T: std::str::FromStr,
+    T::Err: Into<anyhow::Error>,
+    U: std::str::FromStr,
+    U::Err: Into<anyhow::Error>,
+{
+    let (k, v) = s.split_once('=').context("value does not contain `=`")?;
+
+    Ok((
+        k.trim().parse().map_err(Into::into)?,
+        v.trim().parse().map_err(Into::into)?,
+    ))
+}
+
+/// The options used to resolve the packages referenced by compositions.
+#[derive(Args)]
+pub struct PackageOptions {
+    /// The directory to search for package dependencies.
+    ///
+    /// Defaults to the directory declared in `wac.toml`, or `deps` if there is none.
+    #[clap(long, value_name = "PATH")]
+    pub deps_dir: Option<PathBuf>,
+
+    /// A local path for a package dependency.
+    #[clap(long = "dep", short, value_name = "PKG=PATH", value_parser = parse::<String, PathBuf>)]
+    pub deps: Vec<(String, PathBuf)>,
+
+    /// The URL of the registry to use.
+    #[cfg(feature = "registry")]
+    #[clap(long, value_name = "URL")]
+    pub registry: Option<String>,
+
+    /// Require that the lock file is up to date.
+    ///
+    /// The lock file is `wac.lock` in the directory of `wac.toml`, or of
+    /// the composition if there is no manifest; resolution fails if any
+    /// package would be added to it.
+    #[cfg(feature = "registry")]
+    #[clap(long)]
+    pub locked: bool,
+
+    /// Resolve registry packages without network access.
+    ///
+    /// Only package logs and content previously downloaded from the
+    /// registry are used; resolution fails for any package that is missing.
+    #[cfg(feature = "registry")]
+    #[clap(long)]
+    pub offline: bool,
+}
+
+impl PackageOptions {
+    /// Creates the package resolver for the composition at the given path.
+    ///
+    /// The manifest of the composition, if any, provides the defaults for
+    /// the options and the location of the lock file.
+    pub fn resolver(&self, manifest: Option<&Manifest>, path: &Path) -> Result<DocumentResolver> {
+        self.resolver_with_deps_dir(manifest, path, self.deps_dir.clone())
+    }
+
+    /// Creates the package resolver for the composition at the given path
+    /// using the given dependencies directory rather than `--deps-dir`.
+    pub fn resolver_with_deps_dir(
+        &self,
+        manifest: Option<&Manifest>,
+        #[cfg_attr(not(feature = "registry"), allow(unused_variables))] path: &Path,
+        deps_dir: Option<PathBuf>,
+    ) -> Result<DocumentResolver> {
+        let resolver = DocumentResolver::new_with_manifest(
+            manifest,
+            deps_dir,
+            self.deps.iter().cloned().collect(),
+            #[cfg(feature = "registry")]
+            self.registry.as_deref(),
+        )?;
+
+        #[cfg(feature = "registry")]
+        let resolver = resolver
+            .with_lock_file(lock_file_path(manifest, path), self.locked)?
+            .with_offline(self.offline);
+
+        Ok(resolver)
+    }
+}
@@ -1,8 +1,7 @@
-use crate::fmt_err;
-use anyhow::{Context, Result};
+use crate::{fmt_err, load_sources};
+use anyhow::Result;
 use clap::Args;
-use std::{fs, path::PathBuf};
-use wac_parser::ast::Document;
+use std::path::PathBuf;
 
 /// Parses a composition into a JSON AST representation.
 #[derive(Args)]
@@ -18,10 +17,8 @@ impl ParseCommand {
     pub async fn exec(self) -> Result<()> {
         log::debug!("executing parse command");
 
-        let contents = fs::read_to_string(&self.path)
-            .with_context(|| format!("failed to read file `{path}`", path = self.path.display()))?;
-
-        let document = Document::parse(&contents).map_err(|e| fmt_err(e, &self.path, &contents))?;
+        let sources = load_sources(&self.path)?;
+        let document = sources.parse().map_err(|e| fmt_err(e, &sources))?;
 
         serde_json::to_writer_pretty(std::io::stdout(), &document)?;
         println!();
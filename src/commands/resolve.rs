@@ -1,40 +1,17 @@
-use crate::{fmt_err, PackageResolver};
-use anyhow::{Context, Result};
+use super::PackageOptions;
+use crate::{fmt_err, load_sources, Manifest};
+use anyhow::Result;
 use clap::Args;
-use std::{fs, path::PathBuf};
-use wac_parser::{ast::Document, Composition};
-
-fn parse<T, U>(s: &str) -> Result<(T, U)>
-where
-    T: std::str::FromStr,
-    T::Err: Into<anyhow::Error>,
-    U: std::str::FromStr,
-    U::Err: Into<anyhow::Error>,
-{
-    let (k, v) = s.split_once('=').context("value does not contain `=`")?;
-
-    Ok((
-        k.trim().parse().map_err(Into::into)?,
-        v.trim().parse().map_err(Into::into)?,
-    ))
-}
+use std::path::PathBuf;
+use wac_parser::Composition;
 
 /// Resolves a composition into a JSON representation.
 #[derive(Args)]
 #[clap(disable_version_flag = true)]
 pub struct ResolveCommand {
-    /// The directory to search for package dependencies.
-    #[clap(long, value_name = "PATH", default_value = "deps")]
-    pub deps_dir: PathBuf,
-
-    /// The directory to search for package dependencies.
-    #[clap(long = "dep", short, value_name = "PKG=PATH", value_parser = parse::<String, PathBuf>)]
-    pub deps: Vec<(String, PathBuf)>,
-
-    /// The URL of the registry to use.
-    #[cfg(feature = "registry")]
-    #[clap(long, value_name = "URL")]
-    pub registry: Option<String>,
+    /// The options used to resolve packages.
+    #[clap(flatten)]
+    pub packages: PackageOptions,
 
     /// The path to the composition file.
     #[clap(value_name = "PATH")]
@@ -46,25 +23,19 @@ impl ResolveCommand {
     pub async fn exec(self) -> Result<()> {
         log::debug!("executing resolve command");
 
-        let contents = fs::read_to_string(&self.path)
-            .with_context(|| format!("failed to read file `{path}`", path = self.path.display()))?;
-
-        let document = Document::parse(&contents).map_err(|e| fmt_err(e, &self.path, &contents))?;
+        let sources = load_sources(&self.path)?;
+        let document = sources.parse().map_err(|e| fmt_err(e, &sources))?;
 
-        let resolver = PackageResolver::new(
-            self.deps_dir,
-            self.deps.into_iter().collect(),
-            #[cfg(feature = "registry")]
-            self.registry.as_deref(),
-        )?;
+        let manifest = Manifest::find(&self.path)?;
+        let resolver = self.packages.resolver(manifest.as_ref(), &self.path)?;
 
         let packages = resolver
             .resolve(&document)
             .await
-            .map_err(|e| fmt_err(e, &self.path, &contents))?;
+            .map_err(|e| fmt_err(e, &sources))?;
 
-        let resolved = Composition::from_ast(&document, packages)
-            .map_err(|e| fmt_err(e, &self.path, &contents))?;
+        let resolved =
+            Composition::from_ast(&document, packages).map_err(|e| fmt_err(e, &sources))?;
 
         serde_json::to_writer_pretty(std::io::stdout(), &resolved)?;
         println!();
@@ -0,0 +1,66 @@
+use anyhow::{bail, Context, Result};
+use clap::Args;
+use std::{fs, path::PathBuf};
+use wac_parser::source_map::{ItemSort, SourceMap};
+
+/// Maps the items of an encoded composition back to their WAC source.
+///
+/// The composition must have been encoded with `wac encode --source-map`.
+#[derive(Args)]
+#[clap(disable_version_flag = true)]
+pub struct SymbolizeCommand {
+    /// The path to the encoded WebAssembly component.
+    #[clap(value_name = "PATH")]
+    pub path: PathBuf,
+
+    /// The sort of the item to symbolize.
+    ///
+    /// The supported sorts are `func`, `value`, `type`, `instance`,
+    /// `component`, and `module`.
+    ///
+    /// If not specified, every item in the source map is printed.
+    #[clap(value_name = "SORT", requires = "index")]
+    pub sort: Option<ItemSort>,
+
+    /// The index of the item in the index space of its sort.
+    #[clap(value_name = "INDEX")]
+    pub index: Option<u32>,
+}
+
+impl SymbolizeCommand {
+    /// Executes the command.
+    pub async fn exec(self) -> Result<()> {
+        log::debug!("executing symbolize command");
+
+        let bytes = fs::read(&self.path)
+            .with_context(|| format!("failed to read file `{path}`", path = self.path.display()))?;
+
+        let map = SourceMap::from_component(&bytes)
+            .with_context(|| {
+                format!(
+                    "failed to read the source map of component `{path}`",
+                    path = self.path.display()
+                )
+            })?
+            .with_context(|| {
+                format!(
+                    "component `{path}` does not contain a source map; encode it with `--source-map`",
+                    path = self.path.display()
+                )
+            })?;
+
+        match (self.sort, self.index) {
+            (Some(sort), Some(index)) => match map.lookup(sort, index) {
+                Some(location) => println!("{location}"),
+                None => bail!("{sort} index {index} is not in the source map"),
+            },
+            _ => {
+                for (sort, index, location) in map.items() {
+                    println!("{sort} {index}: {location}");
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
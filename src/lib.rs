@@ -2,24 +2,36 @@
 
 #![deny(missing_docs)]
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use indexmap::IndexMap;
-use miette::{GraphicalReportHandler, GraphicalTheme, NamedSource, Report};
+use miette::{GraphicalReportHandler, GraphicalTheme, Report, SourceSpan};
+use semver::{Version, VersionReq};
+#[cfg(feature = "registry")]
+use std::collections::HashSet;
 use std::{
     collections::HashMap,
     io::IsTerminal,
     path::{Path, PathBuf},
-    sync::Arc,
+    sync::{Arc, Mutex},
+};
+use wac_parser::{
+    ast::Document, sources::Sources, LintLevel, LintLevels, PackageKey, ResolvedPackage,
+};
+#[cfg(feature = "registry")]
+use wac_resolver::OwnedPackageKey;
+use wac_resolver::{
+    packages, ChainResolver, Error, FileSystemPackageResolver, PackageResolver as _,
 };
-use wac_parser::{ast::Document, PackageKey};
-use wac_resolver::{packages, Error, FileSystemPackageResolver};
 
 pub mod commands;
+mod manifest;
 
 #[cfg(feature = "registry")]
 mod progress;
 
-fn fmt_err(e: impl Into<Report>, path: &Path, source: &str) -> anyhow::Error {
+pub use manifest::*;
+
+fn fmt_err(e: impl Into<Report>, sources: &Sources) -> anyhow::Error {
     let mut s = String::new();
     let e = e.into();
     GraphicalReportHandler::new()
@@ -29,28 +41,73 @@ fn fmt_err(e: impl Into<Report>, path: &Path, source: &str) -> anyhow::Error {
         } else {
             GraphicalTheme::unicode_nocolor()
         })
-        .render_report(
-            &mut s,
-            e.with_source_code(NamedSource::new(path.to_string_lossy(), source.to_string()))
-                .as_ref(),
-        )
+        .render_report(&mut s, e.with_source_code(sources.clone()).as_ref())
         .expect("failed to render diagnostic");
     anyhow::Error::msg(s)
 }
 
+/// Reads the composition at the given path and the documents it includes.
+fn load_sources(path: &Path) -> Result<Sources> {
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("failed to read file `{path}`", path = path.display()))?;
+
+    let mut sources = Sources::new(path, contents);
+    sources.load_includes().map_err(|e| fmt_err(e, &sources))?;
+    Ok(sources)
+}
+
+/// Gets the path of the lock file for the composition at the given path.
+///
+/// The lock file is next to the manifest if there is one; otherwise it is
+/// next to the composition.
+#[cfg(feature = "registry")]
+fn lock_file_path(manifest: Option<&Manifest>, path: &Path) -> PathBuf {
+    match manifest {
+        Some(manifest) => manifest.dir().join(wac_resolver::LockFile::FILE_NAME),
+        None => path.with_file_name(wac_resolver::LockFile::FILE_NAME),
+    }
+}
+
+/// Gets the lint levels from the lints allowed and denied on the command line.
+///
+/// Denied lints take precedence over allowed lints.
+fn lint_levels(allow: &[String], deny: &[String]) -> Result<LintLevels> {
+    let mut levels = LintLevels::default();
+    for name in allow {
+        levels.set_by_name(name, LintLevel::Allow)?;
+    }
+
+    for name in deny {
+        levels.set_by_name(name, LintLevel::Deny)?;
+    }
+
+    Ok(levels)
+}
+
+/// Makes the given path absolute by joining it to the current directory.
+fn absolute(path: &Path) -> PathBuf {
+    std::env::current_dir()
+        .map(|dir| dir.join(path))
+        .unwrap_or_else(|_| path.to_path_buf())
+}
+
 /// Represents a package resolver used to resolve packages
 /// referenced from a document.
 ///
 /// The resolver first checks the file system for a matching package.
 ///
 /// If it cannot find a matching package, it will check the registry.
-pub struct PackageResolver {
-    fs: FileSystemPackageResolver,
+pub struct DocumentResolver {
+    fs: Arc<FileSystemPackageResolver>,
+    #[cfg(feature = "registry")]
+    overrides: HashSet<String>,
+    versions: HashMap<String, Version>,
     #[cfg(feature = "registry")]
-    registry: wac_resolver::RegistryPackageResolver,
+    registries: RegistriesResolver,
+    cache: Option<Mutex<HashMap<CacheKey, ResolvedPackage>>>,
 }
 
-impl PackageResolver {
+impl DocumentResolver {
     /// Creates a new package resolver.
     pub fn new(
         dir: impl Into<PathBuf>,
@@ -58,45 +115,511 @@ impl PackageResolver {
         #[cfg(feature = "registry")] registry: Option<&str>,
     ) -> Result<Self> {
         Ok(Self {
-            fs: FileSystemPackageResolver::new(dir, overrides, false),
             #[cfg(feature = "registry")]
-            registry: wac_resolver::RegistryPackageResolver::new(
-                registry,
-                Some(Box::new(progress::ProgressBar::new())),
-            )?,
+            overrides: overrides.keys().cloned().collect(),
+            fs: Arc::new(FileSystemPackageResolver::new(dir, overrides, false)),
+            versions: Default::default(),
+            #[cfg(feature = "registry")]
+            registries: RegistriesResolver {
+                default: registry.map(ToOwned::to_owned),
+                registries: Default::default(),
+                lock: None,
+                offline: false,
+                requested: Default::default(),
+            },
+            cache: None,
         })
     }
 
+    /// Creates a new package resolver configured by an optional manifest.
+    ///
+    /// The given dependencies directory, overrides, and registry take
+    /// precedence over those declared in the manifest.
+    ///
+    /// If no dependencies directory is specified, `deps` is used.
+    pub fn new_with_manifest(
+        manifest: Option<&Manifest>,
+        dir: Option<PathBuf>,
+        mut overrides: HashMap<String, PathBuf>,
+        #[cfg(feature = "registry")] registry: Option<&str>,
+    ) -> Result<Self> {
+        let dir = dir
+            .or_else(|| manifest.map(Manifest::deps_dir))
+            .unwrap_or_else(|| "deps".into());
+
+        let mut versions = HashMap::new();
+        if let Some(manifest) = manifest {
+            for (name, path) in manifest.overrides() {
+                overrides.entry(name).or_insert(path);
+            }
+
+            versions = manifest.versions();
+        }
+
+        // Packages with local path overrides are not pinned
+        versions.retain(|name, _| !overrides.contains_key(name));
+
+        #[cfg(feature = "registry")]
+        let registry = registry.or_else(|| manifest.and_then(|m| m.registry.as_deref()));
+
+        let resolver = Self::new(
+            dir,
+            overrides,
+            #[cfg(feature = "registry")]
+            registry,
+        )?
+        .with_versions(versions);
+
+        #[cfg(feature = "registry")]
+        let resolver = match manifest {
+            Some(manifest) => resolver.with_registries(manifest.registries()),
+            None => resolver,
+        };
+
+        Ok(resolver)
+    }
+
+    /// Pins packages referenced without a version to the given versions.
+    ///
+    /// The map is from package name to the pinned version.
+    pub fn with_versions(mut self, versions: HashMap<String, Version>) -> Self {
+        self.versions = versions;
+        self
+    }
+
+    /// Uses specific registries for the given namespaces or packages.
+    ///
+    /// The map is keyed by either a namespace or a package name; a package
+    /// name takes precedence over its namespace.
+    #[cfg(feature = "registry")]
+    pub fn with_registries(mut self, registries: HashMap<String, String>) -> Self {
+        self.registries.registries = registries;
+        self
+    }
+
+    /// Uses the lock file at the given path for packages resolved from the registry.
+    ///
+    /// If `locked` is `true`, resolution fails if the lock file would change.
+    #[cfg(feature = "registry")]
+    pub fn with_lock_file(mut self, path: impl Into<PathBuf>, locked: bool) -> Result<Self> {
+        let path = path.into();
+
+        // Ensure the lock file can be read before resolving
+        wac_resolver::LockFile::open(&path)?;
+
+        self.registries.lock = Some((path, locked));
+        Ok(self)
+    }
+
+    /// Sets whether packages are resolved from the registry without network access.
+    ///
+    /// When offline, only package logs and content that were previously
+    /// downloaded are used.
+    #[cfg(feature = "registry")]
+    pub fn with_offline(mut self, offline: bool) -> Self {
+        self.registries.offline = offline;
+        self
+    }
+
+    /// Caches resolved packages in memory.
+    ///
+    /// Cached packages are not resolved again until they are invalidated
+    /// with [`DocumentResolver::invalidate`].
+    pub fn with_cache(mut self) -> Self {
+        self.cache = Some(Default::default());
+        self
+    }
+
+    /// Invalidates the cached packages resolved from the given changed paths.
+    ///
+    /// A package is invalidated if any path it depends on changed, such as
+    /// a package of a composition or a foreign dependency of a WIT package.
+    ///
+    /// The paths are expected to be absolute.
+    pub fn invalidate(&self, changed: &[PathBuf]) {
+        if let Some(cache) = &self.cache {
+            cache
+                .lock()
+                .unwrap()
+                .retain(|(name, version, requirement), _| {
+                    let paths = self.fs.paths(&PackageKey {
+                        name,
+                        version: version.as_ref(),
+                        requirement: requirement.as_ref(),
+                    });
+                    !paths.iter().any(|path| {
+                        let path = absolute(path);
+                        changed.iter().any(|p| p.starts_with(&path))
+                    })
+                });
+        }
+    }
+
+    /// Gets the absolute local paths that the packages referenced in the
+    /// given document are resolved from, including the paths they depend on.
+    ///
+    /// A path is returned for each package even if it does not exist, as
+    /// the package may instead be resolved from a registry.
+    pub fn resolved_paths(&self, document: &Document) -> Result<Vec<PathBuf>, Error> {
+        Ok(packages(document)?
+            .keys()
+            .flat_map(|key| self.fs.paths(&self.pin(*key)))
+            .map(|path| absolute(&path))
+            .collect())
+    }
+
     /// Resolve all packages referenced in the given document.
     pub async fn resolve<'a>(
         &self,
         document: &'a Document<'a>,
-    ) -> Result<IndexMap<PackageKey<'a>, Arc<Vec<u8>>>, Error> {
-        let mut keys = packages(document)?;
+    ) -> Result<IndexMap<PackageKey<'a>, ResolvedPackage>, Error> {
+        let keys = packages(document)?;
 
-        // Next, we resolve as many of the packages from the file system as possible
-        // and filter out the ones that were resolved.
-        #[allow(unused_mut)]
-        let mut packages = self.fs.resolve(&keys)?;
-        keys.retain(|key, _| !packages.contains_key(key));
+        // Apply any pinned versions to the keys to resolve
+        let mut pinned: IndexMap<PackageKey, SourceSpan> = keys
+            .iter()
+            .map(|(key, span)| (self.pin(*key), *span))
+            .collect();
 
-        // Next resolve the remaining packages from the registry
-        // The registry resolver will error on missing package
-        #[cfg(feature = "registry")]
-        if !keys.is_empty() {
-            let reg_packages = self.registry.resolve(&keys).await?;
-            keys.retain(|key, _| !reg_packages.contains_key(key));
-            packages.extend(reg_packages);
+        // First, use any cached packages
+        let mut packages = IndexMap::new();
+        if let Some(cache) = &self.cache {
+            let cache = cache.lock().unwrap();
+            for key in pinned.keys() {
+                if let Some(package) = cache.get(&cache_key(key)) {
+                    packages.insert(*key, package.clone());
+                }
+            }
+
+            pinned.retain(|key, _| !packages.contains_key(key));
         }
 
+        // Next, resolve the remaining packages with the chain of resolvers
+        // and filter out the ones that were resolved.
+        packages.extend(self.chain().resolve(&pinned).await?);
+        pinned.retain(|key, _| !packages.contains_key(key));
+
         // At this point keys should be empty, otherwise we have an unknown package
-        if let Some((key, span)) = keys.first() {
+        if let Some((key, span)) = pinned.first() {
             return Err(Error::UnknownPackage {
                 name: key.name.to_string(),
                 span: *span,
             });
         }
 
+        #[cfg(feature = "registry")]
+        self.registries.prune_lock_file()?;
+
+        if let Some(cache) = &self.cache {
+            cache.lock().unwrap().extend(
+                packages
+                    .iter()
+                    .map(|(key, package)| (cache_key(key), package.clone())),
+            );
+        }
+
+        Ok(keys
+            .keys()
+            .map(|key| (*key, packages[&self.pin(*key)].clone()))
+            .collect())
+    }
+
+    /// Gets the packages to resolve from the registries for the given document.
+    ///
+    /// Packages with local path overrides are not included. Compositions and
+    /// WIT packages in the dependencies directory are replaced by the
+    /// packages they use, transitively.
+    ///
+    /// Pinned versions are applied to the packages referenced by the document.
+    #[cfg(feature = "registry")]
+    pub fn registry_packages(
+        &self,
+        document: &Document,
+    ) -> Result<IndexMap<OwnedPackageKey, SourceSpan>, Error> {
+        let keys = packages(document)?
+            .into_iter()
+            .filter(|(key, _)| key.version.is_some() || !self.overrides.contains(key.name))
+            .map(|(key, span)| (self.pin(key), span))
+            .collect();
+
+        Ok(self.fs.binary_packages(&keys))
+    }
+
+    /// Resolves the given packages from the registries.
+    ///
+    /// The packages are typically those returned by
+    /// [`DocumentResolver::registry_packages`].
+    #[cfg(feature = "registry")]
+    pub async fn resolve_from_registries<'a>(
+        &self,
+        keys: &'a IndexMap<OwnedPackageKey, SourceSpan>,
+    ) -> Result<IndexMap<PackageKey<'a>, ResolvedPackage>, Error> {
+        let keys = keys
+            .iter()
+            .map(|((name, version, requirement), span)| {
+                (
+                    PackageKey {
+                        name,
+                        version: version.as_ref(),
+                        requirement: requirement.as_ref(),
+                    },
+                    *span,
+                )
+            })
+            .collect();
+
+        let packages = self.registries.resolve(&keys).await?;
+        self.registries.prune_lock_file()?;
+        Ok(packages)
+    }
+
+    /// Gets the path in the dependencies directory for the given package.
+    pub fn local_path(&self, key: &PackageKey) -> PathBuf {
+        self.fs.package_path(key)
+    }
+
+    /// Gets the chain of resolvers used to resolve packages that are not cached.
+    ///
+    /// Packages are resolved from the file system first; the registries
+    /// resolve the remaining packages and error on any missing package.
+    ///
+    /// The packages of compositions and the foreign dependencies of WIT
+    /// packages on the file system are also resolved from the registries if
+    /// they are not found locally.
+    fn chain(&self) -> ChainResolver {
+        #[cfg(feature = "registry")]
+        let chain = ChainResolver::new().with(
+            FileSystemPackageResolver::clone(&self.fs)
+                .with_dependency_resolver(self.registries.clone()),
+        );
+
+        #[cfg(not(feature = "registry"))]
+        let chain = ChainResolver::new().with(self.fs.clone());
+
+        #[cfg(feature = "registry")]
+        let chain = chain.with(self.registries.clone());
+
+        chain
+    }
+
+    /// Applies any pinned version to the given package key.
+    ///
+    /// Pinned versions only apply to packages referenced without a version
+    /// or version requirement.
+    fn pin<'b>(&'b self, key: PackageKey<'b>) -> PackageKey<'b> {
+        if key.version.is_some() || key.requirement.is_some() {
+            return key;
+        }
+
+        PackageKey {
+            name: key.name,
+            version: self.versions.get(key.name),
+            requirement: None,
+        }
+    }
+}
+
+/// The key of a package in the cache of a [`DocumentResolver`].
+type CacheKey = (String, Option<Version>, Option<VersionReq>);
+
+/// Gets the cache key of the given package key.
+fn cache_key(key: &PackageKey) -> CacheKey {
+    (
+        key.name.to_string(),
+        key.version.cloned(),
+        key.requirement.cloned(),
+    )
+}
+
+/// Used to resolve packages from the registries configured for a
+/// [`DocumentResolver`].
+///
+/// Packages are grouped by registry and each registry is resolved in turn.
+#[cfg(feature = "registry")]
+#[derive(Clone)]
+struct RegistriesResolver {
+    /// The URL of the default registry.
+    default: Option<String>,
+    /// The map of namespace or package name to registry URL.
+    registries: HashMap<String, String>,
+    /// The path to the lock file and whether it must be up to date.
+    lock: Option<(PathBuf, bool)>,
+    /// Whether the registries are resolved without network access.
+    offline: bool,
+    /// The packages requested from the registries, keyed by name and the
+    /// version requirement recorded in the lock file.
+    ///
+    /// This is shared by the clones used to resolve the packages of nested
+    /// compositions and foreign dependencies.
+    requested: Arc<Mutex<HashSet<(String, Option<VersionReq>)>>>,
+}
+
+#[cfg(feature = "registry")]
+impl RegistriesResolver {
+    /// Gets the URL of the registry to use for the given package.
+    ///
+    /// Returns `None` if the default registry should be used.
+    fn registry_for(&self, name: &str) -> Option<&str> {
+        let namespace = name.split(':').next().unwrap_or(name);
+        self.registries
+            .get(name)
+            .or_else(|| self.registries.get(namespace))
+            .map(String::as_str)
+            .or(self.default.as_deref())
+    }
+
+    /// Removes the packages that have not been requested from the lock file.
+    ///
+    /// This is done once all the packages of a document, including those of
+    /// nested compositions, have been resolved; the lock file is not changed
+    /// if it must be up to date.
+    fn prune_lock_file(&self) -> Result<(), Error> {
+        let path = match &self.lock {
+            Some((path, false)) => path,
+            _ => return Ok(()),
+        };
+
+        let mut lock =
+            wac_resolver::LockFile::open(path).map_err(|e| Error::LockFileWriteFailure {
+                path: path.clone(),
+                source: e,
+            })?;
+
+        let original = lock.clone();
+        let requested = self.requested.lock().unwrap();
+        lock.retain(|p| requested.contains(&(p.name.clone(), p.requirement.clone())));
+        if lock == original {
+            return Ok(());
+        }
+
+        log::debug!("pruning lock file `{path}`", path = path.display());
+        lock.write(path).map_err(|e| Error::LockFileWriteFailure {
+            path: path.clone(),
+            source: e,
+        })
+    }
+}
+
+#[cfg(feature = "registry")]
+#[async_trait::async_trait]
+impl wac_resolver::PackageResolver for RegistriesResolver {
+    async fn resolve<'a>(
+        &self,
+        keys: &IndexMap<PackageKey<'a>, SourceSpan>,
+    ) -> Result<IndexMap<PackageKey<'a>, ResolvedPackage>, Error> {
+        self.requested
+            .lock()
+            .unwrap()
+            .extend(keys.keys().map(|key| {
+                (
+                    key.name.to_string(),
+                    wac_resolver::LockedPackage::requirement_for(key),
+                )
+            }));
+
+        let mut registries: IndexMap<Option<&str>, IndexMap<PackageKey, SourceSpan>> =
+            IndexMap::new();
+        for (key, span) in keys {
+            registries
+                .entry(self.registry_for(key.name))
+                .or_default()
+                .insert(*key, *span);
+        }
+
+        // Registry resolvers are created one at a time as each locks its storage
+        let mut packages = IndexMap::new();
+        for (url, keys) in registries {
+            let mut resolver = wac_resolver::RegistryPackageResolver::new(
+                url,
+                Some(Box::new(progress::ProgressBar::new())),
+            )
+            .map_err(|e| Error::RegistryClientFailure { source: e })?
+            .with_offline(self.offline);
+
+            if let Some((path, locked)) = &self.lock {
+                resolver = resolver
+                    .with_lock_file(path, *locked)
+                    .map_err(|e| Error::RegistryClientFailure { source: e })?;
+            }
+
+            packages.extend(resolver.resolve(&keys).await?);
+        }
+
         Ok(packages)
     }
 }
+
+#[cfg(all(test, feature = "registry"))]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn it_prunes_packages_that_were_not_requested() -> Result<()> {
+        use wac_resolver::{LockFile, LockedPackage};
+
+        let dir = tempdir::TempDir::new("test")?;
+        let path = dir.path().join(LockFile::FILE_NAME);
+
+        let mut lock = LockFile::default();
+        for (name, requirement) in [
+            ("test:a", None),
+            ("test:a", Some("=1.0.0")),
+            ("test:b", None),
+        ] {
+            lock.insert(LockedPackage {
+                name: name.to_string(),
+                requirement: requirement.map(VersionReq::parse).transpose()?,
+                version: Version::new(1, 0, 0),
+                registry: None,
+                hash: "sha256:0000".to_string(),
+            });
+        }
+        lock.write(&path)?;
+
+        let requested = |resolver: &DocumentResolver| {
+            let version = Version::new(1, 0, 0);
+            resolver.registries.requested.lock().unwrap().extend(
+                [
+                    PackageKey {
+                        name: "test:a",
+                        version: Some(&version),
+                        requirement: None,
+                    },
+                    PackageKey {
+                        name: "test:b",
+                        version: None,
+                        requirement: None,
+                    },
+                ]
+                .iter()
+                .map(|key| (key.name.to_string(), LockedPackage::requirement_for(key))),
+            );
+        };
+
+        // A lock file that must be up to date is never pruned
+        let resolver = DocumentResolver::new(dir.path(), Default::default(), None)?
+            .with_lock_file(&path, true)?;
+        requested(&resolver);
+        resolver.registries.prune_lock_file()?;
+        assert_eq!(LockFile::open(&path)?, lock);
+
+        let resolver = DocumentResolver::new(dir.path(), Default::default(), None)?
+            .with_lock_file(&path, false)?;
+        requested(&resolver);
+        resolver.registries.prune_lock_file()?;
+        assert_eq!(
+            LockFile::open(&path)?
+                .packages()
+                .iter()
+                .map(|p| (
+                    p.name.as_str(),
+                    p.requirement.as_ref().map(ToString::to_string)
+                ))
+                .collect::<Vec<_>>(),
+            [("test:a", Some("=1.0.0".to_string())), ("test:b", None)]
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,337 @@
+use anyhow::{bail, Context, Result};
+use indexmap::IndexMap;
+use semver::Version;
+use serde::{
+    de::{self, MapAccess, Visitor},
+    Deserialize, Deserializer,
+};
+use std::{
+    collections::HashMap,
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+use wac_parser::EncodingOptions;
+
+/// Represents a package dependency declared in a manifest.
+#[derive(Debug, Clone)]
+pub enum Dependency {
+    /// The dependency is pinned to a version.
+    Version(Version),
+    /// The dependency is described with a table.
+    Detailed(DependencyDetails),
+}
+
+impl<'de> Deserialize<'de> for Dependency {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        // The form of the dependency is determined by its type so that the
+        // errors of the version string or table are reported as is
+        struct DependencyVisitor;
+
+        impl<'de> Visitor<'de> for DependencyVisitor {
+            type Value = Dependency;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "a version string or a dependency table")
+            }
+
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+                v.parse()
+                    .map(Dependency::Version)
+                    .map_err(|e| E::custom(format!("`{v}` is not a valid semantic version: {e}")))
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<Self::Value, A::Error> {
+                DependencyDetails::deserialize(de::value::MapAccessDeserializer::new(map))
+                    .map(Dependency::Detailed)
+            }
+        }
+
+        deserializer.deserialize_any(DependencyVisitor)
+    }
+}
+
+/// Represents the details of a package dependency declared in a manifest.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct DependencyDetails {
+    /// The local path of the package, relative to the manifest.
+    pub path: Option<PathBuf>,
+    /// The version to pin the package to.
+    ///
+    /// The pinned version is used when the package is referenced without a version.
+    pub version: Option<Version>,
+    /// The URL of the registry to resolve the package from.
+    pub registry: Option<String>,
+}
+
+/// Represents the default encoding options declared in a manifest.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct EncodeConfig {
+    /// Whether or not to define referenced packages in the encoded component.
+    pub define: Option<bool>,
+    /// Whether or not to validate the encoded component.
+    pub validate: Option<bool>,
+}
+
+/// Represents a `wac.toml` manifest.
+///
+/// A manifest declares the dependency configuration and default encoding
+/// options for the compositions in its directory and any subdirectories.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Manifest {
+    /// The directory containing the manifest.
+    #[serde(skip)]
+    dir: PathBuf,
+    /// The directory to search for package dependencies, relative to the manifest.
+    pub deps_dir: Option<PathBuf>,
+    /// The URL of the default registry.
+    pub registry: Option<String>,
+    /// A map of package namespace to the URL of the registry for the namespace.
+    #[serde(default)]
+    pub registries: HashMap<String, String>,
+    /// The package dependencies.
+    #[serde(default)]
+    pub dependencies: IndexMap<String, Dependency>,
+    /// The default encoding options.
+    #[serde(default)]
+    pub encode: EncodeConfig,
+}
+
+impl Manifest {
+    /// The file name of a manifest.
+    pub const FILE_NAME: &'static str = "wac.toml";
+
+    /// Finds the manifest for the composition at the given path.
+    ///
+    /// The directory of the composition and each of its ancestors are
+    /// searched for a `wac.toml` file.
+    ///
+    /// Returns `Ok(None)` if no manifest was found.
+    pub fn find(path: &Path) -> Result<Option<Self>> {
+        let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+
+        for dir in path.ancestors().skip(1) {
+            let manifest = dir.join(Self::FILE_NAME);
+            if manifest.is_file() {
+                log::debug!("found manifest `{path}`", path = manifest.display());
+                return Self::load(&manifest).map(Some);
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Loads the manifest at the given path.
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("failed to read manifest `{path}`", path = path.display()))?;
+
+        Self::parse(&contents, path)
+    }
+
+    /// Parses the contents of the manifest at the given path.
+    fn parse(contents: &str, path: &Path) -> Result<Self> {
+        let mut manifest: Self = toml::from_str(contents)
+            .with_context(|| format!("failed to parse manifest `{path}`", path = path.display()))?;
+
+        for (name, dependency) in &manifest.dependencies {
+            if let Dependency::Detailed(DependencyDetails {
+                path: Some(_),
+                version: Some(_),
+                ..
+            }) = dependency
+            {
+                bail!(
+                    "dependency `{name}` in manifest `{path}` cannot specify both a path and a version",
+                    path = path.display()
+                );
+            }
+        }
+
+        manifest.dir = path.parent().unwrap_or(Path::new(".")).to_path_buf();
+        Ok(manifest)
+    }
+
+    /// Gets the directory containing the manifest.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Gets the directory to search for package dependencies.
+    ///
+    /// Defaults to `deps` in the directory containing the manifest.
+    pub fn deps_dir(&self) -> PathBuf {
+        self.dir
+            .join(self.deps_dir.as_deref().unwrap_or(Path::new("deps")))
+    }
+
+    /// Gets the local path overrides of the dependencies.
+    pub fn overrides(&self) -> HashMap<String, PathBuf> {
+        self.dependencies
+            .iter()
+            .filter_map(|(name, dep)| match dep {
+                Dependency::Detailed(DependencyDetails {
+                    path: Some(path), ..
+                }) => Some((name.clone(), self.dir.join(path))),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Gets the pinned versions of the dependencies.
+    pub fn versions(&self) -> HashMap<String, Version> {
+        self.dependencies
+            .iter()
+            .filter_map(|(name, dep)| match dep {
+                Dependency::Version(version)
+                | Dependency::Detailed(DependencyDetails {
+                    version: Some(version),
+                    ..
+                }) => Some((name.clone(), version.clone())),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Gets the registries to use for specific namespaces and packages.
+    ///
+    /// The map is keyed by either a namespace or a package name.
+    pub fn registries(&self) -> HashMap<String, String> {
+        let mut registries = self.registries.clone();
+        for (name, dep) in &self.dependencies {
+            if let Dependency::Detailed(DependencyDetails {
+                registry: Some(registry),
+                ..
+            }) = dep
+            {
+                registries.insert(name.clone(), registry.clone());
+            }
+        }
+
+        registries
+    }
+
+    /// Gets the default encoding options.
+    pub fn encoding_options(&self) -> EncodingOptions {
+        EncodingOptions {
+            define_packages: self.encode.define.unwrap_or(false),
+        }
+    }
+
+    /// Determines if encoded components should be validated by default.
+    pub fn validate(&self) -> bool {
+        self.encode.validate.unwrap_or(true)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn it_parses_manifests() {
+        let manifest = Manifest::parse(
+            r#"deps-dir = "vendor"
+registry = "https://example.com"
+
+[registries]
+wasi = "https://wasi.example.com"
+
+[dependencies]
+"foo:bar" = "1.2.3"
+"foo:baz" = { path = "baz.wasm" }
+"foo:qux" = { version = "0.1.0", registry = "https://qux.example.com" }
+
+[encode]
+define = true
+validate = false
+"#,
+            Path::new("/project/wac.toml"),
+        )
+        .unwrap();
+
+        assert_eq!(manifest.dir(), Path::new("/project"));
+        assert_eq!(manifest.deps_dir(), Path::new("/project/vendor"));
+        assert_eq!(manifest.registry.as_deref(), Some("https://example.com"));
+        assert_eq!(
+            manifest.overrides(),
+            HashMap::from([("foo:baz".to_string(), PathBuf::from("/project/baz.wasm"))])
+        );
+        assert_eq!(
+            manifest.versions(),
+            HashMap::from([
+                ("foo:bar".to_string(), Version::new(1, 2, 3)),
+                ("foo:qux".to_string(), Version::new(0, 1, 0)),
+            ])
+        );
+        assert_eq!(
+            manifest.registries(),
+            HashMap::from([
+                ("wasi".to_string(), "https://wasi.example.com".to_string()),
+                ("foo:qux".to_string(), "https://qux.example.com".to_string()),
+            ])
+        );
+        assert!(manifest.encoding_options().define_packages);
+        assert!(!manifest.validate());
+    }
+
+    #[test]
+    fn it_uses_defaults() {
+        let manifest = Manifest::parse("", Path::new("/project/wac.toml")).unwrap();
+
+        assert_eq!(manifest.deps_dir(), Path::new("/project/deps"));
+        assert_eq!(manifest.registry, None);
+        assert!(manifest.overrides().is_empty());
+        assert!(manifest.versions().is_empty());
+        assert!(manifest.registries().is_empty());
+        assert!(!manifest.encoding_options().define_packages);
+        assert!(manifest.validate());
+    }
+
+    #[test]
+    fn it_rejects_invalid_manifests() {
+        let path = Path::new("/project/wac.toml");
+
+        let e = Manifest::parse(
+            "[dependencies]\n\"foo:bar\" = { path = \"bar.wasm\", version = \"1.0.0\" }\n",
+            path,
+        )
+        .unwrap_err();
+        assert!(
+            e.to_string()
+                .contains("cannot specify both a path and a version"),
+            "unexpected error: {e}"
+        );
+
+        assert!(Manifest::parse("deps = \"vendor\"\n", path).is_err());
+        assert!(Manifest::parse("[encode]\noptimize = true\n", path).is_err());
+
+        let e = Manifest::parse("[dependencies]\n\"foo:bar\" = \"1\"\n", path).unwrap_err();
+        assert!(
+            format!("{e:#}").contains("`1` is not a valid semantic version"),
+            "unexpected error: {e:#}"
+        );
+
+        let e = Manifest::parse(
+            "[dependencies]\n\"foo:bar\" = { paht = \"bar.wasm\" }\n",
+            path,
+        )
+        .unwrap_err();
+        assert!(
+            format!("{e:#}").contains("unknown field `paht`"),
+            "unexpected error: {e:#}"
+        );
+
+        let e = Manifest::parse(
+            "[dependencies]\n\"foo:bar\" = { version = \"1.x\" }\n",
+            path,
+        )
+        .unwrap_err();
+        assert!(
+            format!("{e:#}").contains("while parsing minor version number"),
+            "unexpected error: {e:#}"
+        );
+    }
+}